@@ -1,17 +1,29 @@
 use std::fmt;
-use std::{collections::HashMap, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    vec,
+};
 
 use serde::Serialize;
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
-use crate::instructions::{IndexRegister, TargetDirection};
+use crate::encoder::encode_instruction;
+use crate::instructions::{
+    BitField, BitFieldValue, ControlRegister, FpControlRegister, FpFormat, FpOperand, FpOperation,
+    IndexBase, IndexRegister, MemoryIndirection, TargetDirection,
+};
+use crate::record_file::RecordResult;
+use crate::srecord::{write_srecords, SRecordFormat};
+use crate::symbols::{write_symbol_map, Symbol, SymbolKind};
 use crate::{
     instructions::{
         Condition, Instruction, Label, Operand, RegisterOperand, ShiftDirection, Sign, Size,
     },
-    lexer::{LexedLine, LexedOperand, LexedRegisterType, LexedSize, ParsedLine},
+    lexer::{
+        LexedLine, LexedOperand, LexedRegisterType, LexedSize, LexedSpecialRegister, ParsedLine,
+    },
     math::sign_extend_to_long,
-    utils::{parse_absolute_expression, parse_string_into_padded_bytes},
+    utils::{parse_absolute_expression, parse_float_expression, parse_string_into_padded_bytes},
 };
 
 #[derive(Debug)]
@@ -26,10 +38,18 @@ pub enum Directive {
 pub struct Compiler {
     labels: HashMap<String, Label>,
     line_addresses: Vec<usize>,
+    //bytes taken by each line, the instructions are sized by encoding them
+    line_sizes: Vec<usize>,
     directives: Vec<Directive>,
+    //the EQU constants, they are replaced in the code by the lexer
+    constants: Vec<Symbol>,
+    //the labels whose next line is an instruction
+    code_labels: HashSet<String>,
     instructions: Vec<InstructionLine>,
     start_address: usize,
     final_instrucion_address: usize,
+    //address of the instruction being parsed, used to resolve the pc relative operands
+    current_address: usize,
 }
 
 #[derive(Clone, Serialize)]
@@ -37,6 +57,46 @@ pub struct InstructionLine {
     pub instruction: Instruction,
     pub address: usize,
     pub parsed_line: ParsedLine,
+    //the machine code, the opcode word followed by the extension words
+    pub words: Vec<u16>,
+}
+
+impl InstructionLine {
+    pub fn get_size(&self) -> usize {
+        self.words.len() * 2
+    }
+    pub fn get_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+}
+
+//a contiguous block of the assembled program, the ORG directives can split it in multiple segments
+#[derive(Debug, Clone, Serialize)]
+pub struct MemorySegment {
+    pub address: usize,
+    pub data: Vec<u8>,
+}
+
+impl MemorySegment {
+    /**
+    Sorts the blocks by address and merges the contiguous ones, the empty blocks are skipped
+     */
+    pub fn merge(mut blocks: Vec<(usize, Vec<u8>)>) -> Vec<MemorySegment> {
+        blocks.sort_by_key(|(address, _)| *address);
+        let mut segments: Vec<MemorySegment> = Vec::new();
+        for (address, data) in blocks {
+            if data.is_empty() {
+                continue;
+            }
+            match segments.last_mut() {
+                Some(last) if last.address + last.data.len() == address => {
+                    last.data.extend_from_slice(&data)
+                }
+                _ => segments.push(MemorySegment { address, data }),
+            }
+        }
+        segments
+    }
 }
 
 #[derive(Debug)]
@@ -59,29 +119,58 @@ impl CompilationError {
 }
 
 pub type CompilationResult<T> = Result<T, CompilationError>;
+//the address and size of each line, with the labels
+type LineLayout = (Vec<usize>, Vec<usize>, HashMap<String, Label>);
 
 impl fmt::Debug for InstructionLine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("InstructionLine")
             .field("instruction", &self.instruction)
             .field("address", &self.address)
+            .field("words", &self.words)
             //.field("parsed_line", &self.parsed_line)
             .finish()
     }
 }
 
+#[wasm_bindgen]
+impl Compiler {
+    pub fn wasm_get_memory_segments(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.get_memory_segments()).unwrap()
+    }
+    pub fn wasm_get_symbols(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.get_symbols()).unwrap()
+    }
+    pub fn wasm_get_symbol_map(&self) -> String {
+        write_symbol_map(&self.get_symbols())
+    }
+    pub fn wasm_to_srecords(&self, format: SRecordFormat) -> Result<String, JsValue> {
+        match self.to_srecords(format) {
+            Ok(text) => Ok(text),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+}
+
 impl Compiler {
     pub fn new(lines: &[ParsedLine]) -> Result<Compiler, String> {
-        let mut pre_interpreter = Compiler {
+        let mut pre_interpreter = Compiler::empty();
+        pre_interpreter.load(lines)?;
+        Ok(pre_interpreter)
+    }
+    fn empty() -> Compiler {
+        Compiler {
             labels: HashMap::new(),
             line_addresses: Vec::new(),
+            line_sizes: Vec::new(),
             directives: Vec::new(),
+            constants: Vec::new(),
+            code_labels: HashSet::new(),
             instructions: Vec::new(),
             start_address: 0,
             final_instrucion_address: 0,
-        };
-        pre_interpreter.load(lines)?;
-        Ok(pre_interpreter)
+            current_address: 0,
+        }
     }
 
     pub fn debug_print(&self) {
@@ -117,6 +206,65 @@ impl Compiler {
     pub fn get_directives(&self) -> &Vec<Directive> {
         &self.directives
     }
+    /**
+    The labels and the EQU constants sorted by name, the labels followed by an instruction are code
+    and the others are data, even when they share the address of the next instruction
+     */
+    pub fn get_symbols(&self) -> Vec<Symbol> {
+        let mut symbols: Vec<Symbol> = self
+            .labels
+            .values()
+            .map(|label| Symbol {
+                name: label.name.clone(),
+                value: Some(label.address),
+                expression: None,
+                line: label.line,
+                kind: if self.code_labels.contains(&label.name) {
+                    SymbolKind::Code
+                } else {
+                    SymbolKind::Data
+                },
+            })
+            .chain(self.constants.iter().cloned())
+            .collect();
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        symbols
+    }
+    pub fn get_line_address(&self, index: usize) -> usize {
+        self.line_addresses[index]
+    }
+    /**
+    The assembled bytes of the instructions and of the data directives, sorted by address and
+    merged when they are contiguous
+     */
+    pub fn get_memory_segments(&self) -> Vec<MemorySegment> {
+        let mut blocks: Vec<(usize, Vec<u8>)> = self
+            .instructions
+            .iter()
+            .map(|ins| (ins.address, ins.get_bytes()))
+            .collect();
+        for directive in &self.directives {
+            match directive {
+                Directive::DC { data, address }
+                | Directive::DS { data, address }
+                | Directive::DCB { data, address } => blocks.push((*address, data.clone())),
+                Directive::Other => {}
+            }
+        }
+        MemorySegment::merge(blocks)
+    }
+    /**
+    The assembled program as S-records, the ORG regions are written at their address and START
+    is the entry point of the termination record
+     */
+    pub fn to_srecords(&self, format: SRecordFormat) -> RecordResult<String> {
+        write_srecords(
+            &self.get_memory_segments(),
+            self.start_address,
+            format,
+            "s68k",
+        )
+    }
     fn load(&mut self, lines: &[ParsedLine]) -> Result<(), String> {
         self.parse_labels_and_addresses(lines)?; //has side effect, place before the parsing
         self.parse_instruction_lines(lines)?;
@@ -144,60 +292,486 @@ impl Compiler {
 
     fn parse_instruction_lines(&mut self, lines: &[ParsedLine]) -> Result<(), String> {
         for (i, line) in lines.iter().enumerate() {
-            match &line.parsed {
-                LexedLine::Instruction {
-                    name,
-                    operands,
-                    size,
-                } => {
-                    let parsed_operands = operands
-                        .iter()
-                        .map(|x| self.parse_operand(x, line))
-                        .collect::<CompilationResult<Vec<Operand>>>();
-                    match parsed_operands {
-                        Ok(ops) => {
-                            let instruction = self.parse_instruction(name, ops, size);
-                            match instruction {
-                                Ok(ins) => {
-                                    let address = self.line_addresses[i];
-                                    if address & 0x1 != 0 {
-                                        return Err(format!(
-                                            "Instruction address must not be odd, maybe you defined an odd number of byte constants in memory somewhere? found {} at line {}",
-                                            address, line.line_index
-                                        ));
-                                    }
-                                    let instuction_line = InstructionLine {
-                                        instruction: ins,
-                                        address: self.line_addresses[i],
-                                        parsed_line: line.clone(),
-                                    };
-                                    self.instructions.push(instuction_line);
-                                }
-                                Err(e) => {
-                                    return Err(format!(
-                                        "{}; at line {}",
-                                        e.get_message(),
-                                        line.line_index
-                                    )
-                                    .to_string());
-                                }
+            if let LexedLine::Instruction { size, .. } = &line.parsed {
+                let address = self.line_addresses[i];
+                let reserved = self.line_sizes[i];
+                let compiled = self.compile_instruction(line, address).and_then(|ins| {
+                    let mut words = encode_instruction(&ins, address, size)?;
+                    //the sizes only grow while the addresses are assigned, so a branch can fit a shorter
+                    //form than the space it was given, it is encoded with the larger displacement instead
+                    if words.len() * 2 < reserved {
+                        let larger = match ins {
+                            Instruction::BRA(_) | Instruction::BSR(_) | Instruction::Bcc(..) => {
+                                Some(LexedSize::Word)
                             }
+                            Instruction::FBcc(..) => Some(LexedSize::Long),
+                            _ => None,
+                        };
+                        if let Some(larger) = larger {
+                            words = encode_instruction(&ins, address, &larger)?;
                         }
-                        Err(e) => {
+                    }
+                    Ok((ins, words))
+                });
+                match compiled {
+                    Ok((ins, mut words)) => {
+                        if address & 0x1 != 0 {
                             return Err(format!(
-                                "{}; at line {}",
-                                e.get_message(),
-                                line.line_index
-                            )
-                            .to_string());
+                                "Instruction address must not be odd, maybe you defined an odd number of byte constants in memory somewhere? found {} at line {}",
+                                address, line.line_index
+                            ));
+                        }
+                        //the other instructions are padded if they ever end up shorter
+                        while words.len() * 2 < reserved {
+                            words.push(0x4E71);
                         }
+                        let instuction_line = InstructionLine {
+                            instruction: ins,
+                            address,
+                            parsed_line: line.clone(),
+                            words,
+                        };
+                        self.instructions.push(instuction_line);
+                    }
+                    Err(e) => {
+                        return Err(
+                            format!("{}; at line {}", e.get_message(), line.line_index).to_string()
+                        );
                     }
                 }
-                _ => {}
             }
         }
         Ok(())
     }
+    fn compile_instruction(
+        &mut self,
+        line: &ParsedLine,
+        address: usize,
+    ) -> CompilationResult<Instruction> {
+        let (name, operands, size) = match &line.parsed {
+            LexedLine::Instruction {
+                name,
+                operands,
+                size,
+            } => (name, operands, size),
+            _ => {
+                return Err(CompilationError::Raw(
+                    "Line is not an instruction".to_string(),
+                ))
+            }
+        };
+        self.current_address = address;
+        //the instructions with operands that are not addressing modes are parsed first
+        let instruction = match self.parse_floating_point_instruction(name, operands, size, line) {
+            Ok(None) => self.parse_special_register_instruction(name, operands, line),
+            result => result,
+        };
+        let instruction = match instruction {
+            Ok(None) => self.parse_long_arithmetic_instruction(name, operands, size, line),
+            result => result,
+        };
+        let instruction = match instruction {
+            Ok(None) => self.parse_bit_field_instruction(name, operands, line),
+            result => result,
+        };
+        match instruction {
+            Ok(Some(ins)) => Ok(ins),
+            Ok(None) => operands
+                .iter()
+                .map(|x| self.parse_operand(x, line))
+                .collect::<CompilationResult<Vec<Operand>>>()
+                .and_then(|ops| self.parse_instruction(name, ops, size)),
+            Err(e) => Err(e),
+        }
+    }
+    /**
+    The floating point instructions use the FPn and FPU control registers, which are not addressing modes,
+    and their immediates can be floats, returns None if the line is not one of them
+     */
+    fn parse_floating_point_instruction(
+        &mut self,
+        name: &str,
+        operands: &[LexedOperand],
+        size: &LexedSize,
+        line: &ParsedLine,
+    ) -> CompilationResult<Option<Instruction>> {
+        if !name.starts_with('f') {
+            return Ok(None);
+        }
+        let parsed = match (name, operands) {
+            ("fmove", [LexedOperand::SpecialRegister(register), dest]) => {
+                let register = self.parse_fp_control_register(register)?;
+                Instruction::FMOVEfromCR(register, self.parse_operand(dest, line)?)
+            }
+            ("fmove", [src, LexedOperand::SpecialRegister(register)]) => {
+                let register = self.parse_fp_control_register(register)?;
+                Instruction::FMOVEtoCR(self.parse_operand(src, line)?, register)
+            }
+            ("fmove", [src, dest]) => {
+                let format = self.get_fp_format(size)?;
+                let src = self.parse_fp_operand(src, format, line)?;
+                let dest = self.parse_fp_operand(dest, format, line)?;
+                Instruction::FMOVE(src, dest, format)
+            }
+            ("fmovecr", [offset, dest]) => {
+                let offset = self.parse_operand(offset, line)?;
+                let offset = self.extract_immediate(&offset)? as u8;
+                Instruction::FMOVECR(offset, self.parse_fp_register(dest)?)
+            }
+            ("fcmp", [src, dest]) => {
+                let format = self.get_fp_format(size)?;
+                let src = self.parse_fp_operand(src, format, line)?;
+                Instruction::FCMP(src, self.parse_fp_register(dest)?, format)
+            }
+            ("ftst", [src]) => {
+                let format = self.get_fp_format(size)?;
+                Instruction::FTST(self.parse_fp_operand(src, format, line)?, format)
+            }
+            ("fnop", []) => Instruction::FNOP,
+            _ if name.parse::<FpOperation>().is_ok() => {
+                let operation = name.parse::<FpOperation>().unwrap();
+                let format = self.get_fp_format(size)?;
+                match operands {
+                    [src, dest] => {
+                        let src = self.parse_fp_operand(src, format, line)?;
+                        Instruction::FArith(src, self.parse_fp_register(dest)?, operation, format)
+                    }
+                    //the monadic operations can use the same register as source and destination
+                    [dest] if !operation.is_dyadic() => {
+                        let dest = self.parse_fp_register(dest)?;
+                        Instruction::FArith(FpOperand::Register(dest), dest, operation, format)
+                    }
+                    _ => {
+                        return Err(CompilationError::InvalidAddressingMode(format!(
+                            "Invalid operands for \"{}\"",
+                            name
+                        )))
+                    }
+                }
+            }
+            (_, [target]) if name.starts_with("fb") => match name[2..].parse() {
+                Ok(condition) => {
+                    let target = self.parse_operand(target, line)?;
+                    Instruction::FBcc(self.extract_address(&target)?, condition)
+                }
+                Err(_) => return Ok(None),
+            },
+            (_, [reg, target]) if name.starts_with("fdb") => match name[3..].parse() {
+                Ok(condition) => {
+                    let reg = self.parse_operand(reg, line)?;
+                    let target = self.parse_operand(target, line)?;
+                    Instruction::FDBcc(
+                        self.extract_register(reg)?,
+                        self.extract_address(&target)?,
+                        condition,
+                    )
+                }
+                Err(_) => return Ok(None),
+            },
+            (_, [dest]) if name.starts_with("fs") => match name[2..].parse() {
+                Ok(condition) => Instruction::FScc(self.parse_operand(dest, line)?, condition),
+                Err(_) => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(parsed))
+    }
+    fn get_fp_format(&self, size: &LexedSize) -> CompilationResult<FpFormat> {
+        match size {
+            LexedSize::Byte => Ok(FpFormat::Byte),
+            LexedSize::Word => Ok(FpFormat::Word),
+            LexedSize::Long => Ok(FpFormat::Long),
+            LexedSize::Short => Ok(FpFormat::Single),
+            LexedSize::Double => Ok(FpFormat::Double),
+            LexedSize::Extended | LexedSize::Unspecified => Ok(FpFormat::Extended),
+            _ => Err(CompilationError::ParseError(format!(
+                "Invalid floating point format: {:?}",
+                size
+            ))),
+        }
+    }
+    fn parse_fp_control_register(
+        &self,
+        register: &LexedSpecialRegister,
+    ) -> CompilationResult<FpControlRegister> {
+        match register {
+            LexedSpecialRegister::FPCR => Ok(FpControlRegister::FPCR),
+            LexedSpecialRegister::FPSR => Ok(FpControlRegister::FPSR),
+            LexedSpecialRegister::FPIAR => Ok(FpControlRegister::FPIAR),
+            _ => Err(CompilationError::InvalidAddressingMode(format!(
+                "Special register {:?} not allowed in floating point instructions",
+                register
+            ))),
+        }
+    }
+    fn parse_control_register(
+        &self,
+        register: &LexedSpecialRegister,
+    ) -> CompilationResult<ControlRegister> {
+        match register {
+            LexedSpecialRegister::SFC => Ok(ControlRegister::SFC),
+            LexedSpecialRegister::DFC => Ok(ControlRegister::DFC),
+            LexedSpecialRegister::USP => Ok(ControlRegister::USP),
+            LexedSpecialRegister::VBR => Ok(ControlRegister::VBR),
+            _ => Err(CompilationError::InvalidAddressingMode(format!(
+                "Special register {:?} is not a control register",
+                register
+            ))),
+        }
+    }
+    fn parse_fp_register(&self, operand: &LexedOperand) -> CompilationResult<u8> {
+        match operand {
+            LexedOperand::FloatRegister(name) => match name[2..].parse() {
+                Ok(register) => Ok(register),
+                Err(_) => Err(CompilationError::ParseError(format!(
+                    "Invalid fp register name: {}",
+                    name
+                ))),
+            },
+            _ => Err(CompilationError::InvalidAddressingMode(
+                "Operand is not a floating point register".to_string(),
+            )),
+        }
+    }
+    fn parse_fp_operand(
+        &mut self,
+        operand: &LexedOperand,
+        format: FpFormat,
+        line: &ParsedLine,
+    ) -> CompilationResult<FpOperand> {
+        match operand {
+            LexedOperand::FloatRegister(_) => {
+                Ok(FpOperand::Register(self.parse_fp_register(operand)?))
+            }
+            LexedOperand::Immediate(value) => {
+                match parse_float_expression(&value[1..], format, &self.labels) {
+                    Ok(value) => Ok(FpOperand::Immediate(value)),
+                    Err(e) => Err(CompilationError::ParseError(format!(
+                        "Invalid immediate: {}",
+                        e
+                    ))),
+                }
+            }
+            _ => Ok(FpOperand::Operand(self.parse_operand(operand, line)?)),
+        }
+    }
+    /**
+    The special registers (SR, CCR, USP) are not addressing modes, so the instructions that use them
+    are parsed before the operands are converted, returns None if the line does not use any
+     */
+    fn parse_special_register_instruction(
+        &mut self,
+        name: &str,
+        operands: &[LexedOperand],
+        line: &ParsedLine,
+    ) -> CompilationResult<Option<Instruction>> {
+        let parsed = match (name, operands) {
+            ("move", [src, LexedOperand::SpecialRegister(LexedSpecialRegister::SR)]) => {
+                Instruction::MOVEtoSR(self.parse_operand(src, line)?)
+            }
+            ("move", [LexedOperand::SpecialRegister(LexedSpecialRegister::SR), dest]) => {
+                Instruction::MOVEfromSR(self.parse_operand(dest, line)?)
+            }
+            ("move", [src, LexedOperand::SpecialRegister(LexedSpecialRegister::CCR)]) => {
+                Instruction::MOVEtoCCR(self.parse_operand(src, line)?)
+            }
+            ("move", [LexedOperand::SpecialRegister(LexedSpecialRegister::CCR), dest]) => {
+                Instruction::MOVEfromCCR(self.parse_operand(dest, line)?)
+            }
+            ("movec", [LexedOperand::SpecialRegister(register), dest]) => {
+                let register = self.parse_control_register(register)?;
+                let dest = self.parse_operand(dest, line)?;
+                Instruction::MOVECfromCR(register, self.extract_register(dest)?)
+            }
+            ("movec", [src, LexedOperand::SpecialRegister(register)]) => {
+                let register = self.parse_control_register(register)?;
+                let src = self.parse_operand(src, line)?;
+                Instruction::MOVECtoCR(self.extract_register(src)?, register)
+            }
+            ("move", [src, LexedOperand::SpecialRegister(LexedSpecialRegister::USP)]) => {
+                let src = self.parse_operand(src, line)?;
+                Instruction::MOVEtoUSP(self.extract_register(src)?)
+            }
+            ("move", [LexedOperand::SpecialRegister(LexedSpecialRegister::USP), dest]) => {
+                let dest = self.parse_operand(dest, line)?;
+                Instruction::MOVEfromUSP(self.extract_register(dest)?)
+            }
+            (
+                "andi" | "ori" | "eori",
+                [src, LexedOperand::SpecialRegister(LexedSpecialRegister::SR)],
+            ) => {
+                let src = self.parse_operand(src, line)?;
+                let value = self.extract_immediate(&src)? as u16;
+                match name {
+                    "andi" => Instruction::ANDItoSR(value),
+                    "ori" => Instruction::ORItoSR(value),
+                    _ => Instruction::EORItoSR(value),
+                }
+            }
+            (
+                "andi" | "ori" | "eori",
+                [src, LexedOperand::SpecialRegister(LexedSpecialRegister::CCR)],
+            ) => {
+                let src = self.parse_operand(src, line)?;
+                let value = self.extract_immediate(&src)? as u8;
+                match name {
+                    "andi" => Instruction::ANDItoCCR(value),
+                    "ori" => Instruction::ORItoCCR(value),
+                    _ => Instruction::EORItoCCR(value),
+                }
+            }
+            (_, ops)
+                if ops
+                    .iter()
+                    .any(|op| matches!(op, LexedOperand::SpecialRegister(_))) =>
+            {
+                return Err(CompilationError::InvalidAddressingMode(format!(
+                    "Special register not allowed in instruction \"{}\"",
+                    name
+                )));
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(parsed))
+    }
+    /**
+    The 68020 long multiply and divide can use a register pair (Dh:Dl or Dr:Dq) as destination,
+    so they are parsed before the operands are converted, returns None if the line is not one of them
+     */
+    fn parse_long_arithmetic_instruction(
+        &mut self,
+        name: &str,
+        operands: &[LexedOperand],
+        size: &LexedSize,
+        line: &ParsedLine,
+    ) -> CompilationResult<Option<Instruction>> {
+        let sign = match name {
+            "muls" | "divs" | "divsl" if *size == LexedSize::Long => Sign::Signed,
+            "mulu" | "divu" | "divul" if *size == LexedSize::Long => Sign::Unsigned,
+            "divsl" => Sign::Signed,
+            "divul" => Sign::Unsigned,
+            _ => return Ok(None),
+        };
+        let (src, dest) = match operands {
+            [src, dest] => (self.parse_operand(src, line)?, dest),
+            _ => {
+                return Err(CompilationError::InvalidAddressingMode(format!(
+                    "Invalid operands for \"{}\"",
+                    name
+                )))
+            }
+        };
+        let (high, low) = match dest {
+            LexedOperand::RegisterPair(high, low) => {
+                let high = self.parse_operand(high, line)?;
+                let low = self.parse_operand(low, line)?;
+                (
+                    Some(self.extract_register(high)?),
+                    self.extract_register(low)?,
+                )
+            }
+            _ => {
+                let dest = self.parse_operand(dest, line)?;
+                (None, self.extract_register(dest)?)
+            }
+        };
+        let parsed = match (name, high) {
+            ("muls" | "mulu", _) => Instruction::MULxL(src, low, high, sign),
+            ("divs" | "divu", Some(remainder)) => {
+                Instruction::DIVxL(src, low, remainder, true, sign)
+            }
+            (_, Some(remainder)) => Instruction::DIVxL(src, low, remainder, false, sign),
+            //when the remainder is the quotient register, only the quotient is kept
+            (_, None) => Instruction::DIVxL(src, low, low, false, sign),
+        };
+        Ok(Some(parsed))
+    }
+    /**
+    The bit fields ({offset:width}) are not addressing modes, so the bit field instructions are parsed
+    before the operands are converted, returns None if the line does not use any
+     */
+    fn parse_bit_field_instruction(
+        &mut self,
+        name: &str,
+        operands: &[LexedOperand],
+        line: &ParsedLine,
+    ) -> CompilationResult<Option<Instruction>> {
+        let parsed = match (name, operands) {
+            ("bftst" | "bfchg" | "bfclr" | "bfset", [field]) => {
+                let (op, field) = self.parse_bit_field(field, line)?;
+                match name {
+                    "bftst" => Instruction::BFTST(op, field),
+                    "bfchg" => Instruction::BFCHG(op, field),
+                    "bfclr" => Instruction::BFCLR(op, field),
+                    _ => Instruction::BFSET(op, field),
+                }
+            }
+            ("bfextu" | "bfexts" | "bfffo", [field, dest]) => {
+                let (op, field) = self.parse_bit_field(field, line)?;
+                let dest = self.parse_operand(dest, line)?;
+                let dest = self.extract_register(dest)?;
+                match name {
+                    "bfextu" => Instruction::BFEXTU(op, field, dest),
+                    "bfexts" => Instruction::BFEXTS(op, field, dest),
+                    _ => Instruction::BFFFO(op, field, dest),
+                }
+            }
+            ("bfins", [src, field]) => {
+                let src = self.parse_operand(src, line)?;
+                let src = self.extract_register(src)?;
+                let (op, field) = self.parse_bit_field(field, line)?;
+                Instruction::BFINS(src, op, field)
+            }
+            (_, ops)
+                if ops
+                    .iter()
+                    .any(|op| matches!(op, LexedOperand::BitField { .. })) =>
+            {
+                return Err(CompilationError::InvalidAddressingMode(format!(
+                    "Bit field not allowed in instruction \"{}\"",
+                    name
+                )));
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(parsed))
+    }
+    fn parse_bit_field(
+        &mut self,
+        operand: &LexedOperand,
+        line: &ParsedLine,
+    ) -> CompilationResult<(Operand, BitField)> {
+        match operand {
+            LexedOperand::BitField {
+                operand,
+                offset,
+                width,
+            } => {
+                let operand = self.parse_operand(operand, line)?;
+                let offset = self.parse_bit_field_value(offset, line)?;
+                let width = self.parse_bit_field_value(width, line)?;
+                Ok((operand, BitField { offset, width }))
+            }
+            _ => Err(CompilationError::InvalidAddressingMode(
+                "Operand is not a bit field".to_string(),
+            )),
+        }
+    }
+    fn parse_bit_field_value(
+        &mut self,
+        value: &LexedOperand,
+        line: &ParsedLine,
+    ) -> CompilationResult<BitFieldValue> {
+        match value {
+            LexedOperand::Absolute(num) | LexedOperand::Immediate(num) => Ok(
+                BitFieldValue::Immediate(self.parse_absolute(num.trim_start_matches('#'))? as u8),
+            ),
+            _ => {
+                let register = self.parse_operand(value, line)?;
+                Ok(BitFieldValue::Register(self.extract_register(register)?))
+            }
+        }
+    }
     fn parse_instruction(
         &self,
         name: &String,
@@ -272,6 +846,10 @@ impl Compiler {
                     ),
                     _ => Instruction::SUB(op1, op2, self.get_size(size, Size::Word)?),
                 },
+                "addx" => Instruction::ADDX(op1, op2, self.get_size(size, Size::Word)?),
+                "subx" => Instruction::SUBX(op1, op2, self.get_size(size, Size::Word)?),
+                "abcd" => Instruction::ABCD(op1, op2),
+                "sbcd" => Instruction::SBCD(op1, op2),
                 "cmp" => match (op1, op2) {
                     (_, Operand::Register(RegisterOperand::Address(a))) => Instruction::CMPA(
                         op1,
@@ -319,6 +897,49 @@ impl Compiler {
                 "muls" => Instruction::MULx(op1, self.extract_register(op2)?, Sign::Signed),
                 "mulu" => Instruction::MULx(op1, self.extract_register(op2)?, Sign::Unsigned),
                 "exg" => Instruction::EXG(self.extract_register(op1)?, self.extract_register(op2)?),
+                "movep" => match (op1, op2) {
+                    (Operand::Register(register), target) => Instruction::MOVEP(
+                        target,
+                        register,
+                        TargetDirection::ToMemory,
+                        self.get_size(size, Size::Word)?,
+                    ),
+                    (target, Operand::Register(register)) => Instruction::MOVEP(
+                        target,
+                        register,
+                        TargetDirection::FromMemory,
+                        self.get_size(size, Size::Word)?,
+                    ),
+                    _ => {
+                        return Err(CompilationError::InvalidAddressingMode(
+                            "Invalid operands for MOVEP".to_string(),
+                        ));
+                    }
+                },
+                "moves" => match (op1, op2) {
+                    (Operand::Register(register), target) => Instruction::MOVES(
+                        target,
+                        register,
+                        TargetDirection::ToMemory,
+                        self.get_size(size, Size::Word)?,
+                    ),
+                    (target, Operand::Register(register)) => Instruction::MOVES(
+                        target,
+                        register,
+                        TargetDirection::FromMemory,
+                        self.get_size(size, Size::Word)?,
+                    ),
+                    _ => {
+                        return Err(CompilationError::InvalidAddressingMode(
+                            "Invalid operands for MOVES".to_string(),
+                        ));
+                    }
+                },
+                "chk" => Instruction::CHK(
+                    op1,
+                    self.extract_register(op2)?,
+                    self.get_size(size, Size::Word)?,
+                ),
                 "or" => Instruction::OR(op1, op2, self.get_size(size, Size::Word)?),
                 "and" => Instruction::AND(op1, op2, self.get_size(size, Size::Word)?),
                 "eor" => Instruction::EOR(op1, op2, self.get_size(size, Size::Word)?),
@@ -393,6 +1014,18 @@ impl Compiler {
                     ShiftDirection::Left,
                     self.get_size(size, Size::Word)?,
                 ),
+                "roxl" => Instruction::ROXd(
+                    op1,
+                    op2,
+                    ShiftDirection::Left,
+                    self.get_size(size, Size::Word)?,
+                ),
+                "roxr" => Instruction::ROXd(
+                    op1,
+                    op2,
+                    ShiftDirection::Right,
+                    self.get_size(size, Size::Word)?,
+                ),
                 "lea" => Instruction::LEA(op1, self.extract_register(op2)?),
                 "ror" => Instruction::ROd(
                     op1,
@@ -441,6 +1074,8 @@ impl Compiler {
             let result = match name.as_str() {
                 "clr" => Instruction::CLR(op, self.get_size(size, Size::Word)?),
                 "neg" => Instruction::NEG(op, self.get_size(size, Size::Word)?),
+                "negx" => Instruction::NEGX(op, self.get_size(size, Size::Word)?),
+                "nbcd" => Instruction::NBCD(op),
                 "pea" => Instruction::PEA(op),
                 "ext" => Instruction::EXT(
                     self.extract_register(op)?,
@@ -503,11 +1138,39 @@ impl Compiler {
                         }
                     }
                 }
+                //memory form of the shifts, always word sized and by one bit
+                "asl" => {
+                    Instruction::ASd(Operand::Immediate(1), op, ShiftDirection::Left, Size::Word)
+                }
+                "asr" => {
+                    Instruction::ASd(Operand::Immediate(1), op, ShiftDirection::Right, Size::Word)
+                }
+                "lsl" => {
+                    Instruction::LSd(Operand::Immediate(1), op, ShiftDirection::Left, Size::Word)
+                }
+                "lsr" => {
+                    Instruction::LSd(Operand::Immediate(1), op, ShiftDirection::Right, Size::Word)
+                }
+                "rol" => {
+                    Instruction::ROd(Operand::Immediate(1), op, ShiftDirection::Left, Size::Word)
+                }
+                "ror" => {
+                    Instruction::ROd(Operand::Immediate(1), op, ShiftDirection::Right, Size::Word)
+                }
+                "roxl" => {
+                    Instruction::ROXd(Operand::Immediate(1), op, ShiftDirection::Left, Size::Word)
+                }
+                "roxr" => {
+                    Instruction::ROXd(Operand::Immediate(1), op, ShiftDirection::Right, Size::Word)
+                }
                 "swap" => Instruction::SWAP(self.extract_register(op)?),
                 //not sure if the default is word
                 "not" => Instruction::NOT(op, self.get_size(size, Size::Word)?),
+                "tas" => Instruction::TAS(op),
                 "jsr" => Instruction::JSR(op),
 
+                "stop" => Instruction::STOP(self.extract_immediate(&op)? as u16),
+                "rtd" => Instruction::RTD(self.extract_immediate(&op)? as i16),
                 "trap" => {
                     let value = self.extract_immediate(&op)? as i32;
                     if !(0..=15).contains(&value) {
@@ -529,6 +1192,11 @@ impl Compiler {
         } else if operands.is_empty() {
             let result = match name.as_str() {
                 "rts" => Instruction::RTS,
+                "rtr" => Instruction::RTR,
+                "rte" => Instruction::RTE,
+                "trapv" => Instruction::TRAPV,
+                "illegal" => Instruction::ILLEGAL,
+                "reset" => Instruction::RESET,
                 "nop" => Instruction::NOP,
                 _ => {
                     return Err(CompilationError::Raw(format!(
@@ -576,22 +1244,28 @@ impl Compiler {
     }
     fn extract_address(&self, operand: &Operand) -> CompilationResult<u32> {
         match operand {
-            Operand::Absolute(addr) => Ok(*addr as u32),
+            Operand::Absolute(addr)
+            | Operand::AbsoluteShort(addr)
+            | Operand::AbsoluteLong(addr) => Ok(*addr as u32),
             _ => Err(CompilationError::InvalidAddressingMode(
                 "Operand is not an address".to_string(),
             )),
         }
     }
 
-    fn parse_register_with_size(
+    fn parse_index_register(
         &mut self,
         operand: &LexedOperand,
         _line: &ParsedLine,
-    ) -> CompilationResult<(RegisterOperand, Size)> {
+    ) -> CompilationResult<IndexRegister> {
         match operand {
             LexedOperand::Register(register_type, register_name) => {
                 let register = self.parse_register(register_type, register_name)?;
-                Ok((register, Size::Word))
+                Ok(IndexRegister {
+                    register,
+                    scale: 1,
+                    size: Size::Word,
+                })
             }
             LexedOperand::RegisterWithSize(register_type, register_name, size) => {
                 let register = self.parse_register(register_type, register_name)?;
@@ -601,7 +1275,24 @@ impl Compiler {
                         "Invalid size for register, byte is not allowed".to_string(),
                     ))
                 } else {
-                    Ok((register, size))
+                    Ok(IndexRegister {
+                        register,
+                        scale: 1,
+                        size,
+                    })
+                }
+            }
+            LexedOperand::ScaledIndex { register, scale } => {
+                let index = self.parse_index_register(register, _line)?;
+                match self.parse_absolute(scale)? {
+                    scale @ (1 | 2 | 4 | 8) => Ok(IndexRegister {
+                        scale: scale as u8,
+                        ..index
+                    }),
+                    _ => Err(CompilationError::InvalidAddressingMode(format!(
+                        "Invalid index scale: {}, must be 1, 2, 4 or 8",
+                        scale
+                    ))),
                 }
             }
             _ => Err(CompilationError::ParseError(format!(
@@ -610,6 +1301,12 @@ impl Compiler {
             ))),
         }
     }
+    fn parse_displacement(&self, displacement: &str) -> CompilationResult<i32> {
+        if displacement.is_empty() {
+            return Ok(0);
+        }
+        Ok(self.parse_absolute(displacement)? as i32)
+    }
     fn parse_register(
         &mut self,
         register_type: &LexedRegisterType,
@@ -650,6 +1347,23 @@ impl Compiler {
                     value
                 ))),
             },
+            LexedOperand::AbsoluteWithSize(value, size) => {
+                let absolute = self.parse_absolute(value).map_err(|_| {
+                    CompilationError::ParseError(format!("Invalid absolute: {}", value))
+                })?;
+                match size {
+                    LexedSize::Word => {
+                        Ok(Operand::AbsoluteShort(
+                            sign_extend_to_long(absolute, Size::Word) as u32 as usize,
+                        ))
+                    }
+                    LexedSize::Long => Ok(Operand::AbsoluteLong(absolute as usize)),
+                    _ => Err(CompilationError::InvalidAddressingMode(format!(
+                        "Invalid size for absolute address: {}, only .w and .l are allowed",
+                        value
+                    ))),
+                }
+            }
             LexedOperand::Label(label) => match self.labels.get(label) {
                 Some(label) => Ok(Operand::Absolute(label.address)),
                 None => Err(CompilationError::ParseError(format!(
@@ -684,7 +1398,8 @@ impl Compiler {
                     0
                 } else {
                     match parse_absolute_expression(offset, &self.labels) {
-                        Ok(offset) => sign_extend_to_long(offset as u32, Size::Byte),
+                        //the range of the displacement depends on the cpu model, it is checked by the semantic checker
+                        Ok(offset) => offset as i32,
                         Err(_) => {
                             return Err(CompilationError::ParseError(format!(
                                 "Invalid offset: {}",
@@ -693,34 +1408,87 @@ impl Compiler {
                         }
                     }
                 };
-                if operands.len() != 2 {
-                    return Err(CompilationError::ParseError(format!(
-                        "Invalid number of operands for indirect index addressing mode: {:?}, expected 2 operands, found {}",
-                        operands,
-                        operands.len()
-                    )));
-                }
-                let first = self.parse_operand(&operands[0], line)?;
-                let first = self.extract_register(first)?;
-                let second = self.parse_register_with_size(&operands[1], line)?;
-                match first {
-                    RegisterOperand::Data(_) => {
+                //the 68020 allows the base displacement inside the parenthesis, "(bd, An, Xn)"
+                let (offset, operands) = match &operands[..] {
+                    [LexedOperand::Absolute(displacement), rest @ ..] if !rest.is_empty() => {
+                        (self.parse_absolute(displacement)? as i32, rest)
+                    }
+                    _ => (offset, &operands[..]),
+                };
+                match operands {
+                    [LexedOperand::Register(LexedRegisterType::Address | LexedRegisterType::SP, _)] => {
+                        let base = self.parse_operand(&operands[0], line)?;
+                        Ok(Operand::IndirectDisplacement {
+                            offset,
+                            base: self.extract_register(base)?,
+                        })
+                    }
+                    [LexedOperand::Register(LexedRegisterType::Data, _), _] => {
                         Err(CompilationError::InvalidAddressingMode(
                             "First operand of indirect index addressing mode must be an address register".to_string(),
                         ))
                     }
-                    RegisterOperand::Address(_) => {
+                    [base @ LexedOperand::Register(_, _), index] => {
+                        let base = self.parse_operand(base, line)?;
                         Ok(Operand::IndirectIndex {
                             offset,
-                            base: first,
-                            index: IndexRegister {
-                                register: second.0,
-                                size: second.1,
-                            },
+                            base: self.extract_register(base)?,
+                            index: self.parse_index_register(index, line)?,
                         })
                     }
+                    [index] => Ok(Operand::FullIndex {
+                        base: IndexBase::Suppressed,
+                        base_displacement: offset,
+                        index: Some(self.parse_index_register(index, line)?),
+                        indirection: MemoryIndirection::None,
+                    }),
+                    _ => Err(CompilationError::ParseError(format!(
+                        "Invalid number of operands for indirect index addressing mode: {:?}, expected 2 operands, found {}",
+                        operands,
+                        operands.len()
+                    ))),
                 }
             }
+            LexedOperand::MemoryIndirect {
+                base_displacement,
+                base,
+                index,
+                pre_indexed,
+                outer_displacement,
+            } => {
+                let (base, base_displacement) = match base.as_deref() {
+                    Some(LexedOperand::SpecialRegister(LexedSpecialRegister::PC)) => (
+                        IndexBase::PC,
+                        self.parse_pc_offset(base_displacement, Size::Long)?,
+                    ),
+                    Some(base) => {
+                        let base = self.parse_operand(base, line)?;
+                        (
+                            IndexBase::Register(self.extract_register(base)?),
+                            self.parse_displacement(base_displacement)?,
+                        )
+                    }
+                    None => (
+                        IndexBase::Suppressed,
+                        self.parse_displacement(base_displacement)?,
+                    ),
+                };
+                let index = match index {
+                    Some(index) => Some(self.parse_index_register(index, line)?),
+                    None => None,
+                };
+                let outer_displacement = self.parse_displacement(outer_displacement)?;
+                let indirection = match pre_indexed {
+                    true => MemoryIndirection::PreIndexed(outer_displacement),
+                    false => MemoryIndirection::PostIndexed(outer_displacement),
+                };
+                Ok(Operand::FullIndex {
+                    base,
+                    base_displacement,
+                    index,
+                    indirection,
+                })
+            }
             LexedOperand::Indirect(operand) => {
                 let parsed_operand = self.parse_operand(operand, line)?;
                 let parsed_operand = self.extract_register(parsed_operand)?;
@@ -761,6 +1529,17 @@ impl Compiler {
                     e.get_message()
                 ))),
             },
+            LexedOperand::PCDisplacement { offset } => Ok(Operand::PCDisplacement {
+                offset: self.parse_pc_offset(offset, Size::Word)?,
+            }),
+            LexedOperand::PCIndex { offset, index } => {
+                //the range of the displacement depends on the cpu model, it is checked by the semantic checker
+                let offset = self.parse_pc_offset(offset, Size::Long)?;
+                Ok(Operand::PCIndex {
+                    offset,
+                    index: self.parse_index_register(index, line)?,
+                })
+            }
             LexedOperand::RegisterRange { mask } => Ok(Operand::Immediate(*mask as u32)),
             _ => Err(CompilationError::ParseError(format!(
                 "Invalid operand: {:?}",
@@ -769,6 +1548,49 @@ impl Compiler {
         }
     }
 
+    /**
+    The displacement of the pc relative modes is from the extension word of the instruction, a plain number
+    is used as is, while an offset that uses a label is the target address and is converted to the distance
+     */
+    fn parse_pc_offset(&self, offset: &str, size: Size) -> CompilationResult<i32> {
+        if offset.is_empty() {
+            return Ok(0);
+        }
+        let value = match parse_absolute_expression(offset, &self.labels) {
+            Ok(value) => value,
+            Err(_) => {
+                return Err(CompilationError::ParseError(format!(
+                    "Invalid offset: {}",
+                    offset
+                )));
+            }
+        };
+        let (min, max) = match size {
+            Size::Byte => (-128, 127),
+            Size::Word => (-32768, 32767),
+            Size::Long => (i32::MIN as i64, i32::MAX as i64),
+        };
+        if parse_absolute_expression(offset, &HashMap::new()).is_ok() {
+            //the plain numbers can also be written unsigned, like $FFFE for -2
+            let unsigned_max = max * 2 + 1;
+            if value < min || value > unsigned_max {
+                return Err(CompilationError::InvalidAddressingMode(format!(
+                    "Pc relative displacement {} out of range, must be between {} and {}",
+                    offset, min, unsigned_max
+                )));
+            }
+            return Ok(sign_extend_to_long(value as u32, size));
+        }
+        let displacement = value - (self.current_address as i64 + 2);
+        if displacement < min || displacement > max {
+            return Err(CompilationError::InvalidAddressingMode(format!(
+                "Pc relative displacement {} to \"{}\" out of range, must be between {} and {}",
+                displacement, offset, min, max
+            )));
+        }
+        Ok(displacement as i32)
+    }
+
     fn parse_immediate(&self, num: &str) -> CompilationResult<u32> {
         self.parse_absolute(&num[1..])
     }
@@ -800,10 +1622,21 @@ impl Compiler {
                         _ if arg.starts_with('\'') && arg.ends_with('\'') => {
                             let string_bytes = parse_string_into_padded_bytes(
                                 &arg[1..arg.len() - 1],
-                                size.to_bytes_word_default() as usize,
+                                size.to_data_bytes() as usize,
                             );
                             data.extend_from_slice(&string_bytes);
                         }
+                        _ if matches!(size, LexedSize::Short | LexedSize::Double) => {
+                            let format = self.get_fp_format(size)?;
+                            let value = parse_float_expression(arg, format, &self.labels)
+                                .map_err(CompilationError::ParseError)?;
+                            match format {
+                                FpFormat::Single => {
+                                    data.extend_from_slice(&(value as f32).to_be_bytes())
+                                }
+                                _ => data.extend_from_slice(&value.to_be_bytes()),
+                            }
+                        }
                         _ => {
                             let num = self.parse_absolute(arg)?;
                             match size {
@@ -836,7 +1669,7 @@ impl Compiler {
                     ));
                 }
                 let amount = self.parse_absolute(&args[1])?;
-                let data = vec![0; amount as usize * size.to_bytes_word_default() as usize / 8];
+                let data = vec![0; amount as usize * size.to_bytes_word_default() as usize];
                 Ok(Directive::DS { data, address })
             }
             "dcb" => {
@@ -884,13 +1717,24 @@ impl Compiler {
             _ => Ok(Directive::Other),
         }
     }
-    fn get_next_address(&self, line: &ParsedLine, last_address: usize) -> Result<usize, String> {
+    fn get_next_address(
+        line: &ParsedLine,
+        last_address: usize,
+        instruction_size: usize,
+    ) -> Result<usize, String> {
+        //the labels are not known yet while the addresses are being assigned
+        let parse_absolute = |value: &str| -> CompilationResult<u32> {
+            match parse_absolute_expression(value, &HashMap::new()) {
+                Ok(absolute) => Ok(absolute as u32),
+                Err(e) => Err(CompilationError::ParseError(e)),
+            }
+        };
         let mut next_address = last_address;
         match &line.parsed {
             LexedLine::Directive { args, name, size } => {
                 match name.as_str() {
                     "org" => {
-                        let parsed = match self.parse_absolute(&args[1]) {
+                        let parsed = match parse_absolute(&args[1]) {
                             Ok(value) => value as usize,
                             Err(e) => {
                                 return Err(format!(
@@ -909,7 +1753,7 @@ impl Compiler {
                             next_address += 1;
                         }
                     }
-                    "ds" => match self.parse_absolute(&args[1]) {
+                    "ds" => match parse_absolute(&args[1]) {
                         Ok(bytes) => {
                             next_address = last_address
                                 + (bytes * size.to_bytes_word_default() as u32) as usize;
@@ -921,7 +1765,7 @@ impl Compiler {
                             ));
                         }
                     },
-                    "dcb" => match self.parse_absolute(&args[1]) {
+                    "dcb" => match parse_absolute(&args[1]) {
                         Ok(bytes) => {
                             next_address = last_address
                                 + (bytes * size.to_bytes_word_default() as u32) as usize;
@@ -940,12 +1784,12 @@ impl Compiler {
                                 _ if arg.starts_with('\'') && arg.ends_with('\'') => {
                                     next_address += parse_string_into_padded_bytes(
                                         &arg[1..arg.len() - 1],
-                                        size.to_bytes_word_default() as usize,
+                                        size.to_data_bytes() as usize,
                                     )
                                     .len();
                                 }
                                 _ => {
-                                    next_address += size.to_bytes_word_default() as usize;
+                                    next_address += size.to_data_bytes() as usize;
                                 }
                             }
                         }
@@ -958,19 +1802,65 @@ impl Compiler {
                 if next_address % 2 != 0 {
                     next_address += 1;
                 }
-                next_address += 4;
+                next_address += instruction_size;
             }
 
             _ => {}
         }
         Ok(next_address)
     }
-    fn parse_labels_and_addresses(&mut self, lines: &[ParsedLine]) -> Result<(), String> {
+    /**
+    Assigns an address to each of the lines and collects the labels, used by the semantic checker to know
+    the address of the instructions before the program is compiled
+     */
+    pub fn get_line_addresses(
+        lines: &[ParsedLine],
+    ) -> Result<(Vec<usize>, HashMap<String, Label>), String> {
+        let (line_addresses, _, labels) = Compiler::get_line_layout(lines)?;
+        Ok((line_addresses, labels))
+    }
+    /**
+    The size of an instruction depends on its operands, which can depend on the address of later labels,
+    so the lines are encoded again until no size changes. The sizes only grow, so the passes end,
+    a line that can't be encoded keeps its size and the error is reported when it is compiled
+     */
+    fn get_line_layout(lines: &[ParsedLine]) -> Result<LineLayout, String> {
+        let mut line_sizes = vec![2; lines.len()];
+        loop {
+            let (line_addresses, labels) = Compiler::assign_line_addresses(lines, &line_sizes)?;
+            let mut compiler = Compiler {
+                labels,
+                line_addresses,
+                ..Compiler::empty()
+            };
+            let mut changed = false;
+            for (i, line) in lines.iter().enumerate() {
+                if let LexedLine::Instruction { size, .. } = &line.parsed {
+                    let address = compiler.line_addresses[i];
+                    let words = compiler
+                        .compile_instruction(line, address)
+                        .and_then(|ins| encode_instruction(&ins, address, size));
+                    if let Ok(words) = words {
+                        if words.len() * 2 > line_sizes[i] {
+                            line_sizes[i] = words.len() * 2;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !changed {
+                return Ok((compiler.line_addresses, line_sizes, compiler.labels));
+            }
+        }
+    }
+    fn assign_line_addresses(
+        lines: &[ParsedLine],
+        line_sizes: &[usize],
+    ) -> Result<(Vec<usize>, HashMap<String, Label>), String> {
         let mut last_address = 4096; //same as ORG $1000
         let mut labels: HashMap<String, Label> = HashMap::new();
-        let mut directives: Vec<Directive> = Vec::new();
         let mut line_addresses: Vec<usize> = Vec::new();
-        for line in lines.iter() {
+        for (line, instruction_size) in lines.iter().zip(line_sizes) {
             line_addresses.push(last_address);
             match &line.parsed {
                 LexedLine::Label { name } => {
@@ -991,7 +1881,7 @@ impl Compiler {
                 }
                 _ => {}
             }
-            match self.get_next_address(line, last_address) {
+            match Compiler::get_next_address(line, last_address, *instruction_size) {
                 Ok(address) => {
                     last_address = address;
                 }
@@ -1000,11 +1890,46 @@ impl Compiler {
                 }
             }
         }
+        Ok((line_addresses, labels))
+    }
+    fn parse_labels_and_addresses(&mut self, lines: &[ParsedLine]) -> Result<(), String> {
+        let mut directives: Vec<Directive> = Vec::new();
+        let (line_addresses, line_sizes, labels) = Compiler::get_line_layout(lines)?;
         self.labels = labels;
         self.line_addresses = line_addresses;
+        self.line_sizes = line_sizes;
         //TODO i could merge this inthe previous loop but it would now allow for labels to be defined after the directive
+        let mut last_label: Option<&String> = None;
         for (i, line) in lines.iter().enumerate() {
             match &line.parsed {
+                LexedLine::Comment { .. } | LexedLine::Empty => continue,
+                LexedLine::Instruction { .. } => {
+                    if let Some(label) = last_label {
+                        self.code_labels.insert(label.clone());
+                    }
+                }
+                _ => {}
+            }
+            last_label = match &line.parsed {
+                LexedLine::Label { name } => Some(name),
+                _ => None,
+            };
+            match &line.parsed {
+                LexedLine::Directive { name, args, .. } if name == "equ" => {
+                    let expression = args[2..].join(" ");
+                    self.constants.push(Symbol {
+                        name: args[0].clone(),
+                        //the immediates are constants too, like "ten equ #10"
+                        value: self
+                            .parse_absolute(expression.trim_start_matches('#'))
+                            .ok()
+                            .map(|value| value as usize),
+                        expression: Some(expression),
+                        line: line.line_index,
+                        kind: SymbolKind::Constant,
+                    });
+                    directives.push(Directive::Other);
+                }
                 LexedLine::Directive { name, size, args } => {
                     match self.parse_directive(name, size, args, self.line_addresses[i]) {
                         Ok(directive) => {
@@ -4,8 +4,8 @@ use serde::Serialize;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
 use crate::{
-    instructions::{Label, RegisterOperand, Size},
-    interpreter::Flags,
+    instructions::{ControlRegister, FpControlRegister, Label, RegisterOperand, Size},
+    interpreter::{Flags, InterpreterStatus},
 };
 
 #[derive(Debug, Clone, Serialize)]
@@ -33,6 +33,28 @@ pub enum MutationOperation {
         to: usize,
         from: usize,
     },
+    WriteStatusRegister {
+        old: u16,
+    },
+    WriteShadowStackPointer {
+        old: u32,
+    },
+    WriteControlRegister {
+        register: ControlRegister,
+        old: u32,
+    },
+    WriteFpRegister {
+        register: u8,
+        old: f64,
+    },
+    WriteFpControlRegister {
+        register: FpControlRegister,
+        old: u32,
+    },
+    //an interrupt woke up the cpu stopped by STOP
+    WriteInterpreterStatus {
+        old: InterpreterStatus,
+    },
 }
 #[derive(Serialize)]
 pub struct ExecutionStep {
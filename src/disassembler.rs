@@ -0,0 +1,1735 @@
+use bitflags::bitflags;
+use serde::Serialize;
+
+use crate::{
+    encoder::{control_register_bits, fp_control_register_bits, fp_operation_opmode},
+    instructions::{
+        BitField, BitFieldValue, Condition, ControlRegister, FpCondition, FpControlRegister,
+        FpFormat, FpOperand, FpOperation, IndexBase, IndexRegister, Instruction, MemoryIndirection,
+        Operand, RegisterOperand, ShiftDirection, Sign, Size, TargetDirection,
+    },
+    math::extended_to_f64,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub enum DecodeError {
+    //the opcode, or one of its extension words, is not a valid instruction
+    IllegalInstruction(u16),
+    //the instruction continues past the end of the bytes
+    OutOfBounds,
+}
+
+pub type DecodeResult<T> = Result<T, DecodeError>;
+
+bitflags! {
+    //the addressing modes that an instruction does not allow in its effective address
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct EaRules: u8 {
+        const NONE = 0;
+        const NO_D_REG = 1<<0;
+        const NO_A_REG = 1<<1;
+        const NO_POST_INCREMENT = 1<<2;
+        const NO_PRE_DECREMENT = 1<<3;
+        const NO_PC_RELATIVE = 1<<4;
+        const NO_IMMEDIATE = 1<<5;
+        //the categories of the manual, they are combined like "data alterable"
+        const DATA = Self::NO_A_REG.bits();
+        const MEMORY = Self::NO_D_REG.bits() | Self::NO_A_REG.bits();
+        const CONTROL = Self::MEMORY.bits()
+            | Self::NO_POST_INCREMENT.bits()
+            | Self::NO_PRE_DECREMENT.bits()
+            | Self::NO_IMMEDIATE.bits();
+        const ALTERABLE = Self::NO_PC_RELATIVE.bits() | Self::NO_IMMEDIATE.bits();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisassembledLine {
+    pub address: usize,
+    pub words: Vec<u16>,
+    //None when the words are not a valid instruction, they are shown as data
+    pub instruction: Option<Instruction>,
+    pub text: String,
+}
+
+//in the same order as the condition field of the opcodes
+const CONDITIONS: [Condition; 16] = [
+    Condition::True,
+    Condition::False,
+    Condition::High,
+    Condition::LowOrSame,
+    Condition::CarryClear,
+    Condition::CarrySet,
+    Condition::NotEqual,
+    Condition::Equal,
+    Condition::OverflowClear,
+    Condition::OverflowSet,
+    Condition::Plus,
+    Condition::Minus,
+    Condition::GreaterThanOrEqual,
+    Condition::LessThan,
+    Condition::GreaterThan,
+    Condition::LessThanOrEqual,
+];
+const CONDITION_NAMES: [&str; 16] = [
+    "t", "f", "hi", "ls", "cc", "cs", "ne", "eq", "vc", "vs", "pl", "mi", "ge", "lt", "gt", "le",
+];
+
+const FP_CONDITIONS: [FpCondition; 32] = [
+    FpCondition::False,
+    FpCondition::Equal,
+    FpCondition::OrderedGreaterThan,
+    FpCondition::OrderedGreaterOrEqual,
+    FpCondition::OrderedLessThan,
+    FpCondition::OrderedLessOrEqual,
+    FpCondition::OrderedGreaterOrLess,
+    FpCondition::Ordered,
+    FpCondition::Unordered,
+    FpCondition::UnorderedOrEqual,
+    FpCondition::UnorderedOrGreaterThan,
+    FpCondition::UnorderedOrGreaterOrEqual,
+    FpCondition::UnorderedOrLessThan,
+    FpCondition::UnorderedOrLessOrEqual,
+    FpCondition::NotEqual,
+    FpCondition::True,
+    FpCondition::SignalingFalse,
+    FpCondition::SignalingEqual,
+    FpCondition::GreaterThan,
+    FpCondition::GreaterThanOrEqual,
+    FpCondition::LessThan,
+    FpCondition::LessThanOrEqual,
+    FpCondition::GreaterOrLess,
+    FpCondition::GreaterLessOrEqual,
+    FpCondition::NotGreaterLessOrEqual,
+    FpCondition::NotGreaterOrLess,
+    FpCondition::NotLessThanOrEqual,
+    FpCondition::NotLessThan,
+    FpCondition::NotGreaterThanOrEqual,
+    FpCondition::NotGreaterThan,
+    FpCondition::SignalingNotEqual,
+    FpCondition::SignalingTrue,
+];
+const FP_CONDITION_NAMES: [&str; 32] = [
+    "f", "eq", "ogt", "oge", "olt", "ole", "ogl", "or", "un", "ueq", "ugt", "uge", "ult", "ule",
+    "ne", "t", "sf", "seq", "gt", "ge", "lt", "le", "gl", "gle", "ngle", "ngl", "nle", "nlt",
+    "nge", "ngt", "sne", "st",
+];
+
+const FP_OPERATIONS: [FpOperation; 32] = [
+    FpOperation::Add,
+    FpOperation::Sub,
+    FpOperation::Mul,
+    FpOperation::Div,
+    FpOperation::Mod,
+    FpOperation::Rem,
+    FpOperation::Scale,
+    FpOperation::Abs,
+    FpOperation::Neg,
+    FpOperation::Sqrt,
+    FpOperation::Int,
+    FpOperation::IntRZ,
+    FpOperation::GetExp,
+    FpOperation::GetMan,
+    FpOperation::Sin,
+    FpOperation::Cos,
+    FpOperation::Tan,
+    FpOperation::Asin,
+    FpOperation::Acos,
+    FpOperation::Atan,
+    FpOperation::Sinh,
+    FpOperation::Cosh,
+    FpOperation::Tanh,
+    FpOperation::Atanh,
+    FpOperation::Etox,
+    FpOperation::Etoxm1,
+    FpOperation::Twotox,
+    FpOperation::Tentox,
+    FpOperation::Logn,
+    FpOperation::Lognp1,
+    FpOperation::Log10,
+    FpOperation::Log2,
+];
+
+const CONTROL_REGISTERS: [ControlRegister; 4] = [
+    ControlRegister::SFC,
+    ControlRegister::DFC,
+    ControlRegister::USP,
+    ControlRegister::VBR,
+];
+
+const FP_CONTROL_REGISTERS: [FpControlRegister; 3] = [
+    FpControlRegister::FPCR,
+    FpControlRegister::FPSR,
+    FpControlRegister::FPIAR,
+];
+
+/**
+Decodes the instruction at the start of the bytes, which are located at the address,
+returns the instruction and the words it is made of
+ */
+pub fn decode_instruction(bytes: &[u8], address: usize) -> DecodeResult<(Instruction, Vec<u16>)> {
+    let mut decoder = Decoder {
+        bytes,
+        address,
+        opcode: 0,
+        words: Vec::with_capacity(4),
+    };
+    let instruction = decoder.decode()?;
+    Ok((instruction, decoder.words))
+}
+
+/**
+Decodes the bytes as a sequence of instructions, the words that are not a valid instruction
+are shown as "dc.w" data and decoding continues from the next word
+ */
+pub fn disassemble(bytes: &[u8], address: usize) -> Vec<DisassembledLine> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset + 1 < bytes.len() {
+        let current = address + offset;
+        match decode_instruction(&bytes[offset..], current) {
+            Ok((instruction, words)) => {
+                offset += words.len() * 2;
+                lines.push(DisassembledLine {
+                    address: current,
+                    words,
+                    text: format_instruction(&instruction),
+                    instruction: Some(instruction),
+                });
+            }
+            Err(_) => {
+                let word = u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+                offset += 2;
+                lines.push(DisassembledLine {
+                    address: current,
+                    words: vec![word],
+                    instruction: None,
+                    text: format!("dc.w ${:04X}", word),
+                });
+            }
+        }
+    }
+    //a trailing odd byte can't be an instruction
+    if offset < bytes.len() {
+        lines.push(DisassembledLine {
+            address: address + offset,
+            words: vec![bytes[offset] as u16],
+            instruction: None,
+            text: format!("dc.b ${:02X}", bytes[offset]),
+        });
+    }
+    lines
+}
+
+fn decode_size(bits: u16) -> Option<Size> {
+    match bits & 0x3 {
+        0b00 => Some(Size::Byte),
+        0b01 => Some(Size::Word),
+        0b10 => Some(Size::Long),
+        _ => None,
+    }
+}
+
+//the 4 bit register field of the extension words, the address registers come after the data ones
+fn register_from_index(index: u16) -> RegisterOperand {
+    match index & 0x8 {
+        0 => RegisterOperand::Data((index & 0x7) as u8),
+        _ => RegisterOperand::Address((index & 0x7) as u8),
+    }
+}
+
+fn fp_format(bits: u16) -> Option<FpFormat> {
+    match bits & 0x7 {
+        0b000 => Some(FpFormat::Long),
+        0b001 => Some(FpFormat::Single),
+        0b010 => Some(FpFormat::Extended),
+        0b100 => Some(FpFormat::Word),
+        0b101 => Some(FpFormat::Double),
+        0b110 => Some(FpFormat::Byte),
+        _ => None,
+    }
+}
+
+//the data registers only hold the formats that fit in 32 bits
+fn fp_format_rules(format: FpFormat) -> EaRules {
+    match format {
+        FpFormat::Double | FpFormat::Extended => EaRules::MEMORY,
+        _ => EaRules::DATA,
+    }
+}
+
+fn bit_instruction(kind: u16, bit: Operand, target: Operand) -> Instruction {
+    match kind & 0x3 {
+        0b00 => Instruction::BTST(bit, target),
+        0b01 => Instruction::BCHG(bit, target),
+        0b10 => Instruction::BCLR(bit, target),
+        _ => Instruction::BSET(bit, target),
+    }
+}
+
+fn shift_instruction(
+    kind: u16,
+    count: Operand,
+    dest: Operand,
+    direction: ShiftDirection,
+    size: Size,
+) -> Instruction {
+    match kind & 0x3 {
+        0b00 => Instruction::ASd(count, dest, direction, size),
+        0b01 => Instruction::LSd(count, dest, direction, size),
+        0b10 => Instruction::ROXd(count, dest, direction, size),
+        _ => Instruction::ROd(count, dest, direction, size),
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    address: usize,
+    opcode: u16,
+    words: Vec<u16>,
+}
+
+impl Decoder<'_> {
+    fn illegal<T>(&self) -> DecodeResult<T> {
+        Err(DecodeError::IllegalInstruction(self.opcode))
+    }
+    fn next_word(&mut self) -> DecodeResult<u16> {
+        let index = self.words.len() * 2;
+        if index + 2 > self.bytes.len() {
+            return Err(DecodeError::OutOfBounds);
+        }
+        let word = u16::from_be_bytes([self.bytes[index], self.bytes[index + 1]]);
+        self.words.push(word);
+        Ok(word)
+    }
+    fn next_long(&mut self) -> DecodeResult<u32> {
+        let high = self.next_word()? as u32;
+        let low = self.next_word()? as u32;
+        Ok((high << 16) | low)
+    }
+    fn next_sized(&mut self, size: Size) -> DecodeResult<u32> {
+        match size {
+            Size::Byte => Ok(self.next_word()? as u32 & 0xFF),
+            Size::Word => Ok(self.next_word()? as u32),
+            Size::Long => self.next_long(),
+        }
+    }
+    fn size(&self, bits: u16) -> DecodeResult<Size> {
+        match decode_size(bits) {
+            Some(size) => Ok(size),
+            None => self.illegal(),
+        }
+    }
+    //the inverse of the encoder, from the displacement of the extension word to the one relative to the instruction address + 2
+    fn pc_offset(&self, displacement: i32, extension: usize) -> i32 {
+        displacement + (extension as i32 * 2 - 2)
+    }
+    fn branch_target(&self, from: usize, displacement: i64) -> u32 {
+        (from as i64 + displacement) as u32
+    }
+    fn effective_address(&mut self, ea: u16, size: Size) -> DecodeResult<Operand> {
+        let register = (ea & 0x7) as u8;
+        let operand = match (ea >> 3) & 0x7 {
+            0b000 => Operand::Register(RegisterOperand::Data(register)),
+            //the address registers can't be accessed as bytes
+            0b001 if size == Size::Byte => return self.illegal(),
+            0b001 => Operand::Register(RegisterOperand::Address(register)),
+            0b010 => Operand::Indirect(register),
+            0b011 => Operand::PostIndirect(register),
+            0b100 => Operand::PreIndirect(register),
+            0b101 => Operand::IndirectDisplacement {
+                offset: self.next_word()? as i16 as i32,
+                base: RegisterOperand::Address(register),
+            },
+            0b110 => self.index(IndexBase::Register(RegisterOperand::Address(register)))?,
+            _ => match register {
+                0b000 => Operand::AbsoluteShort(self.next_word()? as i16 as i32 as u32 as usize),
+                0b001 => Operand::AbsoluteLong(self.next_long()? as usize),
+                0b010 => {
+                    let extension = self.words.len();
+                    let displacement = self.next_word()? as i16 as i32;
+                    Operand::PCDisplacement {
+                        offset: self.pc_offset(displacement, extension),
+                    }
+                }
+                0b011 => self.index(IndexBase::PC)?,
+                0b100 => Operand::Immediate(self.next_sized(size)?),
+                _ => return self.illegal(),
+            },
+        };
+        Ok(operand)
+    }
+    //the effective address of an instruction that does not allow some of the addressing modes
+    fn checked_address(&mut self, ea: u16, size: Size, rules: EaRules) -> DecodeResult<Operand> {
+        let mode = match ((ea >> 3) & 0x7, ea & 0x7) {
+            (0b000, _) => EaRules::NO_D_REG,
+            (0b001, _) => EaRules::NO_A_REG,
+            (0b011, _) => EaRules::NO_POST_INCREMENT,
+            (0b100, _) => EaRules::NO_PRE_DECREMENT,
+            (0b111, 0b010 | 0b011) => EaRules::NO_PC_RELATIVE,
+            (0b111, 0b100) => EaRules::NO_IMMEDIATE,
+            _ => EaRules::NONE,
+        };
+        if rules.intersects(mode) {
+            return self.illegal();
+        }
+        self.effective_address(ea, size)
+    }
+    //the operands that can be written, but not the address registers
+    fn alterable_address(&mut self, ea: u16, size: Size) -> DecodeResult<Operand> {
+        self.checked_address(ea, size, EaRules::DATA | EaRules::ALTERABLE)
+    }
+    fn displacement(&mut self, size: u16) -> DecodeResult<i32> {
+        match size & 0x3 {
+            0b01 => Ok(0),
+            0b10 => Ok(self.next_word()? as i16 as i32),
+            0b11 => Ok(self.next_long()? as i32),
+            _ => self.illegal(),
+        }
+    }
+    /**
+    Decodes the brief extension word of the 68000 or the 68020 full extension word,
+    followed by its base and outer displacements
+     */
+    fn index(&mut self, base: IndexBase) -> DecodeResult<Operand> {
+        let extension = self.words.len();
+        let word = self.next_word()?;
+        let index = IndexRegister {
+            register: register_from_index(word >> 12),
+            scale: 1 << ((word >> 9) & 0x3),
+            size: match word & 0x0800 {
+                0 => Size::Word,
+                _ => Size::Long,
+            },
+        };
+        if word & 0x0100 == 0 {
+            let displacement = word as u8 as i8 as i32;
+            return match base {
+                IndexBase::PC => Ok(Operand::PCIndex {
+                    offset: self.pc_offset(displacement, extension),
+                    index,
+                }),
+                IndexBase::Register(base) => Ok(Operand::IndirectIndex {
+                    base,
+                    offset: displacement,
+                    index,
+                }),
+                IndexBase::Suppressed => self.illegal(),
+            };
+        }
+        //bit 3 of the full extension word is reserved
+        if word & 0x0008 != 0 {
+            return self.illegal();
+        }
+        let base = if word & 0x0080 != 0 {
+            IndexBase::Suppressed
+        } else {
+            base
+        };
+        let index = if word & 0x0040 != 0 {
+            None
+        } else {
+            Some(index)
+        };
+        let base_displacement = self.displacement(word >> 4)?;
+        let base_displacement = match base {
+            IndexBase::PC => self.pc_offset(base_displacement, extension),
+            _ => base_displacement,
+        };
+        let selection = word & 0x7;
+        let indirection = match selection {
+            0b000 => MemoryIndirection::None,
+            0b001..=0b011 => MemoryIndirection::PreIndexed(self.displacement(selection)?),
+            0b101..=0b111 if index.is_some() => {
+                MemoryIndirection::PostIndexed(self.displacement(selection)?)
+            }
+            _ => return self.illegal(),
+        };
+        Ok(Operand::FullIndex {
+            base,
+            base_displacement,
+            index,
+            indirection,
+        })
+    }
+    //ADDX, SUBX, ABCD and SBCD, either two data registers or two predecrement operands
+    fn extended_operands(&self) -> (Operand, Operand) {
+        let y = (self.opcode & 0x7) as u8;
+        let x = ((self.opcode >> 9) & 0x7) as u8;
+        match self.opcode & 0x0008 {
+            0 => (
+                Operand::Register(RegisterOperand::Data(y)),
+                Operand::Register(RegisterOperand::Data(x)),
+            ),
+            _ => (Operand::PreIndirect(y), Operand::PreIndirect(x)),
+        }
+    }
+    //ADD, SUB, AND and OR, the direction bit tells if the data register is the source or the destination
+    fn arithmetic_operands(
+        &mut self,
+        src_rules: EaRules,
+    ) -> DecodeResult<(Operand, Operand, Size)> {
+        let size = self.size(self.opcode >> 6)?;
+        let register = Operand::Register(RegisterOperand::Data(((self.opcode >> 9) & 0x7) as u8));
+        if self.opcode & 0x0100 == 0 {
+            let src = self.checked_address(self.opcode & 0x3F, size, src_rules)?;
+            Ok((src, register, size))
+        } else {
+            let dest = self.checked_address(
+                self.opcode & 0x3F,
+                size,
+                EaRules::MEMORY | EaRules::ALTERABLE,
+            )?;
+            Ok((register, dest, size))
+        }
+    }
+    fn fp_immediate(&mut self, format: FpFormat) -> DecodeResult<f64> {
+        let value = match format {
+            FpFormat::Byte => self.next_word()? as u8 as i8 as f64,
+            FpFormat::Word => self.next_word()? as i16 as f64,
+            FpFormat::Long => self.next_long()? as i32 as f64,
+            FpFormat::Single => f32::from_bits(self.next_long()?) as f64,
+            FpFormat::Double => {
+                let high = self.next_long()? as u64;
+                let low = self.next_long()? as u64;
+                f64::from_bits((high << 32) | low)
+            }
+            FpFormat::Extended => {
+                let mut bytes = [0u8; 12];
+                for chunk in bytes.chunks_mut(2) {
+                    chunk.copy_from_slice(&self.next_word()?.to_be_bytes());
+                }
+                extended_to_f64(&bytes)
+            }
+        };
+        Ok(value)
+    }
+    fn fp_condition(&self, bits: u16) -> DecodeResult<FpCondition> {
+        match FP_CONDITIONS.get(bits as usize) {
+            Some(condition) => Ok(*condition),
+            None => self.illegal(),
+        }
+    }
+    fn fp_arithmetic(
+        &self,
+        src: FpOperand,
+        dest: u8,
+        opmode: u16,
+        format: FpFormat,
+    ) -> DecodeResult<Instruction> {
+        let instruction = match opmode {
+            0x00 => Instruction::FMOVE(src, FpOperand::Register(dest), format),
+            0x38 => Instruction::FCMP(src, dest, format),
+            0x3A => Instruction::FTST(src, format),
+            _ => match FP_OPERATIONS
+                .iter()
+                .find(|operation| fp_operation_opmode(**operation) == opmode)
+            {
+                Some(operation) => Instruction::FArith(src, dest, *operation, format),
+                None => return self.illegal(),
+            },
+        };
+        Ok(instruction)
+    }
+    fn decode(&mut self) -> DecodeResult<Instruction> {
+        self.opcode = self.next_word()?;
+        match self.opcode >> 12 {
+            0x0 => self.decode_immediate_and_bit(),
+            0x1..=0x3 => self.decode_move(),
+            0x4 => self.decode_miscellaneous(),
+            0x5 => self.decode_quick_and_condition(),
+            0x6 => self.decode_branch(),
+            0x7 => self.decode_moveq(),
+            0x8 => self.decode_or_div(),
+            0x9 | 0xD => self.decode_add_sub(),
+            0xB => self.decode_cmp_eor(),
+            0xC => self.decode_and_mul(),
+            0xE => self.decode_shift_and_bit_field(),
+            0xF => self.decode_coprocessor(),
+            _ => self.illegal(),
+        }
+    }
+    fn decode_immediate_and_bit(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let instruction = match opcode {
+            0x003C => Instruction::ORItoCCR(self.next_word()? as u8),
+            0x023C => Instruction::ANDItoCCR(self.next_word()? as u8),
+            0x0A3C => Instruction::EORItoCCR(self.next_word()? as u8),
+            0x007C => Instruction::ORItoSR(self.next_word()?),
+            0x027C => Instruction::ANDItoSR(self.next_word()?),
+            0x0A7C => Instruction::EORItoSR(self.next_word()?),
+            _ if opcode & 0x0138 == 0x0108 => {
+                let reg = RegisterOperand::Data(((opcode >> 9) & 0x7) as u8);
+                let target = Operand::IndirectDisplacement {
+                    offset: self.next_word()? as i16 as i32,
+                    base: RegisterOperand::Address((opcode & 0x7) as u8),
+                };
+                let direction = match opcode & 0x0080 {
+                    0 => TargetDirection::FromMemory,
+                    _ => TargetDirection::ToMemory,
+                };
+                let size = match opcode & 0x0040 {
+                    0 => Size::Word,
+                    _ => Size::Long,
+                };
+                Instruction::MOVEP(target, reg, direction, size)
+            }
+            _ if opcode & 0x0100 != 0 => {
+                let bit = Operand::Register(RegisterOperand::Data(((opcode >> 9) & 0x7) as u8));
+                //only btst can read its target, the immediate included
+                let rules = match opcode & 0x00C0 {
+                    0 => EaRules::DATA,
+                    _ => EaRules::DATA | EaRules::ALTERABLE,
+                };
+                let target = self.checked_address(opcode & 0x3F, Size::Byte, rules)?;
+                bit_instruction(opcode >> 6, bit, target)
+            }
+            _ => match (opcode >> 9) & 0x7 {
+                0b100 => {
+                    let extension = self.next_word()?;
+                    //the bit number is in the low byte of the extension word
+                    if extension & 0xFF00 != 0 {
+                        return self.illegal();
+                    }
+                    let bit = Operand::Immediate(extension as u32);
+                    let rules = match opcode & 0x00C0 {
+                        0 => EaRules::DATA | EaRules::NO_IMMEDIATE,
+                        _ => EaRules::DATA | EaRules::ALTERABLE,
+                    };
+                    let target = self.checked_address(opcode & 0x3F, Size::Byte, rules)?;
+                    bit_instruction(opcode >> 6, bit, target)
+                }
+                0b111 => {
+                    let size = self.size(opcode >> 6)?;
+                    let extension = self.next_word()?;
+                    if extension & 0x07FF != 0 {
+                        return self.illegal();
+                    }
+                    let direction = match extension & 0x0800 {
+                        0 => TargetDirection::FromMemory,
+                        _ => TargetDirection::ToMemory,
+                    };
+                    let target = self.checked_address(
+                        opcode & 0x3F,
+                        size,
+                        EaRules::MEMORY | EaRules::ALTERABLE,
+                    )?;
+                    Instruction::MOVES(
+                        target,
+                        register_from_index(extension >> 12),
+                        direction,
+                        size,
+                    )
+                }
+                kind => {
+                    let size = self.size(opcode >> 6)?;
+                    let value = self.next_sized(size)?;
+                    //the 68020 can compare with the pc relative modes too
+                    let rules = match kind {
+                        0b110 => EaRules::DATA | EaRules::NO_IMMEDIATE,
+                        _ => EaRules::DATA | EaRules::ALTERABLE,
+                    };
+                    let op = self.checked_address(opcode & 0x3F, size, rules)?;
+                    match kind {
+                        0b000 => Instruction::ORI(value, op, size),
+                        0b001 => Instruction::ANDI(value, op, size),
+                        0b010 => Instruction::SUBI(value, op, size),
+                        0b011 => Instruction::ADDI(value, op, size),
+                        0b101 => Instruction::EORI(value, op, size),
+                        _ => Instruction::CMPI(value, op, size),
+                    }
+                }
+            },
+        };
+        Ok(instruction)
+    }
+    fn decode_move(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let size = match opcode >> 12 {
+            0x1 => Size::Byte,
+            0x3 => Size::Word,
+            _ => Size::Long,
+        };
+        let src = self.effective_address(opcode & 0x3F, size)?;
+        let register = ((opcode >> 9) & 0x7) as u8;
+        //the destination has the register and mode fields swapped
+        match (opcode >> 6) & 0x7 {
+            0b001 if size == Size::Byte => self.illegal(),
+            0b001 => Ok(Instruction::MOVEA(
+                src,
+                RegisterOperand::Address(register),
+                size,
+            )),
+            mode => {
+                let dest = self.alterable_address((mode << 3) | register as u16, size)?;
+                Ok(Instruction::MOVE(src, dest, size))
+            }
+        }
+    }
+    fn decode_miscellaneous(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let register = (opcode & 0x7) as u8;
+        let instruction = match opcode {
+            0x4AFC => Instruction::ILLEGAL,
+            0x4E70 => Instruction::RESET,
+            0x4E71 => Instruction::NOP,
+            0x4E72 => Instruction::STOP(self.next_word()?),
+            0x4E73 => Instruction::RTE,
+            0x4E74 => Instruction::RTD(self.next_word()? as i16),
+            0x4E75 => Instruction::RTS,
+            0x4E76 => Instruction::TRAPV,
+            0x4E77 => Instruction::RTR,
+            0x4E7A | 0x4E7B => {
+                let extension = self.next_word()?;
+                let reg = register_from_index(extension >> 12);
+                let control = match CONTROL_REGISTERS
+                    .iter()
+                    .find(|control| control_register_bits(**control) == extension & 0x0FFF)
+                {
+                    Some(control) => *control,
+                    None => return self.illegal(),
+                };
+                match opcode {
+                    0x4E7B => Instruction::MOVECtoCR(reg, control),
+                    _ => Instruction::MOVECfromCR(control, reg),
+                }
+            }
+            _ if opcode & 0xFFF0 == 0x4E40 => Instruction::TRAP((opcode & 0xF) as u8),
+            _ => match opcode & 0xFFF8 {
+                0x4808 => Instruction::LINK(RegisterOperand::Address(register), self.next_long()?),
+                0x4840 => Instruction::SWAP(RegisterOperand::Data(register)),
+                0x4880 => Instruction::EXT(RegisterOperand::Data(register), Size::Byte, Size::Word),
+                0x48C0 => Instruction::EXT(RegisterOperand::Data(register), Size::Word, Size::Long),
+                0x49C0 => Instruction::EXT(RegisterOperand::Data(register), Size::Byte, Size::Long),
+                0x4E50 => Instruction::LINK(
+                    RegisterOperand::Address(register),
+                    self.next_word()? as i16 as i32 as u32,
+                ),
+                0x4E58 => Instruction::UNLK(RegisterOperand::Address(register)),
+                0x4E60 => Instruction::MOVEtoUSP(RegisterOperand::Address(register)),
+                0x4E68 => Instruction::MOVEfromUSP(RegisterOperand::Address(register)),
+                _ => return self.decode_miscellaneous_ea(),
+            },
+        };
+        Ok(instruction)
+    }
+    //the instructions of the 0x4 line that have an effective address
+    fn decode_miscellaneous_ea(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let ea = opcode & 0x3F;
+        let instruction = match opcode & 0xFFC0 {
+            0x40C0 => Instruction::MOVEfromSR(self.alterable_address(ea, Size::Word)?),
+            0x42C0 => Instruction::MOVEfromCCR(self.alterable_address(ea, Size::Word)?),
+            0x44C0 => {
+                Instruction::MOVEtoCCR(self.checked_address(ea, Size::Word, EaRules::DATA)?)
+            }
+            0x46C0 => Instruction::MOVEtoSR(self.checked_address(ea, Size::Word, EaRules::DATA)?),
+            0x4800 => Instruction::NBCD(self.alterable_address(ea, Size::Byte)?),
+            0x4840 => Instruction::PEA(self.checked_address(ea, Size::Long, EaRules::CONTROL)?),
+            0x4AC0 => Instruction::TAS(self.alterable_address(ea, Size::Byte)?),
+            0x4E80 => Instruction::JSR(self.checked_address(ea, Size::Long, EaRules::CONTROL)?),
+            0x4EC0 => Instruction::JMP(self.checked_address(ea, Size::Long, EaRules::CONTROL)?),
+            0x4C00 => {
+                let extension = self.next_word()?;
+                self.verify_long_extension(extension)?;
+                let src = self.checked_address(ea, Size::Long, EaRules::DATA)?;
+                let high = match extension & 0x0400 {
+                    0 => None,
+                    _ => Some(RegisterOperand::Data((extension & 0x7) as u8)),
+                };
+                Instruction::MULxL(
+                    src,
+                    RegisterOperand::Data(((extension >> 12) & 0x7) as u8),
+                    high,
+                    self.long_sign(extension),
+                )
+            }
+            0x4C40 => {
+                let extension = self.next_word()?;
+                self.verify_long_extension(extension)?;
+                let src = self.checked_address(ea, Size::Long, EaRules::DATA)?;
+                Instruction::DIVxL(
+                    src,
+                    RegisterOperand::Data(((extension >> 12) & 0x7) as u8),
+                    RegisterOperand::Data((extension & 0x7) as u8),
+                    extension & 0x0400 != 0,
+                    self.long_sign(extension),
+                )
+            }
+            _ => match opcode & 0xFF00 {
+                0x4000 | 0x4200 | 0x4400 | 0x4600 | 0x4A00 => {
+                    let size = self.size(opcode >> 6)?;
+                    //the 68020 can test any operand, the address registers only as words and longs
+                    let op = match opcode & 0xFF00 {
+                        0x4A00 => self.effective_address(ea, size)?,
+                        _ => self.alterable_address(ea, size)?,
+                    };
+                    match opcode & 0xFF00 {
+                        0x4000 => Instruction::NEGX(op, size),
+                        0x4200 => Instruction::CLR(op, size),
+                        0x4400 => Instruction::NEG(op, size),
+                        0x4600 => Instruction::NOT(op, size),
+                        _ => Instruction::TST(op, size),
+                    }
+                }
+                _ if opcode & 0xFB80 == 0x4880 => {
+                    let size = match opcode & 0x0040 {
+                        0 => Size::Word,
+                        _ => Size::Long,
+                    };
+                    let direction = match opcode & 0x0400 {
+                        0 => TargetDirection::ToMemory,
+                        _ => TargetDirection::FromMemory,
+                    };
+                    let registers_mask = self.next_word()?;
+                    //the registers are stored with the predecrement and loaded with the postincrement
+                    let rules = match direction {
+                        TargetDirection::ToMemory => {
+                            EaRules::MEMORY | EaRules::ALTERABLE | EaRules::NO_POST_INCREMENT
+                        }
+                        TargetDirection::FromMemory => {
+                            EaRules::MEMORY | EaRules::NO_PRE_DECREMENT | EaRules::NO_IMMEDIATE
+                        }
+                    };
+                    Instruction::MOVEM {
+                        direction,
+                        size,
+                        registers_mask,
+                        target: self.checked_address(ea, size, rules)?,
+                    }
+                }
+                _ => {
+                    let register = ((opcode >> 9) & 0x7) as u8;
+                    match opcode & 0xF1C0 {
+                        0x41C0 => Instruction::LEA(
+                            self.checked_address(ea, Size::Long, EaRules::CONTROL)?,
+                            RegisterOperand::Address(register),
+                        ),
+                        0x4180 => Instruction::CHK(
+                            self.checked_address(ea, Size::Word, EaRules::DATA)?,
+                            RegisterOperand::Data(register),
+                            Size::Word,
+                        ),
+                        0x4100 => Instruction::CHK(
+                            self.checked_address(ea, Size::Long, EaRules::DATA)?,
+                            RegisterOperand::Data(register),
+                            Size::Long,
+                        ),
+                        _ => return self.illegal(),
+                    }
+                }
+            },
+        };
+        Ok(instruction)
+    }
+    //the extension word of MULx.L and DIVx.L only has the registers, the sign and the size
+    fn verify_long_extension(&self, extension: u16) -> DecodeResult<()> {
+        match extension & 0x83F8 {
+            0 => Ok(()),
+            _ => self.illegal(),
+        }
+    }
+    fn long_sign(&self, extension: u16) -> Sign {
+        match extension & 0x0800 {
+            0 => Sign::Unsigned,
+            _ => Sign::Signed,
+        }
+    }
+    fn decode_quick_and_condition(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let condition = CONDITIONS[((opcode >> 8) & 0xF) as usize];
+        let instruction = if opcode & 0x00C0 == 0x00C0 {
+            if opcode & 0x0038 == 0x0008 {
+                let displacement = self.next_word()? as i16 as i64;
+                Instruction::DBcc(
+                    RegisterOperand::Data((opcode & 0x7) as u8),
+                    self.branch_target(self.address + 2, displacement),
+                    condition,
+                )
+            } else {
+                Instruction::Scc(
+                    self.alterable_address(opcode & 0x3F, Size::Byte)?,
+                    condition,
+                )
+            }
+        } else {
+            let size = self.size(opcode >> 6)?;
+            //a data of 0 stands for 8
+            let value = match ((opcode >> 9) & 0x7) as u8 {
+                0 => 8,
+                value => value,
+            };
+            let op = self.checked_address(opcode & 0x3F, size, EaRules::ALTERABLE)?;
+            match opcode & 0x0100 {
+                0 => Instruction::ADDQ(value, op, size),
+                _ => Instruction::SUBQ(value, op, size),
+            }
+        };
+        Ok(instruction)
+    }
+    fn decode_branch(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        //a displacement of 0 or -1 in the opcode means that a word or long displacement follows
+        let displacement = match opcode as u8 {
+            0x00 => self.next_word()? as i16 as i64,
+            0xFF => self.next_long()? as i32 as i64,
+            displacement => displacement as i8 as i64,
+        };
+        let target = self.branch_target(self.address + 2, displacement);
+        let instruction = match (opcode >> 8) & 0xF {
+            0b0000 => Instruction::BRA(target),
+            0b0001 => Instruction::BSR(target),
+            condition => Instruction::Bcc(target, CONDITIONS[condition as usize]),
+        };
+        Ok(instruction)
+    }
+    fn decode_moveq(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        if opcode & 0x0100 != 0 {
+            return self.illegal();
+        }
+        Ok(Instruction::MOVEQ(
+            opcode as u8,
+            RegisterOperand::Data(((opcode >> 9) & 0x7) as u8),
+        ))
+    }
+    fn decode_or_div(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let register = RegisterOperand::Data(((opcode >> 9) & 0x7) as u8);
+        let instruction = match opcode & 0xF1C0 {
+            0x80C0 => Instruction::DIVx(
+                self.checked_address(opcode & 0x3F, Size::Word, EaRules::DATA)?,
+                register,
+                Sign::Unsigned,
+            ),
+            0x81C0 => Instruction::DIVx(
+                self.checked_address(opcode & 0x3F, Size::Word, EaRules::DATA)?,
+                register,
+                Sign::Signed,
+            ),
+            _ if opcode & 0xF1F0 == 0x8100 => {
+                let (src, dest) = self.extended_operands();
+                Instruction::SBCD(src, dest)
+            }
+            _ => {
+                let (src, dest, size) = self.arithmetic_operands(EaRules::DATA)?;
+                Instruction::OR(src, dest, size)
+            }
+        };
+        Ok(instruction)
+    }
+    fn decode_add_sub(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let is_add = opcode >> 12 == 0xD;
+        let instruction = if opcode & 0x00C0 == 0x00C0 {
+            let size = match opcode & 0x0100 {
+                0 => Size::Word,
+                _ => Size::Long,
+            };
+            let src = self.effective_address(opcode & 0x3F, size)?;
+            let dest = RegisterOperand::Address(((opcode >> 9) & 0x7) as u8);
+            match is_add {
+                true => Instruction::ADDA(src, dest, size),
+                false => Instruction::SUBA(src, dest, size),
+            }
+        } else if opcode & 0x0130 == 0x0100 {
+            let size = self.size(opcode >> 6)?;
+            let (src, dest) = self.extended_operands();
+            match is_add {
+                true => Instruction::ADDX(src, dest, size),
+                false => Instruction::SUBX(src, dest, size),
+            }
+        } else {
+            let (src, dest, size) = self.arithmetic_operands(EaRules::NONE)?;
+            match is_add {
+                true => Instruction::ADD(src, dest, size),
+                false => Instruction::SUB(src, dest, size),
+            }
+        };
+        Ok(instruction)
+    }
+    fn decode_cmp_eor(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let register = ((opcode >> 9) & 0x7) as u8;
+        if opcode & 0x00C0 == 0x00C0 {
+            let size = match opcode & 0x0100 {
+                0 => Size::Word,
+                _ => Size::Long,
+            };
+            let src = self.effective_address(opcode & 0x3F, size)?;
+            return Ok(Instruction::CMPA(
+                src,
+                RegisterOperand::Address(register),
+                size,
+            ));
+        }
+        let size = self.size(opcode >> 6)?;
+        let instruction = if opcode & 0x0100 == 0 {
+            let src = self.effective_address(opcode & 0x3F, size)?;
+            Instruction::CMP(src, RegisterOperand::Data(register), size)
+        } else if opcode & 0x0038 == 0x0008 {
+            Instruction::CMPM(
+                Operand::PostIndirect((opcode & 0x7) as u8),
+                Operand::PostIndirect(register),
+                size,
+            )
+        } else {
+            let dest = self.alterable_address(opcode & 0x3F, size)?;
+            Instruction::EOR(
+                Operand::Register(RegisterOperand::Data(register)),
+                dest,
+                size,
+            )
+        };
+        Ok(instruction)
+    }
+    fn decode_and_mul(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let x = ((opcode >> 9) & 0x7) as u8;
+        let y = (opcode & 0x7) as u8;
+        let instruction = match opcode & 0xF1C0 {
+            0xC0C0 => Instruction::MULx(
+                self.checked_address(opcode & 0x3F, Size::Word, EaRules::DATA)?,
+                RegisterOperand::Data(x),
+                Sign::Unsigned,
+            ),
+            0xC1C0 => Instruction::MULx(
+                self.checked_address(opcode & 0x3F, Size::Word, EaRules::DATA)?,
+                RegisterOperand::Data(x),
+                Sign::Signed,
+            ),
+            _ => match opcode & 0xF1F8 {
+                0xC100 | 0xC108 => {
+                    let (src, dest) = self.extended_operands();
+                    Instruction::ABCD(src, dest)
+                }
+                0xC140 => Instruction::EXG(RegisterOperand::Data(x), RegisterOperand::Data(y)),
+                0xC148 => {
+                    Instruction::EXG(RegisterOperand::Address(x), RegisterOperand::Address(y))
+                }
+                0xC188 => Instruction::EXG(RegisterOperand::Data(x), RegisterOperand::Address(y)),
+                _ => {
+                    let (src, dest, size) = self.arithmetic_operands(EaRules::DATA)?;
+                    Instruction::AND(src, dest, size)
+                }
+            },
+        };
+        Ok(instruction)
+    }
+    fn decode_shift_and_bit_field(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        let direction = match opcode & 0x0100 {
+            0 => ShiftDirection::Right,
+            _ => ShiftDirection::Left,
+        };
+        if opcode & 0x00C0 != 0x00C0 {
+            let size = self.size(opcode >> 6)?;
+            let count = ((opcode >> 9) & 0x7) as u8;
+            //the count is either a data register or an immediate where 0 stands for 8
+            let count = match (opcode & 0x0020, count) {
+                (0, 0) => Operand::Immediate(8),
+                (0, count) => Operand::Immediate(count as u32),
+                (_, count) => Operand::Register(RegisterOperand::Data(count)),
+            };
+            let dest = Operand::Register(RegisterOperand::Data((opcode & 0x7) as u8));
+            return Ok(shift_instruction(opcode >> 3, count, dest, direction, size));
+        }
+        if opcode & 0x0800 == 0 {
+            //the memory form shifts a word by one bit
+            let dest = self.checked_address(
+                opcode & 0x3F,
+                Size::Word,
+                EaRules::MEMORY | EaRules::ALTERABLE,
+            )?;
+            return Ok(shift_instruction(
+                opcode >> 9,
+                Operand::Immediate(1),
+                dest,
+                direction,
+                Size::Word,
+            ));
+        }
+        let extension = self.next_word()?;
+        //the fields are in a data register or in memory, the ones that change it can't be pc relative
+        let kind = (opcode >> 8) & 0x7;
+        let rules = match kind {
+            0b000 | 0b001 | 0b011 | 0b101 => EaRules::CONTROL & !EaRules::NO_D_REG,
+            _ => (EaRules::CONTROL | EaRules::ALTERABLE) & !EaRules::NO_D_REG,
+        };
+        //the register field is reserved for the instructions that don't use it, like the bits
+        //between the offset or width register and their flag
+        let has_register = matches!(kind, 0b001 | 0b011 | 0b101 | 0b111);
+        if extension & 0x8000 != 0
+            || (!has_register && extension & 0x7000 != 0)
+            || (extension & 0x0800 != 0 && extension & 0x0600 != 0)
+            || (extension & 0x0020 != 0 && extension & 0x0018 != 0)
+        {
+            return self.illegal();
+        }
+        let op = self.checked_address(opcode & 0x3F, Size::Long, rules)?;
+        let field = BitField {
+            offset: match extension & 0x0800 {
+                0 => BitFieldValue::Immediate(((extension >> 6) & 0x1F) as u8),
+                _ => BitFieldValue::Register(RegisterOperand::Data(((extension >> 6) & 0x7) as u8)),
+            },
+            //a width of 0 stands for 32
+            width: match (extension & 0x0020, (extension & 0x1F) as u8) {
+                (0, 0) => BitFieldValue::Immediate(32),
+                (0, width) => BitFieldValue::Immediate(width),
+                (_, width) => BitFieldValue::Register(RegisterOperand::Data(width & 0x7)),
+            },
+        };
+        let register = RegisterOperand::Data(((extension >> 12) & 0x7) as u8);
+        let instruction = match kind {
+            0b000 => Instruction::BFTST(op, field),
+            0b001 => Instruction::BFEXTU(op, field, register),
+            0b010 => Instruction::BFCHG(op, field),
+            0b011 => Instruction::BFEXTS(op, field, register),
+            0b100 => Instruction::BFCLR(op, field),
+            0b101 => Instruction::BFFFO(op, field, register),
+            0b110 => Instruction::BFSET(op, field),
+            _ => Instruction::BFINS(register, op, field),
+        };
+        Ok(instruction)
+    }
+    //only the floating point coprocessor, with id 1, is supported
+    fn decode_coprocessor(&mut self) -> DecodeResult<Instruction> {
+        let opcode = self.opcode;
+        if (opcode >> 9) & 0x7 != 0b001 {
+            return self.illegal();
+        }
+        let instruction = match (opcode >> 6) & 0x7 {
+            0b000 => return self.decode_fp_general(),
+            0b001 => {
+                let condition = self.next_word()?;
+                let condition = self.fp_condition(condition)?;
+                if opcode & 0x0038 == 0x0008 {
+                    //the displacement is relative to its own extension word, after the condition
+                    let displacement = self.next_word()? as i16 as i64;
+                    Instruction::FDBcc(
+                        RegisterOperand::Data((opcode & 0x7) as u8),
+                        self.branch_target(self.address + 4, displacement),
+                        condition,
+                    )
+                } else {
+                    Instruction::FScc(
+                        self.alterable_address(opcode & 0x3F, Size::Byte)?,
+                        condition,
+                    )
+                }
+            }
+            kind @ (0b010 | 0b011) => {
+                let condition = self.fp_condition(opcode & 0x3F)?;
+                let displacement = match kind {
+                    0b010 => self.next_word()? as i16 as i64,
+                    _ => self.next_long()? as i32 as i64,
+                };
+                match (kind, opcode & 0x3F, displacement) {
+                    (0b010, 0, 0) => Instruction::FNOP,
+                    _ => Instruction::FBcc(
+                        self.branch_target(self.address + 2, displacement),
+                        condition,
+                    ),
+                }
+            }
+            _ => return self.illegal(),
+        };
+        Ok(instruction)
+    }
+    fn decode_fp_general(&mut self) -> DecodeResult<Instruction> {
+        let ea = self.opcode & 0x3F;
+        let command = self.next_word()?;
+        let opmode = command & 0x7F;
+        let register = ((command >> 7) & 0x7) as u8;
+        match command >> 13 {
+            //the register sources are already extended precision
+            0b000 => self.fp_arithmetic(
+                FpOperand::Register(((command >> 10) & 0x7) as u8),
+                register,
+                opmode,
+                FpFormat::Extended,
+            ),
+            //the constant rom has no effective address
+            0b010 if command & 0xFC00 == 0x5C00 && ea == 0 => {
+                Ok(Instruction::FMOVECR(opmode as u8, register))
+            }
+            0b010 if command & 0xFC00 == 0x5C00 => self.illegal(),
+            0b010 => {
+                let format = match fp_format(command >> 10) {
+                    Some(format) => format,
+                    None => return self.illegal(),
+                };
+                let src = match ea {
+                    0x3C => FpOperand::Immediate(self.fp_immediate(format)?),
+                    _ => {
+                        let size = format.to_integer_size().unwrap_or(Size::Long);
+                        let rules = fp_format_rules(format);
+                        FpOperand::Operand(self.checked_address(ea, size, rules)?)
+                    }
+                };
+                self.fp_arithmetic(src, register, opmode, format)
+            }
+            0b011 => {
+                let format = match fp_format(command >> 10) {
+                    Some(format) => format,
+                    None => return self.illegal(),
+                };
+                let size = format.to_integer_size().unwrap_or(Size::Long);
+                let rules = fp_format_rules(format) | EaRules::ALTERABLE;
+                let dest = self.checked_address(ea, size, rules)?;
+                Ok(Instruction::FMOVE(
+                    FpOperand::Register(register),
+                    FpOperand::Operand(dest),
+                    format,
+                ))
+            }
+            kind @ (0b100 | 0b101) if command & 0x03FF == 0 => {
+                let control = match FP_CONTROL_REGISTERS
+                    .iter()
+                    .find(|control| fp_control_register_bits(**control) == (command >> 10) & 0x7)
+                {
+                    Some(control) => *control,
+                    None => return self.illegal(),
+                };
+                //only the instruction address register can be moved to and from an address register
+                let rules = match control {
+                    FpControlRegister::FPIAR => EaRules::NONE,
+                    _ => EaRules::DATA,
+                };
+                match kind {
+                    0b100 => Ok(Instruction::FMOVEtoCR(
+                        self.checked_address(ea, Size::Long, rules)?,
+                        control,
+                    )),
+                    _ => Ok(Instruction::FMOVEfromCR(
+                        control,
+                        self.checked_address(ea, Size::Long, rules | EaRules::ALTERABLE)?,
+                    )),
+                }
+            }
+            _ => self.illegal(),
+        }
+    }
+}
+
+fn size_suffix(size: Size) -> &'static str {
+    match size {
+        Size::Byte => "b",
+        Size::Word => "w",
+        Size::Long => "l",
+    }
+}
+
+fn fp_format_suffix(format: FpFormat) -> &'static str {
+    match format {
+        FpFormat::Byte => "b",
+        FpFormat::Word => "w",
+        FpFormat::Long => "l",
+        FpFormat::Single => "s",
+        FpFormat::Double => "d",
+        FpFormat::Extended => "x",
+    }
+}
+
+fn fp_operation_name(operation: FpOperation) -> &'static str {
+    match operation {
+        FpOperation::Add => "fadd",
+        FpOperation::Sub => "fsub",
+        FpOperation::Mul => "fmul",
+        FpOperation::Div => "fdiv",
+        FpOperation::Mod => "fmod",
+        FpOperation::Rem => "frem",
+        FpOperation::Scale => "fscale",
+        FpOperation::Abs => "fabs",
+        FpOperation::Neg => "fneg",
+        FpOperation::Sqrt => "fsqrt",
+        FpOperation::Int => "fint",
+        FpOperation::IntRZ => "fintrz",
+        FpOperation::GetExp => "fgetexp",
+        FpOperation::GetMan => "fgetman",
+        FpOperation::Sin => "fsin",
+        FpOperation::Cos => "fcos",
+        FpOperation::Tan => "ftan",
+        FpOperation::Asin => "fasin",
+        FpOperation::Acos => "facos",
+        FpOperation::Atan => "fatan",
+        FpOperation::Sinh => "fsinh",
+        FpOperation::Cosh => "fcosh",
+        FpOperation::Tanh => "ftanh",
+        FpOperation::Atanh => "fatanh",
+        FpOperation::Etox => "fetox",
+        FpOperation::Etoxm1 => "fetoxm1",
+        FpOperation::Twotox => "ftwotox",
+        FpOperation::Tentox => "ftentox",
+        FpOperation::Logn => "flogn",
+        FpOperation::Lognp1 => "flognp1",
+        FpOperation::Log10 => "flog10",
+        FpOperation::Log2 => "flog2",
+    }
+}
+
+fn format_register(register: &RegisterOperand) -> String {
+    match register {
+        RegisterOperand::Data(reg) => format!("d{}", reg),
+        RegisterOperand::Address(reg) => format!("a{}", reg),
+    }
+}
+
+fn format_index(index: &IndexRegister) -> String {
+    let scale = match index.scale {
+        1 => String::new(),
+        scale => format!("*{}", scale),
+    };
+    format!(
+        "{}.{}{}",
+        format_register(&index.register),
+        size_suffix(index.size),
+        scale
+    )
+}
+
+fn format_index_base(base: &IndexBase) -> Option<String> {
+    match base {
+        IndexBase::Register(register) => Some(format_register(register)),
+        IndexBase::PC => Some("pc".to_string()),
+        IndexBase::Suppressed => None,
+    }
+}
+
+/**
+Formats the operand in Motorola syntax, the immediates and addresses in hex
+and the displacements in signed decimal
+ */
+pub fn format_operand(operand: &Operand) -> String {
+    match operand {
+        Operand::Immediate(value) => format!("#${:X}", value),
+        Operand::Register(register) => format_register(register),
+        Operand::Indirect(reg) => format!("(a{})", reg),
+        Operand::PostIndirect(reg) => format!("(a{})+", reg),
+        Operand::PreIndirect(reg) => format!("-(a{})", reg),
+        Operand::IndirectDisplacement { offset, base } => {
+            format!("{}({})", offset, format_register(base))
+        }
+        Operand::IndirectIndex {
+            base,
+            offset,
+            index,
+        } => format!(
+            "{}({},{})",
+            offset,
+            format_register(base),
+            format_index(index)
+        ),
+        Operand::PCDisplacement { offset } => format!("{}(pc)", offset),
+        Operand::PCIndex { offset, index } => format!("{}(pc,{})", offset, format_index(index)),
+        Operand::FullIndex {
+            base,
+            base_displacement,
+            index,
+            indirection,
+        } => {
+            let base = format_index_base(base);
+            let index = index.as_ref().map(format_index);
+            let parts = |parts: Vec<Option<String>>| -> String {
+                parts
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<String>>()
+                    .join(",")
+            };
+            let displacement = Some(base_displacement.to_string());
+            match indirection {
+                MemoryIndirection::None => format!("({})", parts(vec![displacement, base, index])),
+                MemoryIndirection::PreIndexed(outer) => {
+                    format!("([{}],{})", parts(vec![displacement, base, index]), outer)
+                }
+                MemoryIndirection::PostIndexed(outer) => format!(
+                    "([{}],{})",
+                    parts(vec![displacement, base]),
+                    parts(vec![index, Some(outer.to_string())])
+                ),
+            }
+        }
+        Operand::Absolute(address) => format!("${:X}", address),
+        Operand::AbsoluteShort(address) => format!("${:X}.w", *address as u16),
+        Operand::AbsoluteLong(address) => format!("${:X}.l", address),
+    }
+}
+
+//the register list of MOVEM, with the consecutive registers merged in ranges like "d0-d3/a6"
+fn format_register_list(mask: u16) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < 16 {
+        if mask & (1 << index) == 0 {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        //the ranges don't cross from the data to the address registers
+        while index + 1 < 16 && (index + 1) % 8 != 0 && mask & (1 << (index + 1)) != 0 {
+            index += 1;
+        }
+        let name = |i: u16| format_register(&register_from_index(i));
+        if start == index {
+            ranges.push(name(start));
+        } else {
+            ranges.push(format!("{}-{}", name(start), name(index)));
+        }
+        index += 1;
+    }
+    ranges.join("/")
+}
+
+fn format_bit_field(op: &Operand, field: &BitField) -> String {
+    let value = |value: &BitFieldValue| match value {
+        BitFieldValue::Immediate(value) => value.to_string(),
+        BitFieldValue::Register(register) => format_register(register),
+    };
+    format!(
+        "{}{{{}:{}}}",
+        format_operand(op),
+        value(&field.offset),
+        value(&field.width)
+    )
+}
+
+fn format_fp_operand(operand: &FpOperand) -> String {
+    match operand {
+        FpOperand::Register(reg) => format!("fp{}", reg),
+        FpOperand::Immediate(value) => format!("#{}", value),
+        FpOperand::Operand(op) => format_operand(op),
+    }
+}
+
+/**
+Formats the instruction in Motorola syntax, the text can be assembled back to the same instruction,
+the branch targets are absolute addresses
+ */
+pub fn format_instruction(instruction: &Instruction) -> String {
+    let op = format_operand;
+    let reg = format_register;
+    let sized = |name: &str, size: &Size| format!("{}.{}", name, size_suffix(*size));
+    let sign = |sign: &Sign, signed: &str, unsigned: &str| match sign {
+        Sign::Signed => signed.to_string(),
+        Sign::Unsigned => unsigned.to_string(),
+    };
+    let shift =
+        |name: &str, count: &Operand, dest: &Operand, direction: &ShiftDirection, size: &Size| {
+            let name = match direction {
+                ShiftDirection::Left => format!("{}l", name),
+                ShiftDirection::Right => format!("{}r", name),
+            };
+            match dest {
+                Operand::Register(_) => {
+                    format!("{} {}, {}", sized(&name, size), op(count), op(dest))
+                }
+                _ => format!("{} {}", sized(&name, size), op(dest)),
+            }
+        };
+    match instruction {
+        Instruction::MOVE(src, dest, size) => {
+            format!("{} {}, {}", sized("move", size), op(src), op(dest))
+        }
+        Instruction::MOVEA(src, dest, size) => {
+            format!("{} {}, {}", sized("movea", size), op(src), reg(dest))
+        }
+        Instruction::ADDA(src, dest, size) => {
+            format!("{} {}, {}", sized("adda", size), op(src), reg(dest))
+        }
+        Instruction::SUBA(src, dest, size) => {
+            format!("{} {}, {}", sized("suba", size), op(src), reg(dest))
+        }
+        Instruction::CMPA(src, dest, size) => {
+            format!("{} {}, {}", sized("cmpa", size), op(src), reg(dest))
+        }
+        Instruction::MOVEM {
+            direction,
+            size,
+            registers_mask,
+            target,
+        } => {
+            //the predecrement mode has the mask reversed, a7 in bit 0 and d0 in bit 15
+            let mask = match target {
+                Operand::PreIndirect(_) => registers_mask.reverse_bits(),
+                _ => *registers_mask,
+            };
+            let list = format_register_list(mask);
+            match direction {
+                TargetDirection::ToMemory => {
+                    format!("{} {}, {}", sized("movem", size), list, op(target))
+                }
+                TargetDirection::FromMemory => {
+                    format!("{} {}, {}", sized("movem", size), op(target), list)
+                }
+            }
+        }
+        Instruction::MOVEP(target, register, direction, size) => match direction {
+            TargetDirection::ToMemory => {
+                format!("{} {}, {}", sized("movep", size), reg(register), op(target))
+            }
+            TargetDirection::FromMemory => {
+                format!("{} {}, {}", sized("movep", size), op(target), reg(register))
+            }
+        },
+        Instruction::MOVEtoSR(src) => format!("move.w {}, sr", op(src)),
+        Instruction::MOVEfromSR(dest) => format!("move.w sr, {}", op(dest)),
+        Instruction::MOVEtoCCR(src) => format!("move.w {}, ccr", op(src)),
+        Instruction::MOVEfromCCR(dest) => format!("move.w ccr, {}", op(dest)),
+        Instruction::MOVEtoUSP(src) => format!("move.l {}, usp", reg(src)),
+        Instruction::MOVEfromUSP(dest) => format!("move.l usp, {}", reg(dest)),
+        Instruction::MOVECtoCR(src, control) => {
+            format!(
+                "movec {}, {}",
+                reg(src),
+                format!("{:?}", control).to_lowercase()
+            )
+        }
+        Instruction::MOVECfromCR(control, dest) => {
+            format!(
+                "movec {}, {}",
+                format!("{:?}", control).to_lowercase(),
+                reg(dest)
+            )
+        }
+        Instruction::MOVES(target, register, direction, size) => match direction {
+            TargetDirection::ToMemory => {
+                format!("{} {}, {}", sized("moves", size), reg(register), op(target))
+            }
+            TargetDirection::FromMemory => {
+                format!("{} {}, {}", sized("moves", size), op(target), reg(register))
+            }
+        },
+        Instruction::ANDItoSR(value) => format!("andi.w #${:X}, sr", value),
+        Instruction::ORItoSR(value) => format!("ori.w #${:X}, sr", value),
+        Instruction::EORItoSR(value) => format!("eori.w #${:X}, sr", value),
+        Instruction::ANDItoCCR(value) => format!("andi.b #${:X}, ccr", value),
+        Instruction::ORItoCCR(value) => format!("ori.b #${:X}, ccr", value),
+        Instruction::EORItoCCR(value) => format!("eori.b #${:X}, ccr", value),
+        Instruction::ADD(src, dest, size) => {
+            format!("{} {}, {}", sized("add", size), op(src), op(dest))
+        }
+        Instruction::SUB(src, dest, size) => {
+            format!("{} {}, {}", sized("sub", size), op(src), op(dest))
+        }
+        Instruction::ADDX(src, dest, size) => {
+            format!("{} {}, {}", sized("addx", size), op(src), op(dest))
+        }
+        Instruction::SUBX(src, dest, size) => {
+            format!("{} {}, {}", sized("subx", size), op(src), op(dest))
+        }
+        Instruction::AND(src, dest, size) => {
+            format!("{} {}, {}", sized("and", size), op(src), op(dest))
+        }
+        Instruction::OR(src, dest, size) => {
+            format!("{} {}, {}", sized("or", size), op(src), op(dest))
+        }
+        Instruction::EOR(src, dest, size) => {
+            format!("{} {}, {}", sized("eor", size), op(src), op(dest))
+        }
+        Instruction::CMPM(src, dest, size) => {
+            format!("{} {}, {}", sized("cmpm", size), op(src), op(dest))
+        }
+        Instruction::ABCD(src, dest) => format!("abcd {}, {}", op(src), op(dest)),
+        Instruction::SBCD(src, dest) => format!("sbcd {}, {}", op(src), op(dest)),
+        Instruction::NBCD(dest) => format!("nbcd {}", op(dest)),
+        Instruction::NEGX(dest, size) => format!("{} {}", sized("negx", size), op(dest)),
+        Instruction::CLR(dest, size) => format!("{} {}", sized("clr", size), op(dest)),
+        Instruction::NEG(dest, size) => format!("{} {}", sized("neg", size), op(dest)),
+        Instruction::NOT(dest, size) => format!("{} {}", sized("not", size), op(dest)),
+        Instruction::TST(dest, size) => format!("{} {}", sized("tst", size), op(dest)),
+        Instruction::TAS(dest) => format!("tas {}", op(dest)),
+        Instruction::ADDQ(value, dest, size) => {
+            format!("{} #{}, {}", sized("addq", size), value, op(dest))
+        }
+        Instruction::SUBQ(value, dest, size) => {
+            format!("{} #{}, {}", sized("subq", size), value, op(dest))
+        }
+        Instruction::MOVEQ(value, dest) => format!("moveq #{}, {}", *value as i8, reg(dest)),
+        Instruction::ADDI(value, dest, size) => {
+            format!("{} #${:X}, {}", sized("addi", size), value, op(dest))
+        }
+        Instruction::SUBI(value, dest, size) => {
+            format!("{} #${:X}, {}", sized("subi", size), value, op(dest))
+        }
+        Instruction::ANDI(value, dest, size) => {
+            format!("{} #${:X}, {}", sized("andi", size), value, op(dest))
+        }
+        Instruction::ORI(value, dest, size) => {
+            format!("{} #${:X}, {}", sized("ori", size), value, op(dest))
+        }
+        Instruction::EORI(value, dest, size) => {
+            format!("{} #${:X}, {}", sized("eori", size), value, op(dest))
+        }
+        Instruction::CMPI(value, dest, size) => {
+            format!("{} #${:X}, {}", sized("cmpi", size), value, op(dest))
+        }
+        Instruction::CMP(src, dest, size) => {
+            format!("{} {}, {}", sized("cmp", size), op(src), reg(dest))
+        }
+        Instruction::DIVx(src, dest, s) => {
+            format!("{} {}, {}", sign(s, "divs", "divu"), op(src), reg(dest))
+        }
+        Instruction::MULx(src, dest, s) => {
+            format!("{} {}, {}", sign(s, "muls", "mulu"), op(src), reg(dest))
+        }
+        Instruction::MULxL(src, low, high, s) => {
+            let dest = match high {
+                Some(high) => format!("{}:{}", reg(high), reg(low)),
+                None => reg(low),
+            };
+            format!("{}.l {}, {}", sign(s, "muls", "mulu"), op(src), dest)
+        }
+        Instruction::DIVxL(src, quotient, remainder, is_64, s) => {
+            //the 32 bit dividend with a separate remainder register uses the "l" variant
+            let same = quotient.to_index() == remainder.to_index();
+            match (is_64, same) {
+                (false, true) => format!(
+                    "{}.l {}, {}",
+                    sign(s, "divs", "divu"),
+                    op(src),
+                    reg(quotient)
+                ),
+                (false, false) => format!(
+                    "{}.l {}, {}:{}",
+                    sign(s, "divsl", "divul"),
+                    op(src),
+                    reg(remainder),
+                    reg(quotient)
+                ),
+                (true, _) => format!(
+                    "{}.l {}, {}:{}",
+                    sign(s, "divs", "divu"),
+                    op(src),
+                    reg(remainder),
+                    reg(quotient)
+                ),
+            }
+        }
+        Instruction::CHK(src, dest, size) => {
+            format!("{} {}, {}", sized("chk", size), op(src), reg(dest))
+        }
+        Instruction::SWAP(register) => format!("swap {}", reg(register)),
+        Instruction::EXG(first, second) => format!("exg {}, {}", reg(first), reg(second)),
+        Instruction::LEA(src, dest) => format!("lea {}, {}", op(src), reg(dest)),
+        Instruction::PEA(src) => format!("pea {}", op(src)),
+        Instruction::EXT(register, from, to) => match (from, to) {
+            (Size::Byte, Size::Long) => format!("extb.l {}", reg(register)),
+            _ => format!("{} {}", sized("ext", to), reg(register)),
+        },
+        Instruction::Bcc(target, condition) => {
+            format!("b{} ${:X}", CONDITION_NAMES[*condition as usize], target)
+        }
+        Instruction::BRA(target) => format!("bra ${:X}", target),
+        Instruction::BSR(target) => format!("bsr ${:X}", target),
+        Instruction::Scc(dest, condition) => {
+            format!("s{} {}", CONDITION_NAMES[*condition as usize], op(dest))
+        }
+        Instruction::DBcc(register, target, condition) => {
+            let name = match condition {
+                Condition::False => "ra",
+                _ => CONDITION_NAMES[*condition as usize],
+            };
+            format!("db{} {}, ${:X}", name, reg(register), target)
+        }
+        Instruction::LINK(register, displacement) => {
+            format!("link {}, #{}", reg(register), *displacement as i32)
+        }
+        Instruction::UNLK(register) => format!("unlk {}", reg(register)),
+        Instruction::JSR(target) => format!("jsr {}", op(target)),
+        Instruction::JMP(target) => format!("jmp {}", op(target)),
+        Instruction::ASd(count, dest, direction, size) => shift("as", count, dest, direction, size),
+        Instruction::LSd(count, dest, direction, size) => shift("ls", count, dest, direction, size),
+        Instruction::ROXd(count, dest, direction, size) => {
+            shift("rox", count, dest, direction, size)
+        }
+        Instruction::ROd(count, dest, direction, size) => shift("ro", count, dest, direction, size),
+        Instruction::BTST(bit, target) => format!("btst {}, {}", op(bit), op(target)),
+        Instruction::BCHG(bit, target) => format!("bchg {}, {}", op(bit), op(target)),
+        Instruction::BCLR(bit, target) => format!("bclr {}, {}", op(bit), op(target)),
+        Instruction::BSET(bit, target) => format!("bset {}, {}", op(bit), op(target)),
+        Instruction::BFTST(target, field) => format!("bftst {}", format_bit_field(target, field)),
+        Instruction::BFCHG(target, field) => format!("bfchg {}", format_bit_field(target, field)),
+        Instruction::BFCLR(target, field) => format!("bfclr {}", format_bit_field(target, field)),
+        Instruction::BFSET(target, field) => format!("bfset {}", format_bit_field(target, field)),
+        Instruction::BFEXTU(src, field, dest) => {
+            format!("bfextu {}, {}", format_bit_field(src, field), reg(dest))
+        }
+        Instruction::BFEXTS(src, field, dest) => {
+            format!("bfexts {}, {}", format_bit_field(src, field), reg(dest))
+        }
+        Instruction::BFFFO(src, field, dest) => {
+            format!("bfffo {}, {}", format_bit_field(src, field), reg(dest))
+        }
+        Instruction::BFINS(src, dest, field) => {
+            format!("bfins {}, {}", reg(src), format_bit_field(dest, field))
+        }
+        Instruction::FMOVE(src, dest, format) => format!(
+            "fmove.{} {}, {}",
+            fp_format_suffix(*format),
+            format_fp_operand(src),
+            format_fp_operand(dest)
+        ),
+        Instruction::FMOVEtoCR(src, control) => {
+            format!(
+                "fmove.l {}, {}",
+                op(src),
+                format!("{:?}", control).to_lowercase()
+            )
+        }
+        Instruction::FMOVEfromCR(control, dest) => {
+            format!(
+                "fmove.l {}, {}",
+                format!("{:?}", control).to_lowercase(),
+                op(dest)
+            )
+        }
+        Instruction::FMOVECR(offset, dest) => format!("fmovecr #${:X}, fp{}", offset, dest),
+        Instruction::FArith(src, dest, operation, format) => format!(
+            "{}.{} {}, fp{}",
+            fp_operation_name(*operation),
+            fp_format_suffix(*format),
+            format_fp_operand(src),
+            dest
+        ),
+        Instruction::FCMP(src, dest, format) => format!(
+            "fcmp.{} {}, fp{}",
+            fp_format_suffix(*format),
+            format_fp_operand(src),
+            dest
+        ),
+        Instruction::FTST(src, format) => {
+            format!(
+                "ftst.{} {}",
+                fp_format_suffix(*format),
+                format_fp_operand(src)
+            )
+        }
+        Instruction::FBcc(target, condition) => {
+            format!(
+                "fb{} ${:X}",
+                FP_CONDITION_NAMES[*condition as usize], target
+            )
+        }
+        Instruction::FScc(dest, condition) => {
+            format!("fs{} {}", FP_CONDITION_NAMES[*condition as usize], op(dest))
+        }
+        Instruction::FDBcc(register, target, condition) => format!(
+            "fdb{} {}, ${:X}",
+            FP_CONDITION_NAMES[*condition as usize],
+            reg(register),
+            target
+        ),
+        Instruction::FNOP => "fnop".to_string(),
+        Instruction::TRAP(vector) => format!("trap #{}", vector),
+        Instruction::TRAPV => "trapv".to_string(),
+        Instruction::ILLEGAL => "illegal".to_string(),
+        Instruction::RTS => "rts".to_string(),
+        Instruction::RTR => "rtr".to_string(),
+        Instruction::RTE => "rte".to_string(),
+        Instruction::STOP(value) => format!("stop #${:X}", value),
+        Instruction::RTD(displacement) => format!("rtd #{}", displacement),
+        Instruction::RESET => "reset".to_string(),
+        Instruction::NOP => "nop".to_string(),
+    }
+}
@@ -0,0 +1,980 @@
+use crate::{
+    compiler::{CompilationError, CompilationResult},
+    instructions::{
+        BitField, BitFieldValue, ControlRegister, FpControlRegister, FpFormat, FpOperand,
+        FpOperation, IndexBase, IndexRegister, Instruction, MemoryIndirection, Operand,
+        RegisterOperand, ShiftDirection, Sign, Size, TargetDirection,
+    },
+    lexer::LexedSize,
+    math::f64_to_extended,
+};
+
+/**
+Encodes an instruction to its machine code, the opcode word followed by the extension words.
+The size written in the source is only used to pick the form of the branches
+ */
+pub fn encode_instruction(
+    instruction: &Instruction,
+    address: usize,
+    size: &LexedSize,
+) -> CompilationResult<Vec<u16>> {
+    let mut encoder = Encoder {
+        address,
+        words: Vec::with_capacity(4),
+    };
+    encoder.encode(instruction, size)?;
+    Ok(encoder.words)
+}
+
+//the 6 bit effective address field, the mode in the upper 3 bits and the register in the lower 3
+pub fn get_mode_register(mode: u16, register: u16) -> u16 {
+    (mode << 3) | (register & 0x7)
+}
+
+fn size_bits(size: Size) -> u16 {
+    let bits = match size {
+        Size::Byte => 0b00,
+        Size::Word => 0b01,
+        Size::Long => 0b10,
+    };
+    bits << 6
+}
+
+fn register_number(register: &RegisterOperand) -> u16 {
+    match register {
+        RegisterOperand::Address(reg) | RegisterOperand::Data(reg) => *reg as u16 & 0x7,
+    }
+}
+
+fn fits_byte(value: i64) -> bool {
+    (-128..=127).contains(&value)
+}
+
+fn fits_word(value: i64) -> bool {
+    (-32768..=32767).contains(&value)
+}
+
+//the index register fields shared by the brief and full extension words
+fn index_bits(index: &IndexRegister) -> u16 {
+    let long = match index.size {
+        Size::Long => 0x0800,
+        _ => 0,
+    };
+    (index.register.to_index() << 12) | long | ((index.scale.trailing_zeros() as u16 & 0x3) << 9)
+}
+
+pub fn fp_format_bits(format: FpFormat) -> u16 {
+    match format {
+        FpFormat::Long => 0b000,
+        FpFormat::Single => 0b001,
+        FpFormat::Extended => 0b010,
+        FpFormat::Word => 0b100,
+        FpFormat::Double => 0b101,
+        FpFormat::Byte => 0b110,
+    }
+}
+
+pub fn fp_operation_opmode(operation: FpOperation) -> u16 {
+    match operation {
+        FpOperation::Int => 0x01,
+        FpOperation::Sinh => 0x02,
+        FpOperation::IntRZ => 0x03,
+        FpOperation::Sqrt => 0x04,
+        FpOperation::Lognp1 => 0x06,
+        FpOperation::Etoxm1 => 0x08,
+        FpOperation::Tanh => 0x09,
+        FpOperation::Atan => 0x0A,
+        FpOperation::Asin => 0x0C,
+        FpOperation::Atanh => 0x0D,
+        FpOperation::Sin => 0x0E,
+        FpOperation::Tan => 0x0F,
+        FpOperation::Etox => 0x10,
+        FpOperation::Twotox => 0x11,
+        FpOperation::Tentox => 0x12,
+        FpOperation::Logn => 0x14,
+        FpOperation::Log10 => 0x15,
+        FpOperation::Log2 => 0x16,
+        FpOperation::Abs => 0x18,
+        FpOperation::Cosh => 0x19,
+        FpOperation::Neg => 0x1A,
+        FpOperation::Acos => 0x1C,
+        FpOperation::Cos => 0x1D,
+        FpOperation::GetExp => 0x1E,
+        FpOperation::GetMan => 0x1F,
+        FpOperation::Div => 0x20,
+        FpOperation::Mod => 0x21,
+        FpOperation::Add => 0x22,
+        FpOperation::Mul => 0x23,
+        FpOperation::Rem => 0x25,
+        FpOperation::Scale => 0x26,
+        FpOperation::Sub => 0x28,
+    }
+}
+
+pub fn control_register_bits(register: ControlRegister) -> u16 {
+    match register {
+        ControlRegister::SFC => 0x000,
+        ControlRegister::DFC => 0x001,
+        ControlRegister::USP => 0x800,
+        ControlRegister::VBR => 0x801,
+    }
+}
+
+pub fn fp_control_register_bits(register: FpControlRegister) -> u16 {
+    match register {
+        FpControlRegister::FPCR => 0b100,
+        FpControlRegister::FPSR => 0b010,
+        FpControlRegister::FPIAR => 0b001,
+    }
+}
+
+fn invalid_operands(name: &str) -> CompilationError {
+    CompilationError::InvalidAddressingMode(format!(
+        "Invalid operands for \"{}\", the instruction cannot be encoded",
+        name
+    ))
+}
+
+struct Encoder {
+    address: usize,
+    words: Vec<u16>,
+}
+
+impl Encoder {
+    fn push(&mut self, word: u16) {
+        self.words.push(word);
+    }
+    fn push_long(&mut self, value: u32) {
+        self.words.push((value >> 16) as u16);
+        self.words.push(value as u16);
+    }
+    //the byte immediates take a whole word, with the value in the low byte
+    fn push_sized(&mut self, value: u32, size: Size) {
+        match size {
+            Size::Byte => self.push(value as u16 & 0xFF),
+            Size::Word => self.push(value as u16),
+            Size::Long => self.push_long(value),
+        }
+    }
+    /**
+    The interpreter resolves the pc relative operands from the instruction address + 2, while the cpu
+    uses the address of the extension word, which is later when other extension words come first
+     */
+    fn pc_displacement(&self, offset: i32) -> i32 {
+        offset - (self.words.len() as i32 * 2 - 2)
+    }
+    fn branch_displacement(&self, target: u32) -> i64 {
+        target as i64 - (self.address as i64 + 2)
+    }
+    fn opcode_with_ea(&mut self, opcode: u16, op: &Operand, size: Size) -> CompilationResult<()> {
+        self.push(opcode);
+        let ea = self.effective_address(op, size)?;
+        self.words[0] |= ea;
+        Ok(())
+    }
+    /**
+    Appends the extension words of the operand and returns its 6 bit effective address field,
+    the 68020 full extension word is used when the displacement doesn't fit the 68000 formats
+     */
+    fn effective_address(&mut self, op: &Operand, size: Size) -> CompilationResult<u16> {
+        let ea = match op {
+            Operand::Register(RegisterOperand::Data(reg)) => get_mode_register(0b000, *reg as u16),
+            Operand::Register(RegisterOperand::Address(reg)) => {
+                get_mode_register(0b001, *reg as u16)
+            }
+            Operand::Indirect(reg) => get_mode_register(0b010, *reg as u16),
+            Operand::PostIndirect(reg) => get_mode_register(0b011, *reg as u16),
+            Operand::PreIndirect(reg) => get_mode_register(0b100, *reg as u16),
+            Operand::IndirectDisplacement {
+                offset,
+                base: RegisterOperand::Address(reg),
+            } => {
+                if fits_word(*offset as i64) {
+                    self.push(*offset as u16);
+                    get_mode_register(0b101, *reg as u16)
+                } else {
+                    let base = IndexBase::Register(RegisterOperand::Address(*reg));
+                    self.full_extension(&base, *offset, None, &MemoryIndirection::None);
+                    get_mode_register(0b110, *reg as u16)
+                }
+            }
+            Operand::IndirectIndex {
+                base: RegisterOperand::Address(reg),
+                offset,
+                index,
+            } => {
+                if fits_byte(*offset as i64) {
+                    self.push(index_bits(index) | (*offset as u8 as u16));
+                } else {
+                    let base = IndexBase::Register(RegisterOperand::Address(*reg));
+                    self.full_extension(&base, *offset, Some(index), &MemoryIndirection::None);
+                }
+                get_mode_register(0b110, *reg as u16)
+            }
+            Operand::PCDisplacement { offset } => {
+                let displacement = self.pc_displacement(*offset);
+                if !fits_word(displacement as i64) {
+                    return Err(CompilationError::InvalidAddressingMode(format!(
+                        "Pc relative displacement {} out of range",
+                        displacement
+                    )));
+                }
+                self.push(displacement as u16);
+                get_mode_register(0b111, 0b010)
+            }
+            Operand::PCIndex { offset, index } => {
+                let displacement = self.pc_displacement(*offset);
+                if fits_byte(displacement as i64) {
+                    self.push(index_bits(index) | (displacement as u8 as u16));
+                } else {
+                    self.full_extension(
+                        &IndexBase::PC,
+                        *offset,
+                        Some(index),
+                        &MemoryIndirection::None,
+                    );
+                }
+                get_mode_register(0b111, 0b011)
+            }
+            Operand::FullIndex {
+                base,
+                base_displacement,
+                index,
+                indirection,
+            } => {
+                self.full_extension(base, *base_displacement, index.as_ref(), indirection);
+                match base {
+                    IndexBase::Register(register) => {
+                        get_mode_register(0b110, register_number(register))
+                    }
+                    IndexBase::PC => get_mode_register(0b111, 0b011),
+                    IndexBase::Suppressed => get_mode_register(0b110, 0),
+                }
+            }
+            //the .w addresses are sign extended, so they cover the first and last 32kb
+            Operand::Absolute(address) if fits_word(*address as u32 as i32 as i64) => {
+                self.push(*address as u16);
+                get_mode_register(0b111, 0b000)
+            }
+            Operand::AbsoluteShort(address) => {
+                self.push(*address as u16);
+                get_mode_register(0b111, 0b000)
+            }
+            Operand::Absolute(address) | Operand::AbsoluteLong(address) => {
+                self.push_long(*address as u32);
+                get_mode_register(0b111, 0b001)
+            }
+            Operand::Immediate(value) => {
+                self.push_sized(*value, size);
+                get_mode_register(0b111, 0b100)
+            }
+            _ => {
+                return Err(CompilationError::InvalidAddressingMode(format!(
+                    "Operand {:?} cannot be encoded, the base must be an address register",
+                    op
+                )))
+            }
+        };
+        Ok(ea)
+    }
+    /**
+    Appends the 68020 full extension word followed by the base and outer displacements,
+    each displacement uses the smallest of the null, word and long sizes
+     */
+    fn full_extension(
+        &mut self,
+        base: &IndexBase,
+        base_displacement: i32,
+        index: Option<&IndexRegister>,
+        indirection: &MemoryIndirection,
+    ) {
+        let displacement_size = |value: i32| -> u16 {
+            match value as i64 {
+                0 => 0b01,
+                v if fits_word(v) => 0b10,
+                _ => 0b11,
+            }
+        };
+        let base_displacement = match base {
+            IndexBase::PC => self.pc_displacement(base_displacement),
+            _ => base_displacement,
+        };
+        let mut extension = 0x0100 | (displacement_size(base_displacement) << 4);
+        match index {
+            Some(index) => extension |= index_bits(index),
+            None => extension |= 0x0040,
+        }
+        if let IndexBase::Suppressed = base {
+            extension |= 0x0080;
+        }
+        let outer_displacement = match indirection {
+            MemoryIndirection::None => None,
+            MemoryIndirection::PreIndexed(outer) => {
+                extension |= displacement_size(*outer);
+                Some(*outer)
+            }
+            MemoryIndirection::PostIndexed(outer) => {
+                //without an index there is no difference between pre and post indexing
+                let post = if index.is_some() { 0b100 } else { 0 };
+                extension |= post | displacement_size(*outer);
+                Some(*outer)
+            }
+        };
+        self.push(extension);
+        for displacement in [Some(base_displacement), outer_displacement]
+            .into_iter()
+            .flatten()
+        {
+            match displacement_size(displacement) {
+                0b01 => {}
+                0b10 => self.push(displacement as u16),
+                _ => self.push_long(displacement as u32),
+            }
+        }
+    }
+    fn branch(&mut self, opcode: u16, target: u32, size: &LexedSize) -> CompilationResult<()> {
+        let displacement = self.branch_displacement(target);
+        //a displacement of 0 or -1 in the opcode means that a word or long displacement follows
+        let fits_short = fits_byte(displacement) && displacement != 0 && displacement != -1;
+        let short = match size {
+            LexedSize::Short | LexedSize::Byte => {
+                if !fits_short {
+                    return Err(CompilationError::InvalidAddressingMode(format!(
+                        "Branch displacement {} out of range for the short form",
+                        displacement
+                    )));
+                }
+                true
+            }
+            LexedSize::Unspecified => fits_short,
+            _ => false,
+        };
+        if short {
+            self.push(opcode | (displacement as u8 as u16));
+        } else if fits_word(displacement) {
+            self.push(opcode);
+            self.push(displacement as u16);
+        } else {
+            return Err(CompilationError::InvalidAddressingMode(format!(
+                "Branch displacement {} out of range",
+                displacement
+            )));
+        }
+        Ok(())
+    }
+    fn dbcc_displacement(&self, target: u32, from: usize) -> CompilationResult<u16> {
+        let displacement = target as i64 - from as i64;
+        if !fits_word(displacement) {
+            return Err(CompilationError::InvalidAddressingMode(format!(
+                "Branch displacement {} out of range",
+                displacement
+            )));
+        }
+        Ok(displacement as u16)
+    }
+    //the shared encoding of ADD, SUB, AND and OR, the data register is either the source or the destination
+    fn arithmetic(
+        &mut self,
+        name: &str,
+        opcode: u16,
+        immediate_opcode: u16,
+        src: &Operand,
+        dest: &Operand,
+        size: Size,
+    ) -> CompilationResult<()> {
+        match (src, dest) {
+            (_, Operand::Register(RegisterOperand::Data(reg))) => {
+                self.opcode_with_ea(opcode | ((*reg as u16) << 9) | size_bits(size), src, size)
+            }
+            (Operand::Register(RegisterOperand::Data(reg)), _) => self.opcode_with_ea(
+                opcode | ((*reg as u16) << 9) | 0x0100 | size_bits(size),
+                dest,
+                size,
+            ),
+            (Operand::Immediate(value), _) => self.immediate(immediate_opcode, *value, dest, size),
+            _ => Err(invalid_operands(name)),
+        }
+    }
+    fn immediate(
+        &mut self,
+        opcode: u16,
+        value: u32,
+        dest: &Operand,
+        size: Size,
+    ) -> CompilationResult<()> {
+        self.push(opcode | size_bits(size));
+        self.push_sized(value, size);
+        let ea = self.effective_address(dest, size)?;
+        self.words[0] |= ea;
+        Ok(())
+    }
+    //ADDX, SUBX, ABCD and SBCD, between two data registers or two predecrement operands
+    fn extended(
+        &mut self,
+        name: &str,
+        opcode: u16,
+        src: &Operand,
+        dest: &Operand,
+    ) -> CompilationResult<()> {
+        let word = match (src, dest) {
+            (
+                Operand::Register(RegisterOperand::Data(y)),
+                Operand::Register(RegisterOperand::Data(x)),
+            ) => opcode | ((*x as u16) << 9) | *y as u16,
+            (Operand::PreIndirect(y), Operand::PreIndirect(x)) => {
+                opcode | 0x0008 | ((*x as u16) << 9) | *y as u16
+            }
+            _ => return Err(invalid_operands(name)),
+        };
+        self.push(word);
+        Ok(())
+    }
+    fn shift(
+        &mut self,
+        kind: u16,
+        count: &Operand,
+        dest: &Operand,
+        direction: &ShiftDirection,
+        size: Size,
+    ) -> CompilationResult<()> {
+        let direction = match direction {
+            ShiftDirection::Right => 0,
+            ShiftDirection::Left => 0x0100,
+        };
+        match (count, dest) {
+            (Operand::Immediate(count), Operand::Register(RegisterOperand::Data(reg))) => {
+                self.push(
+                    0xE000
+                        | ((*count as u16 & 0x7) << 9)
+                        | direction
+                        | size_bits(size)
+                        | (kind << 3)
+                        | *reg as u16,
+                );
+                Ok(())
+            }
+            (
+                Operand::Register(RegisterOperand::Data(count)),
+                Operand::Register(RegisterOperand::Data(reg)),
+            ) => {
+                self.push(
+                    0xE020
+                        | ((*count as u16) << 9)
+                        | direction
+                        | size_bits(size)
+                        | (kind << 3)
+                        | *reg as u16,
+                );
+                Ok(())
+            }
+            //the memory form shifts a word by one bit
+            (Operand::Immediate(1), _) => {
+                self.opcode_with_ea(0xE0C0 | (kind << 9) | direction, dest, Size::Word)
+            }
+            _ => Err(invalid_operands("shift")),
+        }
+    }
+    fn bit_operation(
+        &mut self,
+        kind: u16,
+        bit: &Operand,
+        target: &Operand,
+    ) -> CompilationResult<()> {
+        match bit {
+            Operand::Register(RegisterOperand::Data(reg)) => self.opcode_with_ea(
+                0x0100 | ((*reg as u16) << 9) | (kind << 6),
+                target,
+                Size::Byte,
+            ),
+            Operand::Immediate(bit) => {
+                self.push(0x0800 | (kind << 6));
+                self.push(*bit as u16 & 0xFF);
+                let ea = self.effective_address(target, Size::Byte)?;
+                self.words[0] |= ea;
+                Ok(())
+            }
+            _ => Err(invalid_operands("bit operation")),
+        }
+    }
+    fn bit_field(
+        &mut self,
+        opcode: u16,
+        op: &Operand,
+        field: &BitField,
+        register: u16,
+    ) -> CompilationResult<()> {
+        let offset = match field.offset {
+            BitFieldValue::Immediate(offset) => (offset as u16 & 0x1F) << 6,
+            BitFieldValue::Register(reg) => 0x0800 | (register_number(&reg) << 6),
+        };
+        //a width of 32 is encoded as 0
+        let width = match field.width {
+            BitFieldValue::Immediate(width) => width as u16 & 0x1F,
+            BitFieldValue::Register(reg) => 0x0020 | register_number(&reg),
+        };
+        self.push(opcode);
+        self.push((register << 12) | offset | width);
+        let ea = self.effective_address(op, Size::Long)?;
+        self.words[0] |= ea;
+        Ok(())
+    }
+    //the general coprocessor instruction with the command word, the source is a fp register, an operand or an immediate
+    fn fp_general(
+        &mut self,
+        src: &FpOperand,
+        format: FpFormat,
+        command: u16,
+    ) -> CompilationResult<()> {
+        match src {
+            FpOperand::Register(reg) => {
+                self.push(0xF200);
+                self.push(((*reg as u16) << 10) | command);
+            }
+            FpOperand::Operand(op) => {
+                self.push(0xF200);
+                self.push(0x4000 | (fp_format_bits(format) << 10) | command);
+                let size = format.to_integer_size().unwrap_or(Size::Long);
+                let ea = self.effective_address(op, size)?;
+                self.words[0] |= ea;
+            }
+            FpOperand::Immediate(value) => {
+                self.push(0xF200 | get_mode_register(0b111, 0b100));
+                self.push(0x4000 | (fp_format_bits(format) << 10) | command);
+                self.fp_immediate(*value, format);
+            }
+        }
+        Ok(())
+    }
+    fn fp_immediate(&mut self, value: f64, format: FpFormat) {
+        match format {
+            FpFormat::Byte => self.push_sized(value as i8 as u32, Size::Byte),
+            FpFormat::Word => self.push_sized(value as i16 as u32, Size::Word),
+            FpFormat::Long => self.push_long(value as i32 as u32),
+            FpFormat::Single => self.push_long((value as f32).to_bits()),
+            FpFormat::Double => {
+                let bits = value.to_bits();
+                self.push_long((bits >> 32) as u32);
+                self.push_long(bits as u32);
+            }
+            FpFormat::Extended => {
+                for word in f64_to_extended(value).chunks(2) {
+                    self.push(u16::from_be_bytes([word[0], word[1]]));
+                }
+            }
+        }
+    }
+    fn encode(
+        &mut self,
+        instruction: &Instruction,
+        lexed_size: &LexedSize,
+    ) -> CompilationResult<()> {
+        match instruction {
+            Instruction::MOVE(src, dest, size) => {
+                let opcode = match size {
+                    Size::Byte => 0x1000,
+                    Size::Word => 0x3000,
+                    Size::Long => 0x2000,
+                };
+                self.opcode_with_ea(opcode, src, *size)?;
+                //the destination has the register and mode fields swapped
+                let dest = self.effective_address(dest, *size)?;
+                self.words[0] |= ((dest & 0x7) << 9) | ((dest >> 3) << 6);
+            }
+            Instruction::MOVEA(src, dest, size) => {
+                let opcode = match size {
+                    Size::Long => 0x2040,
+                    _ => 0x3040,
+                };
+                self.opcode_with_ea(opcode | (register_number(dest) << 9), src, *size)?;
+            }
+            Instruction::ADDA(src, dest, size)
+            | Instruction::SUBA(src, dest, size)
+            | Instruction::CMPA(src, dest, size) => {
+                let opcode = match instruction {
+                    Instruction::ADDA(..) => 0xD0C0,
+                    Instruction::SUBA(..) => 0x90C0,
+                    _ => 0xB0C0,
+                };
+                let long = match size {
+                    Size::Long => 0x0100,
+                    _ => 0,
+                };
+                self.opcode_with_ea(opcode | long | (register_number(dest) << 9), src, *size)?;
+            }
+            Instruction::MOVEM {
+                direction,
+                size,
+                registers_mask,
+                target,
+            } => {
+                let direction = match direction {
+                    TargetDirection::ToMemory => 0,
+                    TargetDirection::FromMemory => 0x0400,
+                };
+                let long = match size {
+                    Size::Long => 0x0040,
+                    _ => 0,
+                };
+                self.push(0x4880 | direction | long);
+                self.push(*registers_mask);
+                let ea = self.effective_address(target, *size)?;
+                self.words[0] |= ea;
+            }
+            Instruction::MOVEP(target, reg, direction, size) => {
+                let (base, offset) = match target {
+                    Operand::Indirect(base) => (*base as u16, 0),
+                    Operand::IndirectDisplacement {
+                        offset,
+                        base: RegisterOperand::Address(base),
+                    } => (*base as u16, *offset),
+                    _ => return Err(invalid_operands("movep")),
+                };
+                let opmode = match (direction, size) {
+                    (TargetDirection::FromMemory, Size::Long) => 0b101,
+                    (TargetDirection::FromMemory, _) => 0b100,
+                    (TargetDirection::ToMemory, Size::Long) => 0b111,
+                    (TargetDirection::ToMemory, _) => 0b110,
+                };
+                self.push(0x0008 | (register_number(reg) << 9) | (opmode << 6) | base);
+                self.push(offset as u16);
+            }
+            Instruction::MOVEtoSR(op) => self.opcode_with_ea(0x46C0, op, Size::Word)?,
+            Instruction::MOVEfromSR(op) => self.opcode_with_ea(0x40C0, op, Size::Word)?,
+            Instruction::MOVEtoCCR(op) => self.opcode_with_ea(0x44C0, op, Size::Word)?,
+            Instruction::MOVEfromCCR(op) => self.opcode_with_ea(0x42C0, op, Size::Word)?,
+            Instruction::MOVEtoUSP(reg) => self.push(0x4E60 | register_number(reg)),
+            Instruction::MOVEfromUSP(reg) => self.push(0x4E68 | register_number(reg)),
+            Instruction::MOVECtoCR(reg, control) => {
+                self.push(0x4E7B);
+                self.push((reg.to_index() << 12) | control_register_bits(*control));
+            }
+            Instruction::MOVECfromCR(control, reg) => {
+                self.push(0x4E7A);
+                self.push((reg.to_index() << 12) | control_register_bits(*control));
+            }
+            Instruction::MOVES(target, reg, direction, size) => {
+                let direction = match direction {
+                    TargetDirection::ToMemory => 0x0800,
+                    TargetDirection::FromMemory => 0,
+                };
+                self.push(0x0E00 | size_bits(*size));
+                self.push((reg.to_index() << 12) | direction);
+                let ea = self.effective_address(target, *size)?;
+                self.words[0] |= ea;
+            }
+            Instruction::ORItoCCR(value) => {
+                self.push(0x003C);
+                self.push(*value as u16);
+            }
+            Instruction::ANDItoCCR(value) => {
+                self.push(0x023C);
+                self.push(*value as u16);
+            }
+            Instruction::EORItoCCR(value) => {
+                self.push(0x0A3C);
+                self.push(*value as u16);
+            }
+            Instruction::ORItoSR(value) => {
+                self.push(0x007C);
+                self.push(*value);
+            }
+            Instruction::ANDItoSR(value) => {
+                self.push(0x027C);
+                self.push(*value);
+            }
+            Instruction::EORItoSR(value) => {
+                self.push(0x0A7C);
+                self.push(*value);
+            }
+            Instruction::ADD(src, dest, size) => {
+                self.arithmetic("add", 0xD000, 0x0600, src, dest, *size)?
+            }
+            Instruction::SUB(src, dest, size) => {
+                self.arithmetic("sub", 0x9000, 0x0400, src, dest, *size)?
+            }
+            Instruction::AND(src, dest, size) => {
+                self.arithmetic("and", 0xC000, 0x0200, src, dest, *size)?
+            }
+            Instruction::OR(src, dest, size) => {
+                self.arithmetic("or", 0x8000, 0x0000, src, dest, *size)?
+            }
+            //the data register is always the source of EOR
+            Instruction::EOR(src, dest, size) => match src {
+                Operand::Register(RegisterOperand::Data(reg)) => self.opcode_with_ea(
+                    0xB100 | ((*reg as u16) << 9) | size_bits(*size),
+                    dest,
+                    *size,
+                )?,
+                Operand::Immediate(value) => self.immediate(0x0A00, *value, dest, *size)?,
+                _ => return Err(invalid_operands("eor")),
+            },
+            Instruction::ADDX(src, dest, size) => {
+                self.extended("addx", 0xD100 | size_bits(*size), src, dest)?
+            }
+            Instruction::SUBX(src, dest, size) => {
+                self.extended("subx", 0x9100 | size_bits(*size), src, dest)?
+            }
+            Instruction::ABCD(src, dest) => self.extended("abcd", 0xC100, src, dest)?,
+            Instruction::SBCD(src, dest) => self.extended("sbcd", 0x8100, src, dest)?,
+            Instruction::NEGX(op, size) => {
+                self.opcode_with_ea(0x4000 | size_bits(*size), op, *size)?
+            }
+            Instruction::CLR(op, size) => {
+                self.opcode_with_ea(0x4200 | size_bits(*size), op, *size)?
+            }
+            Instruction::NEG(op, size) => {
+                self.opcode_with_ea(0x4400 | size_bits(*size), op, *size)?
+            }
+            Instruction::NOT(op, size) => {
+                self.opcode_with_ea(0x4600 | size_bits(*size), op, *size)?
+            }
+            Instruction::TST(op, size) => {
+                self.opcode_with_ea(0x4A00 | size_bits(*size), op, *size)?
+            }
+            Instruction::NBCD(op) => self.opcode_with_ea(0x4800, op, Size::Byte)?,
+            Instruction::TAS(op) => self.opcode_with_ea(0x4AC0, op, Size::Byte)?,
+            Instruction::ADDQ(value, op, size) => self.opcode_with_ea(
+                0x5000 | ((*value as u16 & 0x7) << 9) | size_bits(*size),
+                op,
+                *size,
+            )?,
+            Instruction::SUBQ(value, op, size) => self.opcode_with_ea(
+                0x5100 | ((*value as u16 & 0x7) << 9) | size_bits(*size),
+                op,
+                *size,
+            )?,
+            Instruction::MOVEQ(value, reg) => {
+                self.push(0x7000 | (register_number(reg) << 9) | *value as u16)
+            }
+            Instruction::ORI(value, op, size) => self.immediate(0x0000, *value, op, *size)?,
+            Instruction::ANDI(value, op, size) => self.immediate(0x0200, *value, op, *size)?,
+            Instruction::SUBI(value, op, size) => self.immediate(0x0400, *value, op, *size)?,
+            Instruction::ADDI(value, op, size) => self.immediate(0x0600, *value, op, *size)?,
+            Instruction::EORI(value, op, size) => self.immediate(0x0A00, *value, op, *size)?,
+            Instruction::CMPI(value, op, size) => self.immediate(0x0C00, *value, op, *size)?,
+            Instruction::CMPM(src, dest, size) => match (src, dest) {
+                (Operand::PostIndirect(y), Operand::PostIndirect(x)) => {
+                    self.push(0xB108 | ((*x as u16) << 9) | size_bits(*size) | *y as u16)
+                }
+                _ => return Err(invalid_operands("cmpm")),
+            },
+            Instruction::CMP(src, reg, size) => self.opcode_with_ea(
+                0xB000 | (register_number(reg) << 9) | size_bits(*size),
+                src,
+                *size,
+            )?,
+            Instruction::DIVx(src, reg, sign) | Instruction::MULx(src, reg, sign) => {
+                let opcode = match (instruction, sign) {
+                    (Instruction::DIVx(..), Sign::Unsigned) => 0x80C0,
+                    (Instruction::DIVx(..), Sign::Signed) => 0x81C0,
+                    (_, Sign::Unsigned) => 0xC0C0,
+                    (_, Sign::Signed) => 0xC1C0,
+                };
+                self.opcode_with_ea(opcode | (register_number(reg) << 9), src, Size::Word)?
+            }
+            Instruction::MULxL(src, low, high, sign) => {
+                let signed = match sign {
+                    Sign::Signed => 0x0800,
+                    Sign::Unsigned => 0,
+                };
+                let high = match high {
+                    Some(high) => 0x0400 | register_number(high),
+                    None => 0,
+                };
+                self.push(0x4C00);
+                self.push((register_number(low) << 12) | signed | high);
+                let ea = self.effective_address(src, Size::Long)?;
+                self.words[0] |= ea;
+            }
+            Instruction::DIVxL(src, quotient, remainder, is_64, sign) => {
+                let signed = match sign {
+                    Sign::Signed => 0x0800,
+                    Sign::Unsigned => 0,
+                };
+                let is_64 = if *is_64 { 0x0400 } else { 0 };
+                self.push(0x4C40);
+                self.push(
+                    (register_number(quotient) << 12) | signed | is_64 | register_number(remainder),
+                );
+                let ea = self.effective_address(src, Size::Long)?;
+                self.words[0] |= ea;
+            }
+            Instruction::CHK(src, reg, size) => {
+                let opcode = match size {
+                    Size::Long => 0x4100,
+                    _ => 0x4180,
+                };
+                self.opcode_with_ea(opcode | (register_number(reg) << 9), src, *size)?
+            }
+            Instruction::SWAP(reg) => self.push(0x4840 | register_number(reg)),
+            Instruction::EXG(first, second) => {
+                let word = match (first, second) {
+                    (RegisterOperand::Data(x), RegisterOperand::Data(y)) => {
+                        0xC140 | ((*x as u16) << 9) | *y as u16
+                    }
+                    (RegisterOperand::Address(x), RegisterOperand::Address(y)) => {
+                        0xC148 | ((*x as u16) << 9) | *y as u16
+                    }
+                    //the data register is always in the first field
+                    (RegisterOperand::Data(x), RegisterOperand::Address(y))
+                    | (RegisterOperand::Address(y), RegisterOperand::Data(x)) => {
+                        0xC188 | ((*x as u16) << 9) | *y as u16
+                    }
+                };
+                self.push(word);
+            }
+            Instruction::LEA(src, reg) => {
+                self.opcode_with_ea(0x41C0 | (register_number(reg) << 9), src, Size::Long)?
+            }
+            Instruction::PEA(src) => self.opcode_with_ea(0x4840, src, Size::Long)?,
+            Instruction::EXT(reg, from, to) => {
+                let opcode = match (from, to) {
+                    (Size::Byte, Size::Word) => 0x4880,
+                    (Size::Word, Size::Long) => 0x48C0,
+                    (Size::Byte, Size::Long) => 0x49C0,
+                    _ => return Err(invalid_operands("ext")),
+                };
+                self.push(opcode | register_number(reg));
+            }
+            Instruction::Bcc(target, condition) => {
+                self.branch(0x6000 | ((*condition as u16) << 8), *target, lexed_size)?
+            }
+            Instruction::BRA(target) => self.branch(0x6000, *target, lexed_size)?,
+            Instruction::BSR(target) => self.branch(0x6100, *target, lexed_size)?,
+            Instruction::Scc(op, condition) => {
+                self.opcode_with_ea(0x50C0 | ((*condition as u16) << 8), op, Size::Byte)?
+            }
+            Instruction::DBcc(reg, target, condition) => {
+                let displacement = self.dbcc_displacement(*target, self.address + 2)?;
+                self.push(0x50C8 | ((*condition as u16) << 8) | register_number(reg));
+                self.push(displacement);
+            }
+            Instruction::LINK(reg, displacement) => {
+                if fits_word(*displacement as i32 as i64) {
+                    self.push(0x4E50 | register_number(reg));
+                    self.push(*displacement as u16);
+                } else {
+                    self.push(0x4808 | register_number(reg));
+                    self.push_long(*displacement);
+                }
+            }
+            Instruction::UNLK(reg) => self.push(0x4E58 | register_number(reg)),
+            Instruction::JSR(op) => self.opcode_with_ea(0x4E80, op, Size::Long)?,
+            Instruction::JMP(op) => self.opcode_with_ea(0x4EC0, op, Size::Long)?,
+            Instruction::ASd(count, dest, direction, size) => {
+                self.shift(0b00, count, dest, direction, *size)?
+            }
+            Instruction::LSd(count, dest, direction, size) => {
+                self.shift(0b01, count, dest, direction, *size)?
+            }
+            Instruction::ROXd(count, dest, direction, size) => {
+                self.shift(0b10, count, dest, direction, *size)?
+            }
+            Instruction::ROd(count, dest, direction, size) => {
+                self.shift(0b11, count, dest, direction, *size)?
+            }
+            Instruction::BTST(bit, target) => self.bit_operation(0b00, bit, target)?,
+            Instruction::BCHG(bit, target) => self.bit_operation(0b01, bit, target)?,
+            Instruction::BCLR(bit, target) => self.bit_operation(0b10, bit, target)?,
+            Instruction::BSET(bit, target) => self.bit_operation(0b11, bit, target)?,
+            Instruction::BFTST(op, field) => self.bit_field(0xE8C0, op, field, 0)?,
+            Instruction::BFCHG(op, field) => self.bit_field(0xEAC0, op, field, 0)?,
+            Instruction::BFCLR(op, field) => self.bit_field(0xECC0, op, field, 0)?,
+            Instruction::BFSET(op, field) => self.bit_field(0xEEC0, op, field, 0)?,
+            Instruction::BFEXTU(op, field, reg) => {
+                self.bit_field(0xE9C0, op, field, register_number(reg))?
+            }
+            Instruction::BFEXTS(op, field, reg) => {
+                self.bit_field(0xEBC0, op, field, register_number(reg))?
+            }
+            Instruction::BFFFO(op, field, reg) => {
+                self.bit_field(0xEDC0, op, field, register_number(reg))?
+            }
+            Instruction::BFINS(reg, op, field) => {
+                self.bit_field(0xEFC0, op, field, register_number(reg))?
+            }
+            Instruction::FMOVE(src, dest, format) => match (src, dest) {
+                (_, FpOperand::Register(dest)) => {
+                    self.fp_general(src, *format, (*dest as u16) << 7)?
+                }
+                (FpOperand::Register(src), FpOperand::Operand(op)) => {
+                    self.push(0xF200);
+                    self.push(0x6000 | (fp_format_bits(*format) << 10) | ((*src as u16) << 7));
+                    let size = format.to_integer_size().unwrap_or(Size::Long);
+                    let ea = self.effective_address(op, size)?;
+                    self.words[0] |= ea;
+                }
+                _ => return Err(invalid_operands("fmove")),
+            },
+            Instruction::FMOVEtoCR(op, register) => {
+                self.push(0xF200);
+                self.push(0x8000 | (fp_control_register_bits(*register) << 10));
+                let ea = self.effective_address(op, Size::Long)?;
+                self.words[0] |= ea;
+            }
+            Instruction::FMOVEfromCR(register, op) => {
+                self.push(0xF200);
+                self.push(0xA000 | (fp_control_register_bits(*register) << 10));
+                let ea = self.effective_address(op, Size::Long)?;
+                self.words[0] |= ea;
+            }
+            Instruction::FMOVECR(offset, dest) => {
+                self.push(0xF200);
+                self.push(0x5C00 | ((*dest as u16) << 7) | (*offset as u16 & 0x7F));
+            }
+            Instruction::FArith(src, dest, operation, format) => self.fp_general(
+                src,
+                *format,
+                ((*dest as u16) << 7) | fp_operation_opmode(*operation),
+            )?,
+            Instruction::FCMP(src, dest, format) => {
+                self.fp_general(src, *format, ((*dest as u16) << 7) | 0x38)?
+            }
+            Instruction::FTST(src, format) => self.fp_general(src, *format, 0x3A)?,
+            Instruction::FBcc(target, condition) => {
+                let displacement = self.branch_displacement(*target);
+                if fits_word(displacement) && *lexed_size != LexedSize::Long {
+                    self.push(0xF280 | *condition as u16);
+                    self.push(displacement as u16);
+                } else {
+                    self.push(0xF2C0 | *condition as u16);
+                    self.push_long(displacement as u32);
+                }
+            }
+            Instruction::FScc(op, condition) => {
+                self.push(0xF240);
+                self.push(*condition as u16);
+                let ea = self.effective_address(op, Size::Byte)?;
+                self.words[0] |= ea;
+            }
+            //the displacement is relative to its own extension word, after the condition
+            Instruction::FDBcc(reg, target, condition) => {
+                let displacement = self.dbcc_displacement(*target, self.address + 4)?;
+                self.push(0xF248 | register_number(reg));
+                self.push(*condition as u16);
+                self.push(displacement);
+            }
+            Instruction::FNOP => {
+                self.push(0xF280);
+                self.push(0x0000);
+            }
+            Instruction::TRAP(vector) => self.push(0x4E40 | (*vector as u16 & 0xF)),
+            Instruction::STOP(value) => {
+                self.push(0x4E72);
+                self.push(*value);
+            }
+            Instruction::RTD(displacement) => {
+                self.push(0x4E74);
+                self.push(*displacement as u16);
+            }
+            Instruction::RESET => self.push(0x4E70),
+            Instruction::NOP => self.push(0x4E71),
+            Instruction::RTE => self.push(0x4E73),
+            Instruction::RTS => self.push(0x4E75),
+            Instruction::TRAPV => self.push(0x4E76),
+            Instruction::RTR => self.push(0x4E77),
+            Instruction::ILLEGAL => self.push(0x4AFC),
+        }
+        Ok(())
+    }
+}
@@ -18,6 +18,48 @@ pub enum TargetDirection {
     FromMemory,
 }
 
+//the models are ordered, so a later model supports every instruction of the previous ones
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Default)]
+pub enum CpuModel {
+    #[default]
+    M68000,
+    M68010,
+    M68020,
+}
+
+impl CpuModel {
+    pub fn get_name(&self) -> String {
+        match self {
+            CpuModel::M68000 => "68000",
+            CpuModel::M68010 => "68010",
+            CpuModel::M68020 => "68020",
+        }
+        .to_string()
+    }
+}
+
+//the floating point coprocessor is optional, both models run the same instructions
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Default)]
+pub enum FpuModel {
+    #[default]
+    None,
+    M68881,
+    M68882,
+}
+
+impl FpuModel {
+    pub fn get_name(&self) -> String {
+        match self {
+            FpuModel::None => "none",
+            FpuModel::M68881 => "68881",
+            FpuModel::M68882 => "68882",
+        }
+        .to_string()
+    }
+}
+
 impl Size {
     #[inline(always)]
     pub fn to_bytes(&self) -> usize {
@@ -47,10 +89,25 @@ impl RegisterOperand {
 #[derive(Debug, Clone, Serialize, Copy)]
 pub struct IndexRegister {
     pub register: RegisterOperand,
-    //pub scale: u8,
+    pub scale: u8,
     pub size: Size,
 }
 
+#[derive(Debug, Clone, Serialize, Copy)]
+pub enum IndexBase {
+    Register(RegisterOperand),
+    PC,
+    Suppressed,
+}
+
+//the outer displacement is added to the address read from memory
+#[derive(Debug, Clone, Serialize, Copy)]
+pub enum MemoryIndirection {
+    None,
+    PreIndexed(i32),
+    PostIndexed(i32),
+}
+
 #[derive(Debug, Clone, Serialize, Copy)]
 pub enum Operand {
     Immediate(u32),
@@ -67,9 +124,271 @@ pub enum Operand {
         offset: i32,
         index: IndexRegister,
     },
+    //the offset is relative to the extension word, at the instruction address + 2
+    PCDisplacement {
+        offset: i32,
+    },
+    PCIndex {
+        offset: i32,
+        index: IndexRegister,
+    },
+    //68020 full extension word, the base and index can be suppressed and the address can be read from memory
+    FullIndex {
+        base: IndexBase,
+        base_displacement: i32,
+        index: Option<IndexRegister>,
+        indirection: MemoryIndirection,
+    },
 
     Absolute(usize),
+    //the .w addresses are stored already sign extended
+    AbsoluteShort(usize),
+    AbsoluteLong(usize),
+}
+//the offset and width of a bit field, either known at compile time or read from a data register
+#[derive(Debug, Clone, Serialize, Copy)]
+pub enum BitFieldValue {
+    Immediate(u8),
+    Register(RegisterOperand),
+}
+
+#[derive(Debug, Clone, Serialize, Copy)]
+pub struct BitField {
+    pub offset: BitFieldValue,
+    pub width: BitFieldValue,
+}
+//the formats of the floating point operands, the integer ones are converted when loaded
+#[derive(Debug, Clone, Copy, Serialize, Eq, PartialEq)]
+pub enum FpFormat {
+    Byte,
+    Word,
+    Long,
+    Single,
+    Double,
+    Extended,
 }
+
+impl FpFormat {
+    pub fn to_bytes(&self) -> usize {
+        match self {
+            FpFormat::Byte => 1,
+            FpFormat::Word => 2,
+            FpFormat::Long | FpFormat::Single => 4,
+            FpFormat::Double => 8,
+            FpFormat::Extended => 12,
+        }
+    }
+    pub fn to_integer_size(&self) -> Option<Size> {
+        match self {
+            FpFormat::Byte => Some(Size::Byte),
+            FpFormat::Word => Some(Size::Word),
+            FpFormat::Long => Some(Size::Long),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Copy)]
+pub enum FpOperand {
+    Register(u8),
+    Immediate(f64),
+    Operand(Operand),
+}
+
+//control registers of the 68010 accessed with MOVEC, the function codes select the address space of MOVES
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum ControlRegister {
+    SFC,
+    DFC,
+    USP,
+    VBR,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum FpControlRegister {
+    FPCR,
+    FPSR,
+    FPIAR,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Eq, PartialEq)]
+pub enum FpOperation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Rem,
+    Scale,
+    Abs,
+    Neg,
+    Sqrt,
+    Int,
+    IntRZ,
+    GetExp,
+    GetMan,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Atanh,
+    Etox,
+    Etoxm1,
+    Twotox,
+    Tentox,
+    Logn,
+    Lognp1,
+    Log10,
+    Log2,
+}
+
+impl FromStr for FpOperation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<FpOperation, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "fadd" => FpOperation::Add,
+            "fsub" => FpOperation::Sub,
+            "fmul" => FpOperation::Mul,
+            "fdiv" => FpOperation::Div,
+            "fmod" => FpOperation::Mod,
+            "frem" => FpOperation::Rem,
+            "fscale" => FpOperation::Scale,
+            "fabs" => FpOperation::Abs,
+            "fneg" => FpOperation::Neg,
+            "fsqrt" => FpOperation::Sqrt,
+            "fint" => FpOperation::Int,
+            "fintrz" => FpOperation::IntRZ,
+            "fgetexp" => FpOperation::GetExp,
+            "fgetman" => FpOperation::GetMan,
+            "fsin" => FpOperation::Sin,
+            "fcos" => FpOperation::Cos,
+            "ftan" => FpOperation::Tan,
+            "fasin" => FpOperation::Asin,
+            "facos" => FpOperation::Acos,
+            "fatan" => FpOperation::Atan,
+            "fsinh" => FpOperation::Sinh,
+            "fcosh" => FpOperation::Cosh,
+            "ftanh" => FpOperation::Tanh,
+            "fatanh" => FpOperation::Atanh,
+            "fetox" => FpOperation::Etox,
+            "fetoxm1" => FpOperation::Etoxm1,
+            "ftwotox" => FpOperation::Twotox,
+            "ftentox" => FpOperation::Tentox,
+            "flogn" => FpOperation::Logn,
+            "flognp1" => FpOperation::Lognp1,
+            "flog10" => FpOperation::Log10,
+            "flog2" => FpOperation::Log2,
+            _ => return Err(format!("Invalid floating point operation: {}", s)),
+        })
+    }
+}
+
+impl FpOperation {
+    //the dyadic operations use the destination register as the first operand
+    pub fn is_dyadic(&self) -> bool {
+        matches!(
+            self,
+            FpOperation::Add
+                | FpOperation::Sub
+                | FpOperation::Mul
+                | FpOperation::Div
+                | FpOperation::Mod
+                | FpOperation::Rem
+                | FpOperation::Scale
+        )
+    }
+}
+
+//the second half of the conditions are the IEEE non aware ones, they signal BSUN when the operands are unordered
+#[derive(Copy, Clone, Debug, Serialize, Eq, PartialEq)]
+pub enum FpCondition {
+    False,
+    Equal,
+    OrderedGreaterThan,
+    OrderedGreaterOrEqual,
+    OrderedLessThan,
+    OrderedLessOrEqual,
+    OrderedGreaterOrLess,
+    Ordered,
+    Unordered,
+    UnorderedOrEqual,
+    UnorderedOrGreaterThan,
+    UnorderedOrGreaterOrEqual,
+    UnorderedOrLessThan,
+    UnorderedOrLessOrEqual,
+    NotEqual,
+    True,
+    SignalingFalse,
+    SignalingEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterOrLess,
+    GreaterLessOrEqual,
+    NotGreaterLessOrEqual,
+    NotGreaterOrLess,
+    NotLessThanOrEqual,
+    NotLessThan,
+    NotGreaterThanOrEqual,
+    NotGreaterThan,
+    SignalingNotEqual,
+    SignalingTrue,
+}
+
+impl FromStr for FpCondition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<FpCondition, Self::Err> {
+        let s = s.to_lowercase();
+        Ok(match s.as_str() {
+            "f" => FpCondition::False,
+            "eq" => FpCondition::Equal,
+            "ogt" => FpCondition::OrderedGreaterThan,
+            "oge" => FpCondition::OrderedGreaterOrEqual,
+            "olt" => FpCondition::OrderedLessThan,
+            "ole" => FpCondition::OrderedLessOrEqual,
+            "ogl" => FpCondition::OrderedGreaterOrLess,
+            "or" => FpCondition::Ordered,
+            "un" => FpCondition::Unordered,
+            "ueq" => FpCondition::UnorderedOrEqual,
+            "ugt" => FpCondition::UnorderedOrGreaterThan,
+            "uge" => FpCondition::UnorderedOrGreaterOrEqual,
+            "ult" => FpCondition::UnorderedOrLessThan,
+            "ule" => FpCondition::UnorderedOrLessOrEqual,
+            "ne" => FpCondition::NotEqual,
+            "t" | "ra" => FpCondition::True,
+            "sf" => FpCondition::SignalingFalse,
+            "seq" => FpCondition::SignalingEqual,
+            "gt" => FpCondition::GreaterThan,
+            "ge" => FpCondition::GreaterThanOrEqual,
+            "lt" => FpCondition::LessThan,
+            "le" => FpCondition::LessThanOrEqual,
+            "gl" => FpCondition::GreaterOrLess,
+            "gle" => FpCondition::GreaterLessOrEqual,
+            "ngle" => FpCondition::NotGreaterLessOrEqual,
+            "ngl" => FpCondition::NotGreaterOrLess,
+            "nle" => FpCondition::NotLessThanOrEqual,
+            "nlt" => FpCondition::NotLessThan,
+            "nge" => FpCondition::NotGreaterThanOrEqual,
+            "ngt" => FpCondition::NotGreaterThan,
+            "sne" => FpCondition::SignalingNotEqual,
+            "st" => FpCondition::SignalingTrue,
+            _ => return Err(format!("Invalid floating point condition: {}", s)),
+        })
+    }
+}
+
+impl FpCondition {
+    pub fn is_signaling(&self) -> bool {
+        (*self as u8) >= FpCondition::SignalingFalse as u8
+    }
+}
+
 /*
 Thanks to:  https://github.com/transistorfet/moa/blob/main/emulator/cpus/m68k/src/instructions.rs
 for the Conditions and inspiration
@@ -154,8 +473,30 @@ pub enum Instruction {
         target: Operand,
     },
     MOVE(Operand, Operand, Size),
+    MOVEP(Operand, RegisterOperand, TargetDirection, Size),
+    MOVEtoSR(Operand),
+    MOVEfromSR(Operand),
+    MOVEtoUSP(RegisterOperand),
+    MOVEfromUSP(RegisterOperand),
+    MOVECtoCR(RegisterOperand, ControlRegister),
+    MOVECfromCR(ControlRegister, RegisterOperand),
+    MOVES(Operand, RegisterOperand, TargetDirection, Size),
+    ANDItoSR(u16),
+    ORItoSR(u16),
+    EORItoSR(u16),
+    MOVEtoCCR(Operand),
+    MOVEfromCCR(Operand),
+    ANDItoCCR(u8),
+    ORItoCCR(u8),
+    EORItoCCR(u8),
     ADD(Operand, Operand, Size),
     SUB(Operand, Operand, Size),
+    ADDX(Operand, Operand, Size),
+    SUBX(Operand, Operand, Size),
+    NEGX(Operand, Size),
+    ABCD(Operand, Operand),
+    SBCD(Operand, Operand),
+    NBCD(Operand),
     ADDQ(u8, Operand, Size),
     MOVEQ(u8, RegisterOperand),
     SUBQ(u8, Operand, Size),
@@ -168,6 +509,9 @@ pub enum Instruction {
     CMPM(Operand, Operand, Size),
     DIVx(Operand, RegisterOperand, Sign),
     MULx(Operand, RegisterOperand, Sign),
+    MULxL(Operand, RegisterOperand, Option<RegisterOperand>, Sign), //Dl and the optional Dh of the 64 bit result
+    DIVxL(Operand, RegisterOperand, RegisterOperand, bool, Sign), //Dq, Dr and if the dividend is 64 bit (Dr:Dq)
+    CHK(Operand, RegisterOperand, Size),
     SWAP(RegisterOperand),
     CLR(Operand, Size),
     EXG(RegisterOperand, RegisterOperand),
@@ -184,21 +528,49 @@ pub enum Instruction {
     LINK(RegisterOperand, u32),
     UNLK(RegisterOperand),
     NOT(Operand, Size),
+    TAS(Operand),
     OR(Operand, Operand, Size),
     AND(Operand, Operand, Size),
     EOR(Operand, Operand, Size),
     JSR(Operand),
     ASd(Operand, Operand, ShiftDirection, Size),
     ROd(Operand, Operand, ShiftDirection, Size),
+    ROXd(Operand, Operand, ShiftDirection, Size),
     LSd(Operand, Operand, ShiftDirection, Size),
     BTST(Operand, Operand),
     BCLR(Operand, Operand),
     BSET(Operand, Operand),
     BCHG(Operand, Operand),
+    BFTST(Operand, BitField),
+    BFCHG(Operand, BitField),
+    BFCLR(Operand, BitField),
+    BFSET(Operand, BitField),
+    BFEXTU(Operand, BitField, RegisterOperand),
+    BFEXTS(Operand, BitField, RegisterOperand),
+    BFFFO(Operand, BitField, RegisterOperand),
+    BFINS(RegisterOperand, Operand, BitField),
+    FMOVE(FpOperand, FpOperand, FpFormat),
+    FMOVEtoCR(Operand, FpControlRegister),
+    FMOVEfromCR(FpControlRegister, Operand),
+    FMOVECR(u8, u8), //offset in the constant rom and destination register
+    FArith(FpOperand, u8, FpOperation, FpFormat),
+    FCMP(FpOperand, u8, FpFormat),
+    FTST(FpOperand, FpFormat),
+    FBcc(u32, FpCondition),
+    FScc(Operand, FpCondition),
+    FDBcc(RegisterOperand, u32, FpCondition),
+    FNOP,
     JMP(Operand),
     BSR(u32),
     TRAP(u8),
+    TRAPV,
+    ILLEGAL,
     RTS,
+    RTR,
+    RTE,
+    STOP(u16),
+    RTD(i16),
+    RESET,
     NOP,
 }
 
@@ -285,4 +657,46 @@ impl Instruction {
         let mut string = string.split('(');
         string.next().unwrap().to_string()
     }
+    /**
+    The first cpu model that has the instruction
+     */
+    pub fn get_cpu_model(&self) -> CpuModel {
+        match self {
+            Instruction::MOVECtoCR(..)
+            | Instruction::MOVECfromCR(..)
+            | Instruction::MOVES(..)
+            | Instruction::RTD(_) => CpuModel::M68010,
+            Instruction::MULxL(..)
+            | Instruction::DIVxL(..)
+            | Instruction::EXT(_, Size::Byte, Size::Long)
+            | Instruction::BFTST(..)
+            | Instruction::BFCHG(..)
+            | Instruction::BFCLR(..)
+            | Instruction::BFSET(..)
+            | Instruction::BFEXTU(..)
+            | Instruction::BFEXTS(..)
+            | Instruction::BFFFO(..)
+            | Instruction::BFINS(..) => CpuModel::M68020,
+            _ => CpuModel::M68000,
+        }
+    }
+    /**
+    The instructions that run on the floating point coprocessor
+     */
+    pub fn is_floating_point(&self) -> bool {
+        matches!(
+            self,
+            Instruction::FMOVE(..)
+                | Instruction::FMOVEtoCR(..)
+                | Instruction::FMOVEfromCR(..)
+                | Instruction::FMOVECR(..)
+                | Instruction::FArith(..)
+                | Instruction::FCMP(..)
+                | Instruction::FTST(..)
+                | Instruction::FBcc(..)
+                | Instruction::FScc(..)
+                | Instruction::FDBcc(..)
+                | Instruction::FNOP
+        )
+    }
 }
@@ -0,0 +1,146 @@
+use serde::Serialize;
+
+use crate::{
+    compiler::MemorySegment,
+    record_file::{RecordError, RecordResult, ADDRESS_SPACE_SIZE, BYTES_PER_RECORD},
+    utils::parse_hex_bytes,
+};
+
+const DATA_RECORD: u8 = 0x00;
+const END_OF_FILE_RECORD: u8 = 0x01;
+const EXTENDED_SEGMENT_ADDRESS_RECORD: u8 = 0x02;
+const START_SEGMENT_ADDRESS_RECORD: u8 = 0x03;
+const EXTENDED_LINEAR_ADDRESS_RECORD: u8 = 0x04;
+const START_LINEAR_ADDRESS_RECORD: u8 = 0x05;
+
+/**
+The memory image read from an Intel HEX file, with the address of the start linear
+or start segment record
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct IntelHexImage {
+    pub segments: Vec<MemorySegment>,
+    pub start: Option<usize>,
+}
+
+fn format_record(record_type: u8, offset: u16, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&offset.to_be_bytes());
+    bytes.push(record_type);
+    bytes.extend_from_slice(data);
+    bytes.push(checksum(&bytes));
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    format!(":{}", hex)
+}
+
+//the two's complement of the low byte of the sum of all the bytes of the record
+fn checksum(bytes: &[u8]) -> u8 {
+    0u8.wrapping_sub(bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)))
+}
+
+/**
+Writes the segments as Intel HEX records, the upper 16 bits of the addresses are set with
+extended linear address records and the data records never cross a 64kb boundary
+ */
+pub fn write_intel_hex(segments: &[MemorySegment], start: Option<usize>) -> String {
+    let mut records = Vec::new();
+    let mut upper = 0;
+    for segment in segments {
+        let mut address = segment.address;
+        let mut data = segment.data.as_slice();
+        while !data.is_empty() {
+            if address >> 16 != upper {
+                upper = address >> 16;
+                records.push(format_record(
+                    EXTENDED_LINEAR_ADDRESS_RECORD,
+                    0,
+                    &(upper as u16).to_be_bytes(),
+                ));
+            }
+            let boundary = 0x10000 - (address & 0xFFFF);
+            let length = data.len().min(BYTES_PER_RECORD).min(boundary);
+            records.push(format_record(DATA_RECORD, address as u16, &data[..length]));
+            address += length;
+            data = &data[length..];
+        }
+    }
+    if let Some(start) = start {
+        records.push(format_record(
+            START_LINEAR_ADDRESS_RECORD,
+            0,
+            &(start as u32).to_be_bytes(),
+        ));
+    }
+    records.push(format_record(END_OF_FILE_RECORD, 0, &[]));
+    let mut text = records.join("\n");
+    text.push('\n');
+    text
+}
+
+/**
+Reads the Intel HEX records into the memory segments, the contiguous data records are merged
+and the segments sorted by address
+ */
+pub fn parse_intel_hex(text: &str) -> RecordResult<IntelHexImage> {
+    let mut blocks: Vec<(usize, Vec<u8>)> = Vec::new();
+    let mut base = 0;
+    let mut start = None;
+    for (line, record) in text.lines().enumerate() {
+        let record = record.trim();
+        if record.is_empty() {
+            continue;
+        }
+        let invalid = |message: &str| RecordError::InvalidRecord {
+            line,
+            message: message.to_string(),
+        };
+        let bytes = match record.strip_prefix(':') {
+            Some(hex) => parse_hex_bytes(hex)
+                .ok_or_else(|| invalid("the record contains non hexadecimal characters"))?,
+            None => return Err(invalid("records must start with :")),
+        };
+        if bytes.len() < 5 || bytes[0] as usize != bytes.len() - 5 {
+            return Err(invalid("the byte count does not match the record length"));
+        }
+        let (body, found) = bytes.split_at(bytes.len() - 1);
+        let expected = checksum(body);
+        if expected != found[0] {
+            return Err(RecordError::ChecksumMismatch {
+                line,
+                expected,
+                found: found[0],
+            });
+        }
+        let offset = u16::from_be_bytes([body[1], body[2]]) as usize;
+        let data = &body[4..];
+        //the value of the address records, like the upper bits of the address
+        let value = data
+            .iter()
+            .fold(0usize, |value, b| (value << 8) | *b as usize);
+        match body[3] {
+            DATA_RECORD => {
+                let address = base + offset;
+                if address + data.len() > ADDRESS_SPACE_SIZE {
+                    return Err(RecordError::AddressOutOfRange { address });
+                }
+                blocks.push((address, data.to_vec()));
+            }
+            END_OF_FILE_RECORD => break,
+            EXTENDED_SEGMENT_ADDRESS_RECORD if data.len() == 2 => base = value << 4,
+            EXTENDED_LINEAR_ADDRESS_RECORD if data.len() == 2 => base = value << 16,
+            //the start segment address is a CS:IP pair
+            START_SEGMENT_ADDRESS_RECORD if data.len() == 4 => {
+                start = Some(((value >> 16) << 4) + (value & 0xFFFF))
+            }
+            START_LINEAR_ADDRESS_RECORD if data.len() == 4 => start = Some(value),
+            EXTENDED_SEGMENT_ADDRESS_RECORD..=START_LINEAR_ADDRESS_RECORD => {
+                return Err(invalid("the address record has the wrong length"))
+            }
+            _ => return Err(invalid("unknown record type")),
+        }
+    }
+    Ok(IntelHexImage {
+        segments: MemorySegment::merge(blocks),
+        start,
+    })
+}
@@ -10,7 +10,7 @@
     There needs to be added a way to only apply the side effect once, and then store the result to the register.
 */
 use core::panic;
-use std::{collections::HashMap, hash::Hash};
+use std::{cmp::Ordering, collections::HashMap, hash::Hash};
 
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
@@ -19,13 +19,19 @@ use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 use crate::debugger::PrettyStackFrame;
 use crate::instructions::TargetDirection;
 use crate::{
-    compiler::{Compiler, Directive, InstructionLine},
+    compiler::{Compiler, InstructionLine, MemorySegment},
     debugger::{Debugger, ExecutionStep, MutationOperation},
+    disassembler::{decode_instruction, disassemble, DecodeError, DisassembledLine},
     instructions::{
-        Condition, Instruction, Interrupt, InterruptResult, Operand, RegisterOperand,
-        ShiftDirection, Sign, Size,
+        BitField, BitFieldValue, Condition, ControlRegister, CpuModel, FpCondition,
+        FpControlRegister, FpFormat, FpOperand, FpOperation, FpuModel, IndexBase, IndexRegister,
+        Instruction, Interrupt, InterruptResult, Label, MemoryIndirection, Operand,
+        RegisterOperand, ShiftDirection, Sign, Size,
     },
+    intel_hex::{parse_intel_hex, write_intel_hex},
     math::*,
+    record_file::{RecordError, RecordResult},
+    srecord::parse_srecords,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -44,7 +50,62 @@ bitflags! {
         const Negative = 1<<4;
         const Extend   = 1<<5;
     }
+
+    #[wasm_bindgen]
+    #[derive(Serialize, Copy, Clone, Debug)]
+    pub struct SystemFlags: u16 {
+        const InterruptMask = 0b111<<8;
+        const Supervisor    = 1<<13;
+        const Trace         = 1<<15;
+    }
+
+    //condition codes, exception status and accrued exceptions of the FPSR, bits 16-23 hold the quotient of FMOD/FREM
+    #[derive(Serialize, Copy, Clone, Debug)]
+    pub struct FpStatus: u32 {
+        const Negative            = 1<<27;
+        const Zero                = 1<<26;
+        const Infinity            = 1<<25;
+        const NotANumber          = 1<<24;
+        const BranchUnordered     = 1<<15;
+        const SignalingNaN        = 1<<14;
+        const OperandError        = 1<<13;
+        const Overflow            = 1<<12;
+        const Underflow           = 1<<11;
+        const DivideByZero        = 1<<10;
+        const InexactOperation    = 1<<9;
+        const InexactDecimal      = 1<<8;
+        const AccruedInvalid      = 1<<7;
+        const AccruedOverflow     = 1<<6;
+        const AccruedUnderflow    = 1<<5;
+        const AccruedDivideByZero = 1<<4;
+        const AccruedInexact      = 1<<3;
+    }
+}
+impl FpStatus {
+    const CONDITION_CODES: FpStatus = FpStatus::from_bits_retain(0x0F00_0000);
+    const EXCEPTIONS: FpStatus = FpStatus::from_bits_retain(0x0000_FF00);
+    /**
+    Adds to the exceptions the accrued exceptions they map to
+     */
+    fn accrued(self) -> FpStatus {
+        let mut status = self;
+        let invalid = FpStatus::BranchUnordered | FpStatus::SignalingNaN | FpStatus::OperandError;
+        let inexact = FpStatus::InexactOperation | FpStatus::InexactDecimal | FpStatus::Overflow;
+        status.set(FpStatus::AccruedInvalid, self.intersects(invalid));
+        status.set(FpStatus::AccruedOverflow, self.contains(FpStatus::Overflow));
+        status.set(
+            FpStatus::AccruedUnderflow,
+            self.contains(FpStatus::Underflow),
+        );
+        status.set(
+            FpStatus::AccruedDivideByZero,
+            self.contains(FpStatus::DivideByZero),
+        );
+        status.set(FpStatus::AccruedInexact, self.intersects(inexact));
+        status
+    }
 }
+
 impl Default for Flags {
     fn default() -> Self {
         Self::new()
@@ -58,9 +119,13 @@ impl Flags {
     pub fn clear(&mut self) {
         *self = Flags::empty();
     }
-    pub fn get_status(&self) -> String {
+    /**
+    The whole status register, the system byte from the sr followed by the condition codes
+     */
+    pub fn get_status(&self, sr: &SystemFlags) -> String {
         format!(
-            "X:{} N:{} Z:{} V:{} C:{}",
+            "{} X:{} N:{} Z:{} V:{} C:{}",
+            sr.get_status(),
             self.contains(Flags::Extend) as u8,
             self.contains(Flags::Negative) as u8,
             self.contains(Flags::Zero) as u8,
@@ -68,6 +133,29 @@ impl Flags {
             self.contains(Flags::Carry) as u8
         )
     }
+    /**
+    The flags are stored one bit higher than in the real CCR, those convert from and to the X N Z V C byte
+     */
+    pub fn to_ccr(&self) -> u8 {
+        (self.bits() >> 1) as u8
+    }
+    pub fn from_ccr(ccr: u8) -> Self {
+        Flags::from_bits_truncate((ccr as u16) << 1)
+    }
+}
+
+impl SystemFlags {
+    pub fn get_interrupt_mask(&self) -> u8 {
+        ((self.bits() & SystemFlags::InterruptMask.bits()) >> 8) as u8
+    }
+    pub fn get_status(&self) -> String {
+        format!(
+            "T:{} S:{} I:{}",
+            self.contains(SystemFlags::Trace) as u8,
+            self.contains(SystemFlags::Supervisor) as u8,
+            self.get_interrupt_mask()
+        )
+    }
 }
 
 pub enum MemoryCell {
@@ -100,6 +188,21 @@ impl MemoryCell {
     }
 }
 
+/**
+A region of the memory to dump or load, the bytes of the region that are not covered by
+the loaded data are set to the fill byte, and the lines of the Intel HEX dumps that only
+contain the fill byte are left out
+ */
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MemoryRegion {
+    pub address: usize,
+    pub length: usize,
+    pub fill: u8,
+}
+
+//the bytes of each line of the Intel HEX dumps
+const DUMP_LINE_SIZE: usize = 16;
+
 #[derive(Debug)]
 #[wasm_bindgen]
 pub struct Memory {
@@ -246,14 +349,28 @@ impl Memory {
         let address = self.verify_address_bounds(address, length)?;
         Ok(&self.data[address..address + length])
     }
+    pub fn dump_binary(&self, region: &MemoryRegion) -> RuntimeResult<Vec<u8>> {
+        Ok(self.read_bytes(region.address, region.length)?.to_vec())
+    }
+    pub fn dump_intel_hex(&self, region: &MemoryRegion) -> RuntimeResult<String> {
+        let address = self.verify_address_bounds(region.address, region.length)?;
+        let bytes = &self.data[address..address + region.length];
+        let blocks = bytes
+            .chunks(DUMP_LINE_SIZE)
+            .enumerate()
+            .filter(|(_, line)| line.iter().any(|b| *b != region.fill))
+            .map(|(i, line)| (address + i * DUMP_LINE_SIZE, line.to_vec()))
+            .collect();
+        Ok(write_intel_hex(&MemorySegment::merge(blocks), None))
+    }
 }
 
 #[wasm_bindgen]
 impl Memory {
-    pub fn wasm_read_bytes(&self, address: usize, size: usize) -> Vec<u8> {
+    pub fn wasm_read_bytes(&self, address: usize, size: usize) -> Result<Vec<u8>, JsValue> {
         match self.read_bytes(address, size) {
-            Ok(bytes) => bytes.to_vec(),
-            Err(_) => vec![],
+            Ok(bytes) => Ok(bytes.to_vec()),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
         }
     }
 }
@@ -331,6 +448,20 @@ pub struct Cpu {
     d_reg: [Register; 8],
     a_reg: [Register; 8],
     ccr: Flags,
+    //system byte of the SR, the low byte is the ccr
+    sr: SystemFlags,
+    //stack pointer of the mode that is not active, a7 always holds the active one
+    shadow_sp: Register,
+    //vector base register, always 0 on the 68000
+    vbr: u32,
+    //source and destination function codes used by MOVES
+    sfc: u8,
+    dfc: u8,
+    //floating point coprocessor, the extended precision registers are emulated with doubles
+    fp_reg: [f64; 8],
+    fpcr: u32,
+    fpsr: u32,
+    fpiar: u32,
 }
 
 impl Default for Cpu {
@@ -345,6 +476,58 @@ impl Cpu {
             d_reg: [Register::new(); 8],
             a_reg: [Register::new(); 8],
             ccr: Flags::new(),
+            sr: SystemFlags::Supervisor,
+            shadow_sp: Register::new(),
+            vbr: 0,
+            sfc: 0,
+            dfc: 0,
+            //the data registers of the coprocessor hold NaNs after a reset
+            fp_reg: [f64::NAN; 8],
+            fpcr: 0,
+            fpsr: 0,
+            fpiar: 0,
+        }
+    }
+    pub fn get_vbr(&self) -> u32 {
+        self.vbr
+    }
+    pub fn get_control_register(&self, register: ControlRegister) -> u32 {
+        match register {
+            ControlRegister::SFC => self.sfc as u32,
+            ControlRegister::DFC => self.dfc as u32,
+            ControlRegister::USP => self.get_usp(),
+            ControlRegister::VBR => self.vbr,
+        }
+    }
+    pub fn get_sr(&self) -> u16 {
+        self.sr.bits() | self.ccr.to_ccr() as u16
+    }
+    pub fn is_supervisor(&self) -> bool {
+        self.sr.contains(SystemFlags::Supervisor)
+    }
+    pub fn get_usp(&self) -> u32 {
+        if self.is_supervisor() {
+            self.shadow_sp.get_long()
+        } else {
+            self.a_reg[7].get_long()
+        }
+    }
+    pub fn get_ssp(&self) -> u32 {
+        if self.is_supervisor() {
+            self.a_reg[7].get_long()
+        } else {
+            self.shadow_sp.get_long()
+        }
+    }
+
+    pub fn get_fp_register(&self, index: usize) -> f64 {
+        self.fp_reg[index]
+    }
+    pub fn get_fp_control_register(&self, register: FpControlRegister) -> u32 {
+        match register {
+            FpControlRegister::FPCR => self.fpcr,
+            FpControlRegister::FPSR => self.fpsr,
+            FpControlRegister::FPIAR => self.fpiar,
         }
     }
 
@@ -375,6 +558,36 @@ impl Cpu {
     pub fn wasm_get_ccr(&self) -> Flags {
         self.ccr
     }
+    pub fn wasm_get_sr(&self) -> u16 {
+        self.get_sr()
+    }
+    pub fn wasm_get_usp(&self) -> u32 {
+        self.get_usp()
+    }
+    pub fn wasm_get_ssp(&self) -> u32 {
+        self.get_ssp()
+    }
+    pub fn wasm_get_fp_regs_value(&self) -> Vec<f64> {
+        self.fp_reg.to_vec()
+    }
+    pub fn wasm_get_fpcr(&self) -> u32 {
+        self.fpcr
+    }
+    pub fn wasm_get_fpsr(&self) -> u32 {
+        self.fpsr
+    }
+    pub fn wasm_get_fpiar(&self) -> u32 {
+        self.fpiar
+    }
+    pub fn wasm_get_vbr(&self) -> u32 {
+        self.vbr
+    }
+    pub fn wasm_get_sfc(&self) -> u8 {
+        self.sfc
+    }
+    pub fn wasm_get_dfc(&self) -> u8 {
+        self.dfc
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -389,6 +602,20 @@ pub enum RuntimeError {
     },
     DivisionByZero,
     IncorrectAddressingMode(String),
+    PrivilegeViolation {
+        address: usize,
+    },
+    ChkOutOfBounds {
+        bound: i32,
+        value: i32,
+        address: usize,
+    },
+    TrapOnOverflow {
+        address: usize,
+    },
+    IllegalInstruction {
+        address: usize,
+    },
     Unimplemented,
 }
 
@@ -401,12 +628,38 @@ pub enum InterpreterStatus {
     Interrupt,
     Terminated,
     TerminatedWithException,
+    //halted by STOP until an interrupt is raised
+    Stopped,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[wasm_bindgen]
+pub enum ExecutionMode {
+    //runs the instructions of the compiled program, writing to the code in memory has no effect
+    #[default]
+    Precompiled,
+    //fetches and decodes the instruction at the pc from memory, like a real cpu
+    FetchDecode,
+}
+
+//the longest instruction, a MOVE between two memory indirect operands with long displacements
+const MAX_INSTRUCTION_SIZE: usize = 22;
+
 #[derive(Serialize, Deserialize)]
 pub struct InterpreterOptions {
     pub keep_history: bool,
     pub history_size: usize,
+    //when disabled, the errors that would cause an exception stop the program instead
+    #[serde(default)]
+    pub exception_processing: bool,
+    //instructions of a later model raise an illegal instruction exception
+    #[serde(default)]
+    pub cpu_model: CpuModel,
+    //without a floating point coprocessor the fpu instructions are illegal instructions
+    #[serde(default)]
+    pub fpu_model: FpuModel,
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
 }
 
 impl InterpreterOptions {
@@ -414,10 +667,32 @@ impl InterpreterOptions {
         Self {
             keep_history: false,
             history_size: 100,
+            exception_processing: false,
+            cpu_model: CpuModel::default(),
+            fpu_model: FpuModel::default(),
+            execution_mode: ExecutionMode::default(),
         }
     }
 }
 
+/**
+Vector numbers of the exceptions, the handler address is read from VBR + vector * 4
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ExceptionVector {
+    BusError = 2,
+    AddressError = 3,
+    IllegalInstruction = 4,
+    DivisionByZero = 5,
+    Chk = 6,
+    TrapV = 7,
+    PrivilegeViolation = 8,
+    Trace = 9,
+    //the autovectors of the interrupt levels 1-7 follow the spurious interrupt one
+    SpuriousInterrupt = 24,
+    Trap = 32,
+}
+
 impl Default for InterpreterOptions {
     fn default() -> Self {
         Self::new()
@@ -435,23 +710,89 @@ pub struct Interpreter {
     instruction_map: Vec<usize>,
     debugger: Debugger,
     keep_history: bool,
+    exception_processing: bool,
+    cpu_model: CpuModel,
+    fpu_model: FpuModel,
+    execution_mode: ExecutionMode,
+    //the instructions decoded from memory with their size, removed when their bytes are written
+    decode_cache: HashMap<usize, (Instruction, usize)>,
     last_line_address: usize,
     final_instruction_address: usize,
+    //the address right after the last instruction, where the program ends
+    code_end: usize,
     current_interrupt: Option<Interrupt>,
     status: InterpreterStatus,
+    reset_signals: usize,
+}
+
+//the end of the segment that holds the address, where the code of a loaded image ends
+fn segment_end(segments: &[MemorySegment], address: usize) -> usize {
+    segments
+        .iter()
+        .find(|s| s.address <= address && address < s.address + s.data.len())
+        .map(|s| s.address + s.data.len())
+        .unwrap_or(0)
 }
 
 impl Interpreter {
     pub fn new(compiled_program: Compiler, options: Option<InterpreterOptions>) -> Self {
-        let sp = 0x01000000;
         let start = compiled_program.get_start_address();
         let end = compiled_program.get_final_instruction_address();
         let program = compiled_program.get_instructions().clone();
-        let length = program.len();
-        let options = options.unwrap_or(InterpreterOptions {
-            keep_history: false,
-            history_size: 100,
-        });
+        let code_end = program
+            .last()
+            .map(|ins| ins.address + ins.get_size())
+            .unwrap_or(0);
+        Self::with_image(
+            program,
+            compiled_program.get_memory_segments(),
+            start,
+            end,
+            code_end,
+            compiled_program.get_labels_map(),
+            options.unwrap_or_default(),
+        )
+    }
+    /**
+    Creates the interpreter of a program assembled elsewhere from its S-records, it starts at the
+    entry point of the termination record, or at the first data record if there is none.
+    There are no compiled instructions so it always runs in the fetch/decode mode, and
+    the program ends when it reaches the end of the segment of the entry point
+     */
+    pub fn from_srecords(text: &str, options: Option<InterpreterOptions>) -> RecordResult<Self> {
+        let image = parse_srecords(text)?;
+        let start = image
+            .entry
+            .or(image.segments.first().map(|s| s.address))
+            .unwrap_or(0);
+        let code_end = segment_end(&image.segments, start);
+        let end = code_end.saturating_sub(2);
+        let options = InterpreterOptions {
+            execution_mode: ExecutionMode::FetchDecode,
+            ..options.unwrap_or_default()
+        };
+        Ok(Self::with_image(
+            Vec::new(),
+            image.segments,
+            start,
+            end,
+            code_end,
+            &HashMap::new(),
+            options,
+        ))
+    }
+    fn with_image(
+        program: Vec<InstructionLine>,
+        segments: Vec<MemorySegment>,
+        start: usize,
+        end: usize,
+        code_end: usize,
+        labels: &HashMap<String, Label>,
+        options: InterpreterOptions,
+    ) -> Self {
+        //the program starts in supervisor mode, the user stack sits 64kb below the supervisor one
+        let sp = 0x01000000;
+        let usp = 0x00FF0000;
         let max_address = program.iter().map(|i| i.address).max().unwrap_or(0);
         let mut instruction_map = vec![usize::MAX; max_address + 1];
         for (index, ins) in program.iter().enumerate() {
@@ -464,35 +805,38 @@ impl Interpreter {
             cpu: Cpu::new(),
             pc: start,
             final_instruction_address: end,
+            code_end,
             program,
             keep_history: options.keep_history,
+            exception_processing: options.exception_processing,
+            cpu_model: options.cpu_model,
+            fpu_model: options.fpu_model,
+            execution_mode: options.execution_mode,
+            decode_cache: HashMap::new(),
             last_line_address: 0,
-            debugger: Debugger::new(options.history_size, compiled_program.get_labels_map()),
+            debugger: Debugger::new(options.history_size, labels),
             current_interrupt: None,
-            status: if start <= end && length > 0 {
+            reset_signals: 0,
+            //the program has no code to run when it starts after its last instruction
+            status: if start < code_end {
                 InterpreterStatus::Running
             } else {
                 InterpreterStatus::Terminated
             },
         };
         interpreter.cpu.a_reg[7].store_long(sp as u32);
-        match interpreter.prepare_memory(compiled_program.get_directives()) {
+        interpreter.cpu.shadow_sp.store_long(usp as u32);
+        match interpreter.prepare_memory(&segments) {
             Ok(_) => interpreter,
             Err(e) => panic!("Error preparing memory: {:?}", e),
         }
     }
 
     //TODO could make this an external function and pass the memory in
-    fn prepare_memory(&mut self, directives: &Vec<Directive>) -> RuntimeResult<()> {
-        for directive in directives {
-            match &directive {
-                Directive::DC { data, address }
-                | Directive::DS { data, address }
-                | Directive::DCB { data, address } => {
-                    self.memory.write_bytes(*address, data)?;
-                }
-                Directive::Other => {}
-            };
+    //the machine code of the instructions is loaded together with the data
+    fn prepare_memory(&mut self, segments: &[MemorySegment]) -> RuntimeResult<()> {
+        for segment in segments {
+            self.memory.write_bytes(segment.address, &segment.data)?;
         }
         Ok(())
     }
@@ -544,7 +888,12 @@ impl Interpreter {
 
     #[inline(always)]
     pub fn has_reached_bottom(&self) -> bool {
-        self.pc > self.final_instruction_address
+        match self.execution_mode {
+            ExecutionMode::Precompiled => self.pc > self.final_instruction_address,
+            //the code can run anywhere in memory, so the program ends only when it reaches the end of the
+            //code, by falling through or jumping there, or when the pc leaves the address space
+            ExecutionMode::FetchDecode => self.pc == self.code_end || self.pc > 0x00ffffff,
+        }
     }
 
     pub fn step(&mut self) -> RuntimeResult<InterpreterStatus> {
@@ -553,9 +902,15 @@ impl Interpreter {
                 .add_step(ExecutionStep::new(self.pc, self.cpu.ccr));
         }
         self.last_line_address = self.pc;
-        let instruction = self
-            .get_instruction_at(self.pc)
-            .map(|i| (i.parsed_line.line_index, i.instruction));
+        let instruction = match self.execution_mode {
+            ExecutionMode::Precompiled => self
+                .get_instruction_at(self.pc)
+                .map(|i| Ok((i.instruction, i.get_size()))),
+            ExecutionMode::FetchDecode if self.status == InterpreterStatus::Running => {
+                Some(self.fetch_instruction())
+            }
+            ExecutionMode::FetchDecode => None,
+        };
         match instruction {
             _ if self.status == InterpreterStatus::Terminated
                 || self.status == InterpreterStatus::TerminatedWithException =>
@@ -567,16 +922,36 @@ impl Interpreter {
             _ if self.status == InterpreterStatus::Interrupt => Err(RuntimeError::Raw(
                 "Attempted to step while interrupt is pending".to_string(),
             )),
+            _ if self.status == InterpreterStatus::Stopped => Err(RuntimeError::Raw(
+                "Attempted to step while the cpu is stopped, raise an interrupt to resume"
+                    .to_string(),
+            )),
 
-            Some((index, ins)) => {
+            //the opcode at the pc could not be fetched or is not a valid instruction
+            Some(Err(error)) => {
+                self.handle_runtime_error(error)?;
+                Ok(self.status)
+            }
+            Some(Ok((ins, size))) => {
                 if self.keep_history {
-                    self.debugger.set_line(index);
+                    //the instructions decoded from memory might not belong to a line of the program
+                    if let Some(line) = self.get_instruction_at(self.pc) {
+                        self.debugger.set_line(line.parsed_line.line_index);
+                    }
+                }
+                self.increment_pc(size);
+                let trace = self.cpu.sr.contains(SystemFlags::Trace);
+                if let Err(error) = self.execute_instruction(&ins) {
+                    self.handle_runtime_error(error)?;
+                } else if trace && self.exception_processing {
+                    self.raise_exception(ExceptionVector::Trace as u8, self.pc, None)?;
                 }
-                self.increment_pc(4);
-                self.execute_instruction(&ins)?;
                 let status = self.get_status();
                 //TODO not sure if doing this before or after running the instruction
-                if self.has_reached_bottom() && *status != InterpreterStatus::Interrupt {
+                if self.has_reached_bottom()
+                    && *status != InterpreterStatus::Interrupt
+                    && *status != InterpreterStatus::Stopped
+                {
                     self.set_status(InterpreterStatus::Terminated);
                 }
                 if self.keep_history {
@@ -597,12 +972,69 @@ impl Interpreter {
             }
         }
     }
+    /**
+    Fetches and decodes the instruction at the pc from memory, the decoded instructions are cached
+    until one of their bytes is written
+     */
+    fn fetch_instruction(&mut self) -> RuntimeResult<(Instruction, usize)> {
+        let address = self.pc & 0x00ffffff;
+        if let Some(decoded) = self.decode_cache.get(&address) {
+            return Ok(*decoded);
+        }
+        if address & 1 != 0 {
+            return Err(RuntimeError::AddressError {
+                address,
+                size: Size::Word,
+            });
+        }
+        let length = MAX_INSTRUCTION_SIZE.min(self.memory.data.len().saturating_sub(address));
+        let bytes = self.memory.read_bytes(address, length)?;
+        match decode_instruction(bytes, address) {
+            Ok((instruction, words)) => {
+                let decoded = (instruction, words.len() * 2);
+                self.decode_cache.insert(address, decoded);
+                Ok(decoded)
+            }
+            Err(DecodeError::IllegalInstruction(_)) => {
+                Err(RuntimeError::IllegalInstruction { address })
+            }
+            Err(DecodeError::OutOfBounds) => Err(RuntimeError::OutOfBounds(format!(
+                "Instruction at address: 0x{:x} continues past the end of memory",
+                address
+            ))),
+        }
+    }
+    //removes the decoded instructions that overlap the written bytes
+    fn invalidate_decode_cache(&mut self, address: usize, length: usize) {
+        if self.decode_cache.is_empty() {
+            return;
+        }
+        let address = address & 0x00ffffff;
+        let end = address + length;
+        let first = address.saturating_sub(MAX_INSTRUCTION_SIZE - 1);
+        if end - first > self.decode_cache.len() {
+            self.decode_cache
+                .retain(|start, (_, size)| *start + *size <= address || *start >= end);
+        } else {
+            for start in first..end {
+                if let Some((_, size)) = self.decode_cache.get(&start) {
+                    if start + size > address {
+                        self.decode_cache.remove(&start);
+                    }
+                }
+            }
+        }
+    }
     pub fn get_pretty_call_stack(&self) -> Vec<PrettyStackFrame> {
         self.debugger.to_call_stack()
     }
     pub fn undo(&mut self) -> RuntimeResult<ExecutionStep> {
         match self.debugger.pop_step() {
             Some(step) => {
+                //the only instruction that can be undone while stopped is the STOP itself
+                if self.status == InterpreterStatus::Stopped {
+                    self.status = InterpreterStatus::Running;
+                }
                 self.pc = step.get_pc();
                 self.cpu.ccr = step.get_ccr();
                 //doing from right to left because mutations are added from left to right
@@ -622,13 +1054,18 @@ impl Interpreter {
                         },
                         MutationOperation::WriteMemory { address, old, size } => {
                             self.memory.write_size(*address, *size, *old)?;
+                            self.invalidate_decode_cache(*address, size.to_bytes());
                         }
                         MutationOperation::WriteMemoryBytes { address, old } => {
                             self.memory.write_bytes(*address, old)?;
+                            self.invalidate_decode_cache(*address, old.len());
                         }
                         MutationOperation::PopCall { to, from } => {
-                            //try to get the address of the function that popped the call
-                            let ins = self.get_instruction_at(to.wrapping_sub(4));
+                            //try to get the address of the function that popped the call, the call ends at the return address
+                            let ins = self
+                                .program
+                                .iter()
+                                .find(|ins| ins.address + ins.get_size() == *to);
                             let callee_address = match ins {
                                 Some(ins) => match &ins.instruction {
                                     Instruction::BSR(address) => *address as usize,
@@ -648,6 +1085,35 @@ impl Interpreter {
                         MutationOperation::PushCall { to: _, from: _ } => {
                             self.debugger.pop_call();
                         }
+                        MutationOperation::WriteStatusRegister { old } => {
+                            self.cpu.sr = SystemFlags::from_bits_truncate(*old);
+                            self.cpu.ccr = Flags::from_ccr(*old as u8);
+                        }
+                        MutationOperation::WriteShadowStackPointer { old } => {
+                            self.cpu.shadow_sp.store_long(*old);
+                        }
+                        MutationOperation::WriteFpRegister { register, old } => {
+                            self.cpu.fp_reg[*register as usize] = *old;
+                        }
+                        MutationOperation::WriteControlRegister { register, old } => {
+                            match register {
+                                ControlRegister::SFC => self.cpu.sfc = *old as u8,
+                                ControlRegister::DFC => self.cpu.dfc = *old as u8,
+                                ControlRegister::VBR => self.cpu.vbr = *old,
+                                //the usp is restored by the shadow stack pointer mutation
+                                ControlRegister::USP => {}
+                            }
+                        }
+                        MutationOperation::WriteFpControlRegister { register, old } => {
+                            match register {
+                                FpControlRegister::FPCR => self.cpu.fpcr = *old,
+                                FpControlRegister::FPSR => self.cpu.fpsr = *old,
+                                FpControlRegister::FPIAR => self.cpu.fpiar = *old,
+                            }
+                        }
+                        MutationOperation::WriteInterpreterStatus { old } => {
+                            self.status = *old;
+                        }
                     }
                 }
                 Ok(step)
@@ -739,6 +1205,18 @@ impl Interpreter {
             None => None,
         }
     }
+    /**
+    Decodes the memory between the address and the address + length, showing what the cpu would
+    execute there, including the code written at runtime or with "dc" directives
+     */
+    pub fn disassemble(
+        &self,
+        address: usize,
+        length: usize,
+    ) -> RuntimeResult<Vec<DisassembledLine>> {
+        let bytes = self.memory.read_bytes(address, length)?;
+        Ok(disassemble(bytes, address))
+    }
     pub fn get_current_interrupt(&self) -> RuntimeResult<Interrupt> {
         match &self.current_interrupt {
             Some(interrupt) => Ok(interrupt.clone()),
@@ -746,12 +1224,123 @@ impl Interpreter {
         }
     }
     fn execute_instruction(&mut self, ins: &Instruction) -> RuntimeResult<()> {
+        self.verify_cpu_model(ins.get_cpu_model())?;
+        if ins.is_floating_point() {
+            self.verify_fpu_model()?;
+        }
         match ins {
             Instruction::MOVE(source, dest, size) => {
                 let source_value = self.get_operand_value(source, *size, Used::Once)?;
                 self.set_logic_flags(source_value, *size);
                 self.store_operand_value(dest, source_value, *size, Used::Once)?;
             }
+            Instruction::MOVEtoSR(source) => {
+                self.verify_supervisor()?;
+                let value = self.get_operand_value(source, Size::Word, Used::Once)?;
+                self.set_sr(value as u16);
+            }
+            Instruction::MOVEfromSR(dest) => {
+                //not privileged on the 68000, the 68010 made it privileged to support virtual machines
+                if self.cpu_model >= CpuModel::M68010 {
+                    self.verify_supervisor()?;
+                }
+                let value = self.cpu.get_sr() as u32;
+                self.store_operand_value(dest, value, Size::Word, Used::Once)?;
+            }
+            Instruction::MOVEtoUSP(source) => {
+                self.verify_supervisor()?;
+                let value = self.get_register_value(*source, Size::Long);
+                self.set_shadow_sp(value);
+            }
+            Instruction::MOVEfromUSP(dest) => {
+                self.verify_supervisor()?;
+                let value = self.cpu.shadow_sp.get_long();
+                self.set_register_value(*dest, value, Size::Long);
+            }
+            Instruction::MOVECtoCR(source, register) => {
+                self.verify_supervisor()?;
+                let value = self.get_register_value(*source, Size::Long);
+                self.set_control_register(*register, value);
+            }
+            Instruction::MOVECfromCR(register, dest) => {
+                self.verify_supervisor()?;
+                let value = self.cpu.get_control_register(*register);
+                self.set_register_value(*dest, value, Size::Long);
+            }
+            Instruction::MOVES(target, register, direction, size) => {
+                //there is a single address space, so the function codes don't change the accessed memory
+                self.verify_supervisor()?;
+                match direction {
+                    TargetDirection::ToMemory => {
+                        let value = self.get_register_value(*register, *size);
+                        self.store_operand_value(target, value, *size, Used::Once)?;
+                    }
+                    TargetDirection::FromMemory => {
+                        let value = self.get_operand_value(target, *size, Used::Once)?;
+                        match register {
+                            //like MOVEA the address registers are always written as long
+                            RegisterOperand::Address(_) => {
+                                let value = sign_extend_to_long(value, *size) as u32;
+                                self.set_register_value(*register, value, Size::Long);
+                            }
+                            RegisterOperand::Data(_) => {
+                                self.set_register_value(*register, value, *size)
+                            }
+                        }
+                    }
+                }
+            }
+            Instruction::ANDItoSR(value) => {
+                self.verify_supervisor()?;
+                self.set_sr(self.cpu.get_sr() & *value);
+            }
+            Instruction::ORItoSR(value) => {
+                self.verify_supervisor()?;
+                self.set_sr(self.cpu.get_sr() | *value);
+            }
+            Instruction::EORItoSR(value) => {
+                self.verify_supervisor()?;
+                self.set_sr(self.cpu.get_sr() ^ *value);
+            }
+            Instruction::MOVEtoCCR(source) => {
+                //the source is a word but only the low byte is used
+                let value = self.get_operand_value(source, Size::Word, Used::Once)?;
+                self.cpu.ccr = Flags::from_ccr(value as u8);
+            }
+            Instruction::MOVEfromCCR(dest) => {
+                let value = self.cpu.ccr.to_ccr() as u32;
+                self.store_operand_value(dest, value, Size::Word, Used::Once)?;
+            }
+            Instruction::ANDItoCCR(value) => {
+                self.cpu.ccr = Flags::from_ccr(self.cpu.ccr.to_ccr() & *value);
+            }
+            Instruction::ORItoCCR(value) => {
+                self.cpu.ccr = Flags::from_ccr(self.cpu.ccr.to_ccr() | *value);
+            }
+            Instruction::EORItoCCR(value) => {
+                self.cpu.ccr = Flags::from_ccr(self.cpu.ccr.to_ccr() ^ *value);
+            }
+            Instruction::MOVEP(target, register, direction, size) => {
+                //the bytes are transferred to every other address, as on a peripheral using only half of the data bus
+                let address = self.get_operand_address(target)? as usize;
+                let count = size.to_bytes();
+                match direction {
+                    TargetDirection::ToMemory => {
+                        let value = self.get_register_value(*register, *size);
+                        for i in 0..count {
+                            let byte = (value >> ((count - 1 - i) * 8)) & 0xFF;
+                            self.set_memory_value(address + i * 2, Size::Byte, byte)?;
+                        }
+                    }
+                    TargetDirection::FromMemory => {
+                        let mut value = 0;
+                        for i in 0..count {
+                            value = (value << 8) | self.memory.read_byte(address + i * 2)? as u32;
+                        }
+                        self.set_register_value(*register, value, *size);
+                    }
+                }
+            }
             Instruction::MOVEA(source, dest, size) => {
                 let source_value = self.get_operand_value(source, *size, Used::Once)?;
                 let source_value = sign_extend_to_long(source_value, *size) as u32;
@@ -935,6 +1524,58 @@ impl Interpreter {
                 }
             }
 
+            Instruction::ADDX(source, dest, size) => {
+                let source_value = self.get_operand_value(source, *size, Used::Once)?;
+                let dest_value = self.get_operand_value(dest, *size, Used::Twice)?;
+                let extend = self.get_flag(Flags::Extend);
+                let (result, carry) =
+                    overflowing_add_extended_sized(dest_value, source_value, extend, *size);
+                let overflow = has_add_overflowed(dest_value, source_value, result, *size);
+                self.set_extended_flags(result, *size, carry, overflow);
+                self.store_operand_value(dest, result, *size, Used::Twice)?;
+            }
+            Instruction::SUBX(source, dest, size) => {
+                let source_value = self.get_operand_value(source, *size, Used::Once)?;
+                let dest_value = self.get_operand_value(dest, *size, Used::Twice)?;
+                let extend = self.get_flag(Flags::Extend);
+                let (result, carry) =
+                    overflowing_sub_extended_sized(dest_value, source_value, extend, *size);
+                let overflow = has_sub_overflowed(dest_value, source_value, result, *size);
+                self.set_extended_flags(result, *size, carry, overflow);
+                self.store_operand_value(dest, result, *size, Used::Twice)?;
+            }
+            Instruction::NEGX(dest, size) => {
+                let dest_value = self.get_operand_value(dest, *size, Used::Twice)?;
+                let extend = self.get_flag(Flags::Extend);
+                let (result, carry) = overflowing_sub_extended_sized(0, dest_value, extend, *size);
+                let overflow = has_sub_overflowed(0, dest_value, result, *size);
+                self.set_extended_flags(result, *size, carry, overflow);
+                self.store_operand_value(dest, result, *size, Used::Twice)?;
+            }
+            Instruction::ABCD(source, dest) => {
+                let source_value = self.get_operand_value(source, Size::Byte, Used::Once)?;
+                let dest_value = self.get_operand_value(dest, Size::Byte, Used::Twice)?;
+                let extend = self.get_flag(Flags::Extend);
+                let (result, carry) = bcd_add(dest_value as u8, source_value as u8, extend);
+                self.set_extended_flags(result as u32, Size::Byte, carry, false);
+                self.store_operand_value(dest, result as u32, Size::Byte, Used::Twice)?;
+            }
+            Instruction::SBCD(source, dest) => {
+                let source_value = self.get_operand_value(source, Size::Byte, Used::Once)?;
+                let dest_value = self.get_operand_value(dest, Size::Byte, Used::Twice)?;
+                let extend = self.get_flag(Flags::Extend);
+                let (result, borrow) = bcd_sub(dest_value as u8, source_value as u8, extend);
+                self.set_extended_flags(result as u32, Size::Byte, borrow, false);
+                self.store_operand_value(dest, result as u32, Size::Byte, Used::Twice)?;
+            }
+            Instruction::NBCD(dest) => {
+                let dest_value = self.get_operand_value(dest, Size::Byte, Used::Twice)?;
+                let extend = self.get_flag(Flags::Extend);
+                let (result, borrow) = bcd_sub(0, dest_value as u8, extend);
+                self.set_extended_flags(result as u32, Size::Byte, borrow, false);
+                self.store_operand_value(dest, result as u32, Size::Byte, Used::Twice)?;
+            }
+
             Instruction::MULx(source, dest, sign) => {
                 let source_value = self.get_operand_value(source, Size::Word, Used::Once)?;
                 let dest_value =
@@ -950,6 +1591,32 @@ impl Interpreter {
                 self.set_compare_flags(result as u32, Size::Long, false, false);
                 self.set_register_value(*dest, result as u32, Size::Long);
             }
+            Instruction::MULxL(source, low, high, sign) => {
+                let source_value = self.get_operand_value(source, Size::Long, Used::Once)?;
+                let dest_value = self.get_register_value(*low, Size::Long);
+                let (result, has_overflowed) = match sign {
+                    Sign::Signed => {
+                        let result = (dest_value as i32 as i64) * (source_value as i32 as i64);
+                        (result as u64, result != (result as i32) as i64)
+                    }
+                    Sign::Unsigned => {
+                        let result = dest_value as u64 * source_value as u64;
+                        (result, result > u32::MAX as u64)
+                    }
+                };
+                match high {
+                    Some(high) => {
+                        //the flags are computed on the whole 64 bit result, it can't overflow
+                        self.set_compare_flags((result >> 32) as u32, Size::Long, false, false);
+                        self.set_flag(Flags::Zero, result == 0);
+                        self.set_register_value(*high, (result >> 32) as u32, Size::Long);
+                    }
+                    None => {
+                        self.set_compare_flags(result as u32, Size::Long, false, has_overflowed)
+                    }
+                }
+                self.set_register_value(*low, result as u32, Size::Long);
+            }
 
             Instruction::BRA(address) => {
                 //instead of using the absolute address, the original language uses pc + 2 + offset
@@ -957,22 +1624,13 @@ impl Interpreter {
             }
             Instruction::BSR(address) => {
                 if self.keep_history {
-                    let old_address = self.get_sp().wrapping_sub(4);
-                    let old_value = self.memory.read_long(old_address)?;
-                    self.debugger.add_mutation(MutationOperation::WriteMemory {
-                        address: old_address,
-                        old: old_value,
-                        size: Size::Long,
-                    });
                     self.debugger.add_mutation(MutationOperation::PushCall {
                         to: *address as usize,
-                        from: self.get_pc().wrapping_sub(4), //the pc is incremented before the instruction is executed
+                        from: self.last_line_address,
                     });
                 }
-                let new_sp = self
-                    .memory
-                    .push(&MemoryCell::Long(self.pc as u32), self.get_sp())?;
-                self.set_sp(new_sp);
+                //the return address can overwrite code, it goes through the decode cache
+                self.push_stack(self.pc as u32, Size::Long)?;
                 let caller_address = self.pc;
                 self.pc = *address as usize;
                 self.debugger.push_call(
@@ -984,27 +1642,107 @@ impl Interpreter {
             Instruction::JSR(source) => {
                 let address = self.get_operand_address(source)?;
                 if self.keep_history {
-                    let old_address = self.get_sp().wrapping_sub(4);
-                    let old_value = self.memory.read_long(old_address)?;
-                    self.debugger.add_mutation(MutationOperation::WriteMemory {
-                        address: old_address,
-                        old: old_value,
-                        size: Size::Long,
-                    });
                     self.debugger.add_mutation(MutationOperation::PushCall {
                         to: address as usize,
-                        from: self.get_pc().wrapping_sub(4), //pc is incremented before the instruction is executed
+                        from: self.last_line_address,
                     });
                 }
-                let new_sp = self
-                    .memory
-                    .push(&MemoryCell::Long(self.pc as u32), self.get_sp())?;
-                self.set_sp(new_sp);
+                //the return address can overwrite code, it goes through the decode cache
+                self.push_stack(self.pc as u32, Size::Long)?;
                 let caller_address = self.pc;
                 self.pc = address as usize;
                 self.debugger
                     .push_call(self.pc, caller_address, self.cpu.get_register_values());
             }
+            Instruction::FMOVE(source, dest, format) => {
+                self.set_fp_instruction_address();
+                let (value, mut exceptions) = self.get_fp_operand_value(source, *format)?;
+                match dest {
+                    FpOperand::Register(reg) => {
+                        let value = self.round_fp_precision(value, &mut exceptions);
+                        self.set_fp_register(*reg, value);
+                        self.set_fp_status(Some(value), exceptions);
+                    }
+                    FpOperand::Operand(op) => {
+                        exceptions |= self.store_fp_operand_value(op, value, *format)?;
+                        self.set_fp_status(None, exceptions);
+                    }
+                    FpOperand::Immediate(_) => {
+                        return Err(RuntimeError::IncorrectAddressingMode(
+                            "Attempted to store to immediate value".to_string(),
+                        ))
+                    }
+                }
+            }
+            Instruction::FMOVEtoCR(source, register) => {
+                let value = self.get_operand_value(source, Size::Long, Used::Once)?;
+                self.set_fp_control_register(*register, value);
+            }
+            Instruction::FMOVEfromCR(register, dest) => {
+                let value = self.cpu.get_fp_control_register(*register);
+                self.store_operand_value(dest, value, Size::Long, Used::Once)?;
+            }
+            Instruction::FMOVECR(offset, dest) => {
+                self.set_fp_instruction_address();
+                let mut exceptions = FpStatus::empty();
+                let value = self.round_fp_precision(get_fp_constant(*offset), &mut exceptions);
+                self.set_fp_register(*dest, value);
+                self.set_fp_status(Some(value), exceptions);
+            }
+            Instruction::FArith(source, dest, operation, format) => {
+                self.set_fp_instruction_address();
+                let (source_value, mut exceptions) = self.get_fp_operand_value(source, *format)?;
+                let dest_value = self.cpu.fp_reg[*dest as usize];
+                let value = self.execute_fp_operation(
+                    *operation,
+                    source_value,
+                    dest_value,
+                    &mut exceptions,
+                );
+                let value = self.round_fp_precision(value, &mut exceptions);
+                self.set_fp_register(*dest, value);
+                self.set_fp_status(Some(value), exceptions);
+            }
+            Instruction::FCMP(source, dest, format) => {
+                self.set_fp_instruction_address();
+                let (source_value, exceptions) = self.get_fp_operand_value(source, *format)?;
+                let dest_value = self.cpu.fp_reg[*dest as usize];
+                //only the condition codes of the subtraction are kept, so the operands are just compared
+                let result = match dest_value.partial_cmp(&source_value) {
+                    Some(Ordering::Less) => -1.0,
+                    Some(Ordering::Greater) => 1.0,
+                    Some(Ordering::Equal) => 0.0,
+                    None => f64::NAN,
+                };
+                self.set_fp_status(Some(result), exceptions);
+            }
+            Instruction::FTST(source, format) => {
+                self.set_fp_instruction_address();
+                let (value, exceptions) = self.get_fp_operand_value(source, *format)?;
+                self.set_fp_status(Some(value), exceptions);
+            }
+            Instruction::FBcc(address, condition) => {
+                if self.get_fp_condition_value(condition) {
+                    self.pc = *address as usize;
+                }
+            }
+            Instruction::FScc(op, condition) => {
+                if self.get_fp_condition_value(condition) {
+                    self.store_operand_value(op, 0xFF, Size::Byte, Used::Once)?;
+                } else {
+                    self.store_operand_value(op, 0x00, Size::Byte, Used::Once)?;
+                }
+            }
+            Instruction::FDBcc(reg, address, condition) => {
+                if !self.get_fp_condition_value(condition) {
+                    let next = (self.get_register_value(*reg, Size::Word) as i16).wrapping_sub(1);
+                    self.set_register_value(*reg, next as u32, Size::Word);
+                    if next != -1 {
+                        self.pc = *address as usize;
+                    }
+                }
+            }
+            Instruction::FNOP => {}
             Instruction::JMP(op) => {
                 let addr = self.get_operand_address(op)?;
                 self.pc = addr as usize;
@@ -1015,16 +1753,7 @@ impl Interpreter {
             }
             Instruction::PEA(source) => {
                 let addr = self.get_operand_address(source)?;
-                if self.keep_history {
-                    let old_value = self.memory.read_long(self.get_sp())?;
-                    self.debugger.add_mutation(MutationOperation::WriteMemory {
-                        address: self.get_sp().wrapping_sub(4),
-                        old: old_value,
-                        size: Size::Long,
-                    })
-                }
-                let new_sp = self.memory.push(&MemoryCell::Long(addr), self.get_sp())?;
-                self.set_sp(new_sp);
+                self.push_stack(addr, Size::Long)?;
             }
             Instruction::BCHG(bit_source, dest) => {
                 let bit = self.get_operand_value(bit_source, Size::Byte, Used::Once)?;
@@ -1073,6 +1802,58 @@ impl Interpreter {
                 let value = self.get_operand_value(op2, size, Used::Once)?;
                 self.set_bit_test_flags(value, limited_bit, size);
             }
+            Instruction::BFTST(op, field) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+            }
+            Instruction::BFCHG(op, field) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+                self.write_bit_field(op, offset, width, !value)?;
+            }
+            Instruction::BFCLR(op, field) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+                self.write_bit_field(op, offset, width, 0)?;
+            }
+            Instruction::BFSET(op, field) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+                self.write_bit_field(op, offset, width, u32::MAX)?;
+            }
+            Instruction::BFEXTU(op, field, dest) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+                self.set_register_value(*dest, value, Size::Long);
+            }
+            Instruction::BFEXTS(op, field, dest) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+                let shift = 32 - width;
+                let value = (((value << shift) as i32) >> shift) as u32;
+                self.set_register_value(*dest, value, Size::Long);
+            }
+            Instruction::BFFFO(op, field, dest) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.read_bit_field(op, offset, width)?;
+                self.set_bit_field_flags(value, width);
+                //an empty field gives the offset of the bit after the field
+                let first_one = (value << (32 - width)).leading_zeros().min(width);
+                let result = offset.wrapping_add(first_one as i32) as u32;
+                self.set_register_value(*dest, result, Size::Long);
+            }
+            Instruction::BFINS(src, op, field) => {
+                let (offset, width) = self.get_bit_field_bounds(field);
+                let value = self.get_register_value(*src, Size::Long) & bit_field_mask(width);
+                self.set_bit_field_flags(value, width);
+                self.write_bit_field(op, offset, width, value)?;
+            }
             Instruction::ASd(amount, dest, direction, size) => {
                 let amount_value = self.get_operand_value(amount, *size, Used::Once)? % 64;
                 let dest_value = self.get_operand_value(dest, *size, Used::Twice)?;
@@ -1137,6 +1918,21 @@ impl Interpreter {
                     self.set_flag(Flags::Carry, true);
                 }
             }
+            Instruction::ROXd(amount, dest, direction, size) => {
+                let count = self.get_operand_value(amount, *size, Used::Once)? % 64;
+                let (mut value, mut extend) = (
+                    self.get_operand_value(dest, *size, Used::Twice)?,
+                    self.get_flag(Flags::Extend),
+                );
+                for _ in 0..count {
+                    (value, extend) = rotate_extend(direction, value, *size, extend);
+                }
+                self.store_operand_value(dest, value, *size, Used::Twice)?;
+                self.set_logic_flags(value, *size);
+                //with a count of 0 the carry is set to the value of the extend bit
+                self.set_flag(Flags::Extend, extend);
+                self.set_flag(Flags::Carry, extend);
+            }
 
             Instruction::AND(source, dest, size) => {
                 let source_value = self.get_operand_value(source, *size, Used::Once)?;
@@ -1159,6 +1955,11 @@ impl Interpreter {
                 self.store_operand_value(dest, result, *size, Used::Twice)?;
                 self.set_logic_flags(result, *size);
             }
+            Instruction::TAS(op) => {
+                let value = self.get_operand_value(op, Size::Byte, Used::Twice)?;
+                self.set_logic_flags(value, Size::Byte);
+                self.store_operand_value(op, value | 0x80, Size::Byte, Used::Twice)?;
+            }
             Instruction::NOT(op, size) => {
                 //watchout for the "!"
                 let value = !self.get_operand_value(op, *size, Used::Twice)?;
@@ -1192,6 +1993,32 @@ impl Interpreter {
                 self.set_compare_flags(result, *size, carry, overflow);
                 self.set_flag(Flags::Extend, carry);
             }
+            Instruction::CHK(source, register, size) => {
+                let bound =
+                    sign_extend_to_long(self.get_operand_value(source, *size, Used::Once)?, *size);
+                let value = sign_extend_to_long(self.get_register_value(*register, *size), *size);
+                //Z, V and C are undefined, N tells if the value was below 0 or above the bound
+                if value < 0 || value > bound {
+                    self.set_flag(Flags::Negative, value < 0);
+                    return Err(RuntimeError::ChkOutOfBounds {
+                        bound,
+                        value,
+                        address: self.last_line_address,
+                    });
+                }
+            }
+            Instruction::TRAPV => {
+                if self.get_flag(Flags::Overflow) {
+                    return Err(RuntimeError::TrapOnOverflow {
+                        address: self.last_line_address,
+                    });
+                }
+            }
+            Instruction::ILLEGAL => {
+                return Err(RuntimeError::IllegalInstruction {
+                    address: self.last_line_address,
+                });
+            }
             Instruction::DIVx(source, dest, sign) => {
                 let source_value = self.get_operand_value(source, Size::Word, Used::Once)?;
                 if source_value == 0 {
@@ -1231,24 +2058,70 @@ impl Interpreter {
                     self.set_flag(Flags::Overflow, true);
                 }
             }
-            Instruction::EXG(reg1, reg2) => {
-                let reg1_value = self.get_register_value(*reg1, Size::Long);
-                let reg2_value = self.get_register_value(*reg2, Size::Long);
-                self.set_register_value(*reg1, reg2_value, Size::Long);
-                self.set_register_value(*reg2, reg1_value, Size::Long);
-            }
-            Instruction::EXT(reg, from, to) => {
-                let input = get_value_sized(self.get_register_value(*reg, Size::Long), *from);
-                let result = match (from, to) {
-                    (Size::Byte, Size::Word) => ((((input as u8) as i8) as i16) as u16) as u32,
-                    (Size::Word, Size::Long) => (((input as u16) as i16) as i32) as u32,
-                    (Size::Byte, Size::Long) => (((input as u8) as i8) as i32) as u32,
-                    _ => {
-                        return Err(RuntimeError::Raw(
-                            "Invalid size for EXT instruction".to_string(),
-                        ));
-                    }
-                };
+            Instruction::DIVxL(source, quotient_reg, remainder_reg, long_dividend, sign) => {
+                let source_value = self.get_operand_value(source, Size::Long, Used::Once)?;
+                if source_value == 0 {
+                    return Err(RuntimeError::DivisionByZero);
+                }
+                let low = self.get_register_value(*quotient_reg, Size::Long);
+                let high = self.get_register_value(*remainder_reg, Size::Long);
+                //i128 so that every quotient, even the overflowing ones, can be represented
+                let (quotient, remainder, has_overflowed) = match sign {
+                    Sign::Signed => {
+                        let dividend = match long_dividend {
+                            true => (((high as u64) << 32) | low as u64) as i64 as i128,
+                            false => low as i32 as i128,
+                        };
+                        let divisor = source_value as i32 as i128;
+                        let quotient = dividend / divisor;
+                        (
+                            quotient as u32,
+                            (dividend % divisor) as u32,
+                            quotient > i32::MAX as i128 || quotient < i32::MIN as i128,
+                        )
+                    }
+                    Sign::Unsigned => {
+                        let dividend = match long_dividend {
+                            true => ((high as u64) << 32) | low as u64,
+                            false => low as u64,
+                        };
+                        let divisor = source_value as u64;
+                        let quotient = dividend / divisor;
+                        (
+                            quotient as u32,
+                            (dividend % divisor) as u32,
+                            quotient > u32::MAX as u64,
+                        )
+                    }
+                };
+                if !has_overflowed {
+                    self.set_compare_flags(quotient, Size::Long, false, false);
+                    //the quotient is written last, so it wins if both registers are the same
+                    self.set_register_value(*remainder_reg, remainder, Size::Long);
+                    self.set_register_value(*quotient_reg, quotient, Size::Long);
+                } else {
+                    self.set_flag(Flags::Carry, false);
+                    self.set_flag(Flags::Overflow, true);
+                }
+            }
+            Instruction::EXG(reg1, reg2) => {
+                let reg1_value = self.get_register_value(*reg1, Size::Long);
+                let reg2_value = self.get_register_value(*reg2, Size::Long);
+                self.set_register_value(*reg1, reg2_value, Size::Long);
+                self.set_register_value(*reg2, reg1_value, Size::Long);
+            }
+            Instruction::EXT(reg, from, to) => {
+                let input = get_value_sized(self.get_register_value(*reg, Size::Long), *from);
+                let result = match (from, to) {
+                    (Size::Byte, Size::Word) => ((((input as u8) as i8) as i16) as u16) as u32,
+                    (Size::Word, Size::Long) => (((input as u16) as i16) as i32) as u32,
+                    (Size::Byte, Size::Long) => (((input as u8) as i8) as i32) as u32,
+                    _ => {
+                        return Err(RuntimeError::Raw(
+                            "Invalid size for EXT instruction".to_string(),
+                        ));
+                    }
+                };
                 self.set_register_value(*reg, result, *to);
                 self.set_logic_flags(result, *to);
             }
@@ -1340,33 +2213,85 @@ impl Interpreter {
                 if self.keep_history {
                     self.debugger.add_mutation(MutationOperation::PopCall {
                         to: value.get_long() as usize,
-                        from: self.get_pc().wrapping_sub(4), //pc is incremented before execution
+                        from: self.last_line_address,
                     })
                 }
                 self.set_sp(new_sp);
                 self.pc = value.get_long() as usize;
                 self.debugger.pop_call();
             }
-            Instruction::TRAP(value) => match value {
-                15 => {
-                    let task = self.cpu.d_reg[0].get_byte();
-                    let interrupt = self.get_trap(task)?;
-                    
-                    // TODO should i check if the interrupt is the Terminate one or if it terminated?
-                    match &interrupt {
-                        Interrupt::Terminate => self.set_status(InterpreterStatus::Terminated),
-                        _ => self.set_status(InterpreterStatus::Interrupt),
-                    }
-                    self.current_interrupt = Some(interrupt);
-                
+            Instruction::RTD(displacement) => {
+                let (value, new_sp) = self.memory.pop(Size::Long, self.get_sp())?;
+                if self.keep_history {
+                    self.debugger.add_mutation(MutationOperation::PopCall {
+                        to: value.get_long() as usize,
+                        from: self.last_line_address,
+                    })
                 }
-                _ => {
-                    return Err(RuntimeError::Raw(format!(
-                        "Unknown trap: {}, only IO with #15 allowed",
-                        value
-                    )));
+                //the arguments pushed by the caller are removed together with the return address
+                self.set_sp((new_sp as i32).wrapping_add(*displacement as i32) as usize);
+                self.pc = value.get_long() as usize;
+                self.debugger.pop_call();
+            }
+            Instruction::RTE => {
+                self.verify_supervisor()?;
+                let (sr, new_sp) = self.memory.pop(Size::Word, self.get_sp())?;
+                let (pc, new_sp) = self.memory.pop(Size::Long, new_sp)?;
+                self.set_sp(new_sp);
+                self.pc = pc.get_long() as usize;
+                self.set_sr(sr.get_word());
+            }
+            Instruction::STOP(value) => {
+                self.verify_supervisor()?;
+                self.set_sr(*value);
+                self.set_status(InterpreterStatus::Stopped);
+            }
+            Instruction::RESET => {
+                self.verify_supervisor()?;
+                self.reset_signals += 1;
+            }
+            Instruction::RTR => {
+                let (ccr, new_sp) = self.memory.pop(Size::Word, self.get_sp())?;
+                self.cpu.ccr = Flags::from_ccr(ccr.get_byte());
+                let (value, new_sp) = self.memory.pop(Size::Long, new_sp)?;
+                if self.keep_history {
+                    self.debugger.add_mutation(MutationOperation::PopCall {
+                        to: value.get_long() as usize,
+                        from: self.last_line_address,
+                    })
                 }
-            },
+                self.set_sp(new_sp);
+                self.pc = value.get_long() as usize;
+                self.debugger.pop_call();
+            }
+            Instruction::TRAP(value) => {
+                //a handler installed in the vector table takes precedence over the built-in IO of trap #15
+                let vector = ExceptionVector::Trap as u8 + value;
+                if !self.raise_exception(vector, self.pc, None)? {
+                    match value {
+                        15 => {
+                            let task = self.cpu.d_reg[0].get_byte();
+                            let interrupt = self.get_trap(task)?;
+
+                            // TODO should i check if the interrupt is the Terminate one or if it terminated?
+                            match &interrupt {
+                                Interrupt::Terminate => {
+                                    self.set_status(InterpreterStatus::Terminated)
+                                }
+                                _ => self.set_status(InterpreterStatus::Interrupt),
+                            }
+                            self.current_interrupt = Some(interrupt);
+                        }
+                        _ => {
+                            return Err(RuntimeError::Raw(format!(
+                                "Unknown trap: {}, no handler installed at vector address {:#X}",
+                                value,
+                                self.cpu.vbr as usize + vector as usize * 4
+                            )));
+                        }
+                    }
+                }
+            }
         };
         Ok(())
     }
@@ -1390,8 +2315,11 @@ impl Interpreter {
         println!("A5: {:#010X} ({})", self.cpu.a_reg[5].get_long(), self.cpu.a_reg[5].get_long());
         println!("A6: {:#010X} ({})", self.cpu.a_reg[6].get_long(), self.cpu.a_reg[6].get_long());
         println!("A7: {:#010X} ({})", self.cpu.a_reg[7].get_long(), self.cpu.a_reg[7].get_long());
-        let ccr = self.cpu.ccr.get_status();
-        println!("{}", ccr);
+        println!("USP: {:#010X} SSP: {:#010X}", self.cpu.get_usp(), self.cpu.get_ssp());
+        println!("SR: {:#06X} {}", self.cpu.get_sr(), self.cpu.ccr.get_status(&self.cpu.sr));
+        println!("VBR: {:#010X} SFC: {} DFC: {}", self.cpu.vbr, self.cpu.sfc, self.cpu.dfc);
+        println!("FP0-FP7: {:?}", self.cpu.fp_reg);
+        println!("FPCR: {:#010X} FPSR: {:#010X} FPIAR: {:#010X}", self.cpu.fpcr, self.cpu.fpsr, self.cpu.fpiar);
     }
 
     #[inline]
@@ -1442,6 +2370,7 @@ impl Interpreter {
             });
         }
         self.memory.write_size(address, size, value)?;
+        self.invalidate_decode_cache(address, size.to_bytes());
         Ok(())
     }
 
@@ -1520,6 +2449,75 @@ impl Interpreter {
         Ok(addr)
     }
 
+    /**
+    Loads the bytes at the start of the region and sets the rest of it to the fill byte,
+    like the external writes it is not part of the history
+     */
+    pub fn load_binary(&mut self, bytes: &[u8], region: &MemoryRegion) -> RuntimeResult<()> {
+        if bytes.len() > region.length {
+            return Err(RuntimeError::OutOfBounds(format!(
+                "The data is {} bytes long but the region is {} bytes long",
+                bytes.len(),
+                region.length
+            )));
+        }
+        let mut data = bytes.to_vec();
+        data.resize(region.length, region.fill);
+        self.load_bytes(region.address, &data)
+    }
+    /**
+    Loads the Intel HEX records at their address, when a region is given it is set to the
+    fill byte first and the records must be inside of it.
+    All the records are checked before writing, so nothing is loaded if one of them is invalid
+     */
+    pub fn load_intel_hex(
+        &mut self,
+        text: &str,
+        region: Option<&MemoryRegion>,
+    ) -> RecordResult<()> {
+        let image = parse_intel_hex(text)?;
+        let (address, data) = match region {
+            Some(region) => {
+                let mut data = vec![region.fill; region.length];
+                for segment in &image.segments {
+                    let offset = segment
+                        .address
+                        .checked_sub(region.address)
+                        .filter(|offset| offset + segment.data.len() <= region.length)
+                        .ok_or(RecordError::AddressOutOfRange {
+                            address: segment.address,
+                        })?;
+                    data[offset..offset + segment.data.len()].copy_from_slice(&segment.data);
+                }
+                (region.address, data)
+            }
+            None => {
+                if let Some(segment) = image
+                    .segments
+                    .iter()
+                    .find(|s| s.address + s.data.len() > self.memory.data.len())
+                {
+                    return Err(RecordError::AddressOutOfRange {
+                        address: segment.address,
+                    });
+                }
+                for segment in &image.segments {
+                    self.load_bytes(segment.address, &segment.data)
+                        .map_err(|_| RecordError::AddressOutOfRange {
+                            address: segment.address,
+                        })?;
+                }
+                return Ok(());
+            }
+        };
+        self.load_bytes(address, &data)
+            .map_err(|_| RecordError::AddressOutOfRange { address })
+    }
+    fn load_bytes(&mut self, address: usize, bytes: &[u8]) -> RuntimeResult<()> {
+        self.memory.write_bytes(address, bytes)?;
+        self.invalidate_decode_cache(address, bytes.len());
+        Ok(())
+    }
     pub fn set_memory_bytes(&mut self, address: usize, bytes: &[u8]) -> RuntimeResult<()> {
         if self.keep_history {
             let old_bytes = self.memory.read_bytes(address, bytes.len())?;
@@ -1529,7 +2527,332 @@ impl Interpreter {
                     old: old_bytes.to_vec(),
                 });
         }
-        self.memory.write_bytes(address, bytes)
+        self.memory.write_bytes(address, bytes)?;
+        self.invalidate_decode_cache(address, bytes.len());
+        Ok(())
+    }
+
+    /**
+    The floating point arithmetic instructions save their address in the FPIAR
+     */
+    fn set_fp_instruction_address(&mut self) {
+        self.set_fp_control_register(FpControlRegister::FPIAR, self.last_line_address as u32);
+    }
+    fn set_fp_register(&mut self, register: u8, value: f64) {
+        if self.keep_history {
+            self.debugger
+                .add_mutation(MutationOperation::WriteFpRegister {
+                    register,
+                    old: self.cpu.fp_reg[register as usize],
+                });
+        }
+        self.cpu.fp_reg[register as usize] = value;
+    }
+    /**
+    Writes a floating point control register, the bits that are unused in the real register always read as 0
+     */
+    fn set_fp_control_register(&mut self, register: FpControlRegister, value: u32) {
+        if self.keep_history {
+            self.debugger
+                .add_mutation(MutationOperation::WriteFpControlRegister {
+                    register,
+                    old: self.cpu.get_fp_control_register(register),
+                });
+        }
+        match register {
+            FpControlRegister::FPCR => self.cpu.fpcr = value & 0x0000_FFF0,
+            FpControlRegister::FPSR => self.cpu.fpsr = value & 0x0FFF_FFF8,
+            FpControlRegister::FPIAR => self.cpu.fpiar = value,
+        }
+    }
+    /**
+    Gets the address of a memory operand that holds a floating point value of "bytes" length,
+    the pre decrement and post increment modes move the register by the size of the format
+     */
+    fn get_fp_operand_address(&mut self, op: &Operand, bytes: usize) -> RuntimeResult<usize> {
+        let address = match op {
+            Operand::PreIndirect(reg) => {
+                let address = self
+                    .get_a_reg_sized(*reg, Size::Long)
+                    .wrapping_sub(bytes as u32);
+                self.set_a_reg_sized(*reg, address, Size::Long);
+                address as usize
+            }
+            Operand::PostIndirect(reg) => {
+                let address = self.get_a_reg_sized(*reg, Size::Long);
+                self.set_a_reg_sized(*reg, address.wrapping_add(bytes as u32), Size::Long);
+                address as usize
+            }
+            Operand::Register(_) | Operand::Immediate(_) => {
+                return Err(RuntimeError::IncorrectAddressingMode(
+                    "Double and extended values can only be read from memory".to_string(),
+                ))
+            }
+            _ => self.get_operand_address(op)? as usize,
+        };
+        self.memory.verify_address(address, Size::Word)
+    }
+    /**
+    Reads a floating point source operand converting it from its format, a signaling NaN is
+    reported in the exceptions and turned into a quiet one
+     */
+    fn get_fp_operand_value(
+        &mut self,
+        op: &FpOperand,
+        format: FpFormat,
+    ) -> RuntimeResult<(f64, FpStatus)> {
+        let value = match op {
+            FpOperand::Register(reg) => self.cpu.fp_reg[*reg as usize],
+            FpOperand::Immediate(value) => *value,
+            FpOperand::Operand(op) => match format {
+                FpFormat::Single => {
+                    f32::from_bits(self.get_operand_value(op, Size::Long, Used::Once)?) as f64
+                }
+                FpFormat::Double | FpFormat::Extended => {
+                    let address = self.get_fp_operand_address(op, format.to_bytes())?;
+                    let bytes = self.memory.read_bytes(address, format.to_bytes())?;
+                    match format {
+                        FpFormat::Double => {
+                            let mut double = [0u8; 8];
+                            double.copy_from_slice(bytes);
+                            f64::from_be_bytes(double)
+                        }
+                        _ => extended_to_f64(bytes),
+                    }
+                }
+                _ => {
+                    let size = format.to_integer_size().unwrap_or(Size::Long);
+                    let value = self.get_operand_value(op, size, Used::Once)?;
+                    sign_extend_to_long(value, size) as f64
+                }
+            },
+        };
+        //the quiet bit is the msb of the mantissa
+        let quiet_bit = 1u64 << 51;
+        if value.is_nan() && value.to_bits() & quiet_bit == 0 {
+            let value = f64::from_bits(value.to_bits() | quiet_bit);
+            return Ok((value, FpStatus::SignalingNaN));
+        }
+        Ok((value, FpStatus::empty()))
+    }
+    /**
+    Stores a floating point value to a memory or data register operand converting it to the format,
+    integers are rounded with the FPCR rounding mode and saturate with an operand error when they don't fit
+     */
+    fn store_fp_operand_value(
+        &mut self,
+        op: &Operand,
+        value: f64,
+        format: FpFormat,
+    ) -> RuntimeResult<FpStatus> {
+        let mut exceptions = FpStatus::empty();
+        match format {
+            FpFormat::Single => {
+                let single = value as f32;
+                if single.is_infinite() && value.is_finite() {
+                    exceptions |= FpStatus::Overflow;
+                }
+                self.store_operand_value(op, single.to_bits(), Size::Long, Used::Once)?;
+            }
+            FpFormat::Double | FpFormat::Extended => {
+                let bytes = match format {
+                    FpFormat::Double => value.to_be_bytes().to_vec(),
+                    _ => f64_to_extended(value).to_vec(),
+                };
+                let address = self.get_fp_operand_address(op, bytes.len())?;
+                self.set_memory_bytes(address, &bytes)?;
+            }
+            _ => {
+                let size = format.to_integer_size().unwrap_or(Size::Long);
+                let (min, max) = match size {
+                    Size::Byte => (i8::MIN as f64, i8::MAX as f64),
+                    Size::Word => (i16::MIN as f64, i16::MAX as f64),
+                    Size::Long => (i32::MIN as f64, i32::MAX as f64),
+                };
+                let rounded = round_fp(value, (self.cpu.fpcr >> 4) & 0b11);
+                if rounded.is_nan() || rounded < min || rounded > max {
+                    exceptions |= FpStatus::OperandError;
+                }
+                let clamped = if rounded.is_nan() {
+                    max
+                } else {
+                    rounded.clamp(min, max)
+                };
+                self.store_operand_value(op, clamped as i32 as u32, size, Used::Once)?;
+            }
+        }
+        Ok(exceptions)
+    }
+    /**
+    Rounds a result to the precision selected in the FPCR, extended precision is kept as a double
+     */
+    fn round_fp_precision(&self, value: f64, exceptions: &mut FpStatus) -> f64 {
+        let (rounded, underflow) = match (self.cpu.fpcr >> 6) & 0b11 {
+            0b01 => {
+                let single = value as f32;
+                let underflow = single.is_subnormal() || (single == 0.0 && value != 0.0);
+                (single as f64, underflow)
+            }
+            _ => (value, value.is_subnormal()),
+        };
+        if rounded.is_infinite() && value.is_finite() {
+            exceptions.insert(FpStatus::Overflow);
+        }
+        if underflow {
+            exceptions.insert(FpStatus::Underflow);
+        }
+        rounded
+    }
+    /**
+    Executes a monadic or dyadic floating point operation, the source is the only operand of the monadic ones
+     */
+    fn execute_fp_operation(
+        &mut self,
+        operation: FpOperation,
+        source: f64,
+        dest: f64,
+        exceptions: &mut FpStatus,
+    ) -> f64 {
+        let result = match operation {
+            FpOperation::Add => dest + source,
+            FpOperation::Sub => dest - source,
+            FpOperation::Mul => dest * source,
+            FpOperation::Div => dest / source,
+            FpOperation::Mod | FpOperation::Rem => {
+                let (quotient, remainder) = match operation {
+                    FpOperation::Mod => ((dest / source).trunc(), dest % source),
+                    _ => (
+                        (dest / source).round_ties_even(),
+                        ieee_remainder(dest, source),
+                    ),
+                };
+                //the sign and the 7 lsb of the quotient are saved in the quotient byte of the FPSR
+                let mut quotient_byte = (quotient.abs() % 128.0) as u32;
+                if quotient.is_sign_negative() {
+                    quotient_byte |= 0x80;
+                }
+                let fpsr = (self.cpu.fpsr & !0x00FF_0000) | (quotient_byte << 16);
+                self.set_fp_control_register(FpControlRegister::FPSR, fpsr);
+                remainder
+            }
+            FpOperation::Scale => {
+                if source.is_finite() {
+                    let exp = source.trunc().clamp(-0x4000 as f64, 0x4000 as f64) as i32;
+                    scale_by_power_of_two(dest, exp)
+                } else {
+                    f64::NAN
+                }
+            }
+            FpOperation::Abs => source.abs(),
+            FpOperation::Neg => -source,
+            FpOperation::Sqrt => source.sqrt(),
+            FpOperation::Int => round_fp(source, (self.cpu.fpcr >> 4) & 0b11),
+            FpOperation::IntRZ => source.trunc(),
+            FpOperation::GetExp => match source {
+                s if s == 0.0 => s,
+                s if s.is_finite() => get_fp_exponent(s) as f64,
+                _ => f64::NAN,
+            },
+            FpOperation::GetMan => match source {
+                s if s == 0.0 => s,
+                s if s.is_finite() => scale_by_power_of_two(s, -get_fp_exponent(s)),
+                _ => f64::NAN,
+            },
+            FpOperation::Sin => source.sin(),
+            FpOperation::Cos => source.cos(),
+            FpOperation::Tan => source.tan(),
+            FpOperation::Asin => source.asin(),
+            FpOperation::Acos => source.acos(),
+            FpOperation::Atan => source.atan(),
+            FpOperation::Sinh => source.sinh(),
+            FpOperation::Cosh => source.cosh(),
+            FpOperation::Tanh => source.tanh(),
+            FpOperation::Atanh => source.atanh(),
+            FpOperation::Etox => source.exp(),
+            FpOperation::Etoxm1 => source.exp_m1(),
+            FpOperation::Twotox => source.exp2(),
+            FpOperation::Tentox => 10f64.powf(source),
+            FpOperation::Logn => source.ln(),
+            FpOperation::Lognp1 => source.ln_1p(),
+            FpOperation::Log10 => source.log10(),
+            FpOperation::Log2 => source.log2(),
+        };
+        let dyadic = operation.is_dyadic();
+        if result.is_nan() && !source.is_nan() && !(dyadic && dest.is_nan()) {
+            exceptions.insert(FpStatus::OperandError);
+        }
+        if result.is_infinite() && source.is_finite() && !(dyadic && dest.is_infinite()) {
+            //a pole of the function is a division by zero, anything else that becomes infinite overflowed
+            let pole = match operation {
+                FpOperation::Div | FpOperation::Logn | FpOperation::Log10 | FpOperation::Log2 => {
+                    source == 0.0
+                }
+                FpOperation::Lognp1 => source == -1.0,
+                FpOperation::Atanh => source.abs() == 1.0,
+                _ => false,
+            };
+            if pole {
+                exceptions.insert(FpStatus::DivideByZero);
+            } else {
+                exceptions.insert(FpStatus::Overflow);
+            }
+        }
+        result
+    }
+    /**
+    Updates the FPSR after a floating point instruction, the condition codes are set by the result (if any),
+    the exception byte is replaced by the exceptions of the instruction and those are added to the accrued byte
+     */
+    fn set_fp_status(&mut self, result: Option<f64>, exceptions: FpStatus) {
+        let mut status = FpStatus::from_bits_retain(self.cpu.fpsr);
+        if let Some(value) = result {
+            status.remove(FpStatus::CONDITION_CODES);
+            status.set(FpStatus::Negative, value.is_sign_negative());
+            status.set(FpStatus::Zero, value == 0.0);
+            status.set(FpStatus::Infinity, value.is_infinite());
+            status.set(FpStatus::NotANumber, value.is_nan());
+        }
+        status.remove(FpStatus::EXCEPTIONS);
+        status.insert(exceptions.accrued());
+        self.set_fp_control_register(FpControlRegister::FPSR, status.bits());
+    }
+    /**
+    Evaluates a floating point condition on the FPSR condition codes, the IEEE non aware conditions
+    signal a branch on unordered (BSUN) when the last result was a NaN
+     */
+    fn get_fp_condition_value(&mut self, condition: &FpCondition) -> bool {
+        let status = FpStatus::from_bits_retain(self.cpu.fpsr);
+        let n = status.contains(FpStatus::Negative);
+        let z = status.contains(FpStatus::Zero);
+        let nan = status.contains(FpStatus::NotANumber);
+        if nan && condition.is_signaling() {
+            let status = status | FpStatus::BranchUnordered.accrued();
+            self.set_fp_control_register(FpControlRegister::FPSR, status.bits());
+        }
+        match condition {
+            FpCondition::False | FpCondition::SignalingFalse => false,
+            FpCondition::Equal | FpCondition::SignalingEqual => z,
+            FpCondition::OrderedGreaterThan | FpCondition::GreaterThan => !(nan || z || n),
+            FpCondition::OrderedGreaterOrEqual | FpCondition::GreaterThanOrEqual => {
+                z || !(nan || n)
+            }
+            FpCondition::OrderedLessThan | FpCondition::LessThan => n && !(nan || z),
+            FpCondition::OrderedLessOrEqual | FpCondition::LessThanOrEqual => z || (n && !nan),
+            FpCondition::OrderedGreaterOrLess | FpCondition::GreaterOrLess => !(nan || z),
+            FpCondition::Ordered | FpCondition::GreaterLessOrEqual => !nan,
+            FpCondition::Unordered | FpCondition::NotGreaterLessOrEqual => nan,
+            FpCondition::UnorderedOrEqual | FpCondition::NotGreaterOrLess => nan || z,
+            FpCondition::UnorderedOrGreaterThan | FpCondition::NotLessThanOrEqual => {
+                nan || !(n || z)
+            }
+            FpCondition::UnorderedOrGreaterOrEqual | FpCondition::NotLessThan => nan || z || !n,
+            FpCondition::UnorderedOrLessThan | FpCondition::NotGreaterThanOrEqual => {
+                nan || (n && !z)
+            }
+            FpCondition::UnorderedOrLessOrEqual | FpCondition::NotGreaterThan => nan || z || n,
+            FpCondition::NotEqual | FpCondition::SignalingNotEqual => !z,
+            FpCondition::True | FpCondition::SignalingTrue => true,
+        }
     }
     pub fn get_next_instruction(&self) -> Option<&InstructionLine> {
         self.get_instruction_at(self.pc)
@@ -1774,7 +3097,9 @@ impl Interpreter {
         match op {
             Operand::Immediate(v) => Ok(*v),
             Operand::Register(op) => Ok(self.get_register_value(*op, size)),
-            Operand::Absolute(address) => Ok(self.memory.read_size(*address, size)?),
+            Operand::Absolute(address)
+            | Operand::AbsoluteShort(address)
+            | Operand::AbsoluteLong(address) => Ok(self.memory.read_size(*address, size)?),
 
             Operand::Indirect(reg) => {
                 let address = self.get_a_reg_sized(*reg, Size::Long);
@@ -1802,21 +3127,29 @@ impl Interpreter {
                 let address = address.wrapping_add(*offset);
                 Ok(self.memory.read_size(address as usize, size)?)
             }
-            Operand::IndirectIndex {
-                offset,
-                base,
-                index,
-            } => {
-                //TODO not sure if this is how it should work
-                //TODO should this be i32?
-                let base_value = self.get_register_value(*base, Size::Long) as i32;
-                let index_value = self.get_register_value(index.register, index.size);
-                let index_value = sign_extend_to_long(index_value, index.size);
-                let final_address = base_value.wrapping_add(*offset).wrapping_add(index_value);
-                Ok(self.memory.read_size(final_address as usize, size)?)
+            Operand::IndirectIndex { .. }
+            | Operand::PCDisplacement { .. }
+            | Operand::PCIndex { .. }
+            | Operand::FullIndex { .. } => {
+                let address = self.get_operand_address(op)?;
+                Ok(self.memory.read_size(address as usize, size)?)
             }
         }
     }
+    fn get_index_value(&self, index: &IndexRegister) -> i32 {
+        let value = self.get_register_value(index.register, index.size);
+        sign_extend_to_long(value, index.size).wrapping_mul(index.scale as i32)
+    }
+    /**
+    The 68000 only has the brief extension word, the scaled index and the displacements
+    that don't fit in 8 bits need the full extension word of the 68020
+     */
+    fn verify_brief_index(&self, offset: i32, index: &IndexRegister) -> RuntimeResult<()> {
+        if index.scale > 1 || !(-128..=127).contains(&offset) {
+            self.verify_cpu_model(CpuModel::M68020)?;
+        }
+        Ok(())
+    }
     fn get_operand_address(&mut self, op: &Operand) -> RuntimeResult<u32> {
         match op {
             Operand::PreIndirect(op) | Operand::PostIndirect(op) => {
@@ -1834,14 +3167,59 @@ impl Interpreter {
                 base,
                 index,
             } => {
-                //TODO not sure if this is how it should work
+                self.verify_brief_index(*offset, index)?;
                 let base_value = self.get_register_value(*base, Size::Long) as i32;
-                let index_value = self.get_register_value(index.register, index.size);
-                let index_value = sign_extend_to_long(index_value, index.size);
+                let index_value = self.get_index_value(index);
                 let final_address = base_value.wrapping_add(*offset).wrapping_add(index_value);
                 Ok(final_address as u32)
             }
-            Operand::Absolute(address) => Ok(*address as u32),
+            Operand::PCDisplacement { offset } => {
+                let pc = self.last_line_address as i32 + 2;
+                Ok(pc.wrapping_add(*offset) as u32)
+            }
+            Operand::PCIndex { offset, index } => {
+                self.verify_brief_index(*offset, index)?;
+                let pc = self.last_line_address as i32 + 2;
+                let index_value = self.get_index_value(index);
+                Ok(pc.wrapping_add(*offset).wrapping_add(index_value) as u32)
+            }
+            Operand::FullIndex {
+                base,
+                base_displacement,
+                index,
+                indirection,
+            } => {
+                self.verify_cpu_model(CpuModel::M68020)?;
+                let base_value = match base {
+                    IndexBase::Register(reg) => self.get_register_value(*reg, Size::Long) as i32,
+                    IndexBase::PC => self.last_line_address as i32 + 2,
+                    IndexBase::Suppressed => 0,
+                };
+                let index_value = match index {
+                    Some(index) => self.get_index_value(index),
+                    None => 0,
+                };
+                let base_address = base_value.wrapping_add(*base_displacement);
+                let address = match indirection {
+                    MemoryIndirection::None => base_address.wrapping_add(index_value),
+                    MemoryIndirection::PreIndexed(outer_displacement) => {
+                        let address = base_address.wrapping_add(index_value);
+                        let intermediate = self.memory.read_long(address as u32 as usize)? as i32;
+                        intermediate.wrapping_add(*outer_displacement)
+                    }
+                    MemoryIndirection::PostIndexed(outer_displacement) => {
+                        let intermediate =
+                            self.memory.read_long(base_address as u32 as usize)? as i32;
+                        intermediate
+                            .wrapping_add(index_value)
+                            .wrapping_add(*outer_displacement)
+                    }
+                };
+                Ok(address as u32)
+            }
+            Operand::Absolute(address)
+            | Operand::AbsoluteShort(address)
+            | Operand::AbsoluteLong(address) => Ok(*address as u32),
             _ => Err(RuntimeError::IncorrectAddressingMode(
                 "Attempted to get address of non address addressing mode".to_string(),
             )),
@@ -1858,11 +3236,21 @@ impl Interpreter {
             Operand::Immediate(_) => Err(RuntimeError::IncorrectAddressingMode(
                 "Attempted to store to immediate value".to_string(),
             )),
+            Operand::PCDisplacement { .. }
+            | Operand::PCIndex { .. }
+            | Operand::FullIndex {
+                base: IndexBase::PC,
+                ..
+            } => Err(RuntimeError::IncorrectAddressingMode(
+                "Attempted to store to pc relative address".to_string(),
+            )),
             Operand::Register(op) => {
                 self.set_register_value(*op, value, size);
                 Ok(())
             }
-            Operand::Absolute(address) => Ok(self.set_memory_value(*address, size, value)?),
+            Operand::Absolute(address)
+            | Operand::AbsoluteShort(address)
+            | Operand::AbsoluteLong(address) => Ok(self.set_memory_value(*address, size, value)?),
             Operand::Indirect(reg) => {
                 let address = self.get_a_reg_sized(*reg, Size::Long);
                 Ok(self.set_memory_value(address as usize, size, value)?)
@@ -1897,16 +3285,9 @@ impl Interpreter {
                 let address = address.wrapping_add(*offset);
                 Ok(self.set_memory_value(address as usize, size, value)?)
             }
-            Operand::IndirectIndex {
-                offset,
-                index,
-                base,
-            } => {
-                let base_value = self.get_register_value(*base, Size::Long) as i32;
-                let index_value = self.get_register_value(index.register, index.size);
-                let index_value = sign_extend_to_long(index_value, index.size);
-                let final_address = base_value.wrapping_add(*offset).wrapping_add(index_value);
-                Ok(self.set_memory_value(final_address as usize, size, value)?)
+            Operand::IndirectIndex { .. } | Operand::FullIndex { .. } => {
+                let address = self.get_operand_address(op)?;
+                Ok(self.set_memory_value(address as usize, size, value)?)
             }
         }
     }
@@ -1998,6 +3379,222 @@ impl Interpreter {
     pub fn get_flag(&self, flag: Flags) -> bool {
         self.cpu.ccr.contains(flag)
     }
+    #[inline(always)]
+    pub fn get_sr(&self) -> u16 {
+        self.cpu.get_sr()
+    }
+    /**
+    Writes the whole status register, when the supervisor bit changes the active stack pointer (a7)
+    is swapped with the one of the other mode
+     */
+    pub fn set_sr(&mut self, value: u16) {
+        let was_supervisor = self.cpu.is_supervisor();
+        if self.keep_history {
+            self.debugger
+                .add_mutation(MutationOperation::WriteStatusRegister {
+                    old: self.cpu.get_sr(),
+                });
+        }
+        self.cpu.sr = SystemFlags::from_bits_truncate(value);
+        self.cpu.ccr = Flags::from_ccr(value as u8);
+        if was_supervisor != self.cpu.is_supervisor() {
+            let active = self.cpu.a_reg[7].get_long();
+            let inactive = self.cpu.shadow_sp.get_long();
+            self.set_register_value(RegisterOperand::Address(7), inactive, Size::Long);
+            self.set_shadow_sp(active);
+        }
+    }
+    fn set_shadow_sp(&mut self, value: u32) {
+        if self.keep_history {
+            self.debugger
+                .add_mutation(MutationOperation::WriteShadowStackPointer {
+                    old: self.cpu.shadow_sp.get_long(),
+                });
+        }
+        self.cpu.shadow_sp.store_long(value);
+    }
+    /**
+    Writes a MOVEC control register, only the low 3 bits of the function codes are kept,
+    MOVEC is privileged so the USP is always the shadow stack pointer
+     */
+    fn set_control_register(&mut self, register: ControlRegister, value: u32) {
+        if register == ControlRegister::USP {
+            return self.set_shadow_sp(value);
+        }
+        if self.keep_history {
+            self.debugger
+                .add_mutation(MutationOperation::WriteControlRegister {
+                    register,
+                    old: self.cpu.get_control_register(register),
+                });
+        }
+        match register {
+            ControlRegister::SFC => self.cpu.sfc = (value & 0b111) as u8,
+            ControlRegister::DFC => self.cpu.dfc = (value & 0b111) as u8,
+            ControlRegister::VBR => self.cpu.vbr = value,
+            ControlRegister::USP => {}
+        }
+    }
+    /**
+    When exception processing is enabled, the errors that the 68000 reports as exceptions are vectored
+    to their handler, otherwise (or if there is no handler installed) the error stops the program
+     */
+    fn handle_runtime_error(&mut self, error: RuntimeError) -> RuntimeResult<()> {
+        if !self.exception_processing {
+            return Err(error);
+        }
+        //the address error is the only one of group 0, it stacks the extra frame with the accessed address
+        let (vector, pc, access_address) = match &error {
+            RuntimeError::AddressError { address, .. } => (
+                ExceptionVector::AddressError,
+                self.last_line_address,
+                Some(*address),
+            ),
+            RuntimeError::DivisionByZero => (ExceptionVector::DivisionByZero, self.pc, None),
+            RuntimeError::PrivilegeViolation { address } => {
+                (ExceptionVector::PrivilegeViolation, *address, None)
+            }
+            RuntimeError::IllegalInstruction { address } => {
+                (ExceptionVector::IllegalInstruction, *address, None)
+            }
+            RuntimeError::ChkOutOfBounds { .. } => (ExceptionVector::Chk, self.pc, None),
+            RuntimeError::TrapOnOverflow { .. } => (ExceptionVector::TrapV, self.pc, None),
+            _ => return Err(error),
+        };
+        if self.raise_exception(vector as u8, pc, access_address)? {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+    /**
+    Enters supervisor mode, stacks the PC and SR (plus the group 0 frame if an access address is given)
+    and jumps to the handler of the vector, returns false if no handler is installed
+     */
+    fn raise_exception(
+        &mut self,
+        vector: u8,
+        pc: usize,
+        access_address: Option<usize>,
+    ) -> RuntimeResult<bool> {
+        let handler = self
+            .memory
+            .read_long(self.cpu.vbr as usize + vector as usize * 4)?;
+        if handler == 0 {
+            return Ok(false);
+        }
+        let sr = self.cpu.get_sr();
+        self.set_sr((sr | SystemFlags::Supervisor.bits()) & !SystemFlags::Trace.bits());
+        self.push_stack(pc as u32, Size::Long)?;
+        self.push_stack(sr as u32, Size::Word)?;
+        if let Some(address) = access_address {
+            let opcode = match self.execution_mode {
+                ExecutionMode::Precompiled => self
+                    .get_instruction_at(self.last_line_address)
+                    .map(|ins| ins.words[0])
+                    .unwrap_or(0),
+                ExecutionMode::FetchDecode => {
+                    self.memory.read_word(self.last_line_address).unwrap_or(0)
+                }
+            };
+            self.push_stack(opcode as u32, Size::Word)?;
+            self.push_stack(address as u32, Size::Long)?;
+            //function code of a data access in the mode the cpu was in
+            let function_code = if sr & SystemFlags::Supervisor.bits() != 0 {
+                0b101
+            } else {
+                0b001
+            };
+            self.push_stack(function_code, Size::Word)?;
+        }
+        self.pc = handler as usize;
+        Ok(true)
+    }
+    /**
+    Raises an external interrupt of the given level (1-7), waking the cpu up if it was stopped.
+    Returns false if the interrupt is masked, level 7 is non maskable.
+    If an autovector handler is installed it is called and the interrupt mask is raised to the level,
+    the interrupt is a step of its own in the history so it is undone separately from the instructions
+     */
+    pub fn raise_interrupt(&mut self, level: u8) -> RuntimeResult<bool> {
+        if !(1..=7).contains(&level) {
+            return Err(RuntimeError::Raw(format!(
+                "Invalid interrupt level: {}, must be between 1 and 7",
+                level
+            )));
+        }
+        self.verify_can_run()?;
+        if level < 7 && level <= self.cpu.sr.get_interrupt_mask() {
+            return Ok(false);
+        }
+        if self.keep_history {
+            self.debugger
+                .add_step(ExecutionStep::new(self.pc, self.cpu.ccr));
+        }
+        let vector = ExceptionVector::SpuriousInterrupt as u8 + level;
+        if self.raise_exception(vector, self.pc, None)? {
+            let sr = self.cpu.get_sr() & !SystemFlags::InterruptMask.bits();
+            self.set_sr(sr | ((level as u16) << 8));
+        }
+        if self.status == InterpreterStatus::Stopped {
+            if self.keep_history {
+                self.debugger
+                    .add_mutation(MutationOperation::WriteInterpreterStatus { old: self.status });
+            }
+            //edge case if the stop was the last instruction and there is no handler
+            self.status = if self.has_reached_bottom() {
+                InterpreterStatus::Terminated
+            } else {
+                InterpreterStatus::Running
+            };
+        }
+        Ok(true)
+    }
+    /**
+    The number of times the RESET instruction asserted the reset line
+     */
+    pub fn get_reset_signals(&self) -> usize {
+        self.reset_signals
+    }
+    fn push_stack(&mut self, value: u32, size: Size) -> RuntimeResult<()> {
+        let sp = self.get_sp() - size.to_bytes();
+        self.set_memory_value(sp, size, value)?;
+        self.set_sp(sp);
+        Ok(())
+    }
+    /**
+    The instructions that the selected cpu model does not have are illegal instructions
+     */
+    fn verify_cpu_model(&self, model: CpuModel) -> RuntimeResult<()> {
+        if self.cpu_model >= model {
+            Ok(())
+        } else {
+            Err(RuntimeError::IllegalInstruction {
+                address: self.last_line_address,
+            })
+        }
+    }
+    /**
+    Without a floating point coprocessor there is nothing to run the fpu instructions
+     */
+    fn verify_fpu_model(&self) -> RuntimeResult<()> {
+        if self.fpu_model != FpuModel::None {
+            Ok(())
+        } else {
+            Err(RuntimeError::IllegalInstruction {
+                address: self.last_line_address,
+            })
+        }
+    }
+    fn verify_supervisor(&self) -> RuntimeResult<()> {
+        if self.cpu.is_supervisor() {
+            Ok(())
+        } else {
+            Err(RuntimeError::PrivilegeViolation {
+                address: self.last_line_address,
+            })
+        }
+    }
     fn set_flag(&mut self, flag: Flags, value: bool) {
         self.cpu.ccr.set(flag, value)
     }
@@ -2014,6 +3611,97 @@ impl Interpreter {
         }
         self.cpu.ccr = flags;
     }
+    /**
+    Returns the offset and width of a bit field, the register offsets are signed and the
+    widths are taken modulo 32, with 0 meaning 32
+     */
+    fn get_bit_field_bounds(&self, field: &BitField) -> (i32, u32) {
+        let offset = match field.offset {
+            BitFieldValue::Immediate(offset) => offset as i32,
+            BitFieldValue::Register(reg) => self.get_register_value(reg, Size::Long) as i32,
+        };
+        let width = match field.width {
+            BitFieldValue::Immediate(width) => width as u32,
+            BitFieldValue::Register(reg) => self.get_register_value(reg, Size::Long),
+        };
+        match width % 32 {
+            0 => (offset, 32),
+            width => (offset, width),
+        }
+    }
+    /**
+    Returns the address of the first byte of a memory bit field, the offset of the field inside
+    that byte and how many bytes it spans, up to 5
+     */
+    fn get_bit_field_location(
+        &mut self,
+        op: &Operand,
+        offset: i32,
+        width: u32,
+    ) -> RuntimeResult<(usize, u32, usize)> {
+        let address = self.get_operand_address(op)?;
+        let address = address.wrapping_add((offset >> 3) as u32) as usize;
+        let bit_offset = (offset & 7) as u32;
+        let length = (bit_offset + width).div_ceil(8) as usize;
+        Ok((address, bit_offset, length))
+    }
+    fn read_bit_field(&mut self, op: &Operand, offset: i32, width: u32) -> RuntimeResult<u32> {
+        match op {
+            //in a register the field wraps around, the offset starts from the most significant bit
+            Operand::Register(reg) => {
+                let value = self.get_register_value(*reg, Size::Long);
+                Ok(value.rotate_left(offset as u32 % 32) >> (32 - width))
+            }
+            _ => {
+                let (address, bit_offset, length) =
+                    self.get_bit_field_location(op, offset, width)?;
+                let mut data = 0u64;
+                for i in 0..length {
+                    data = (data << 8) | self.memory.read_byte(address + i)? as u64;
+                }
+                let shift = length as u32 * 8 - bit_offset - width;
+                Ok((data >> shift) as u32 & bit_field_mask(width))
+            }
+        }
+    }
+    fn write_bit_field(
+        &mut self,
+        op: &Operand,
+        offset: i32,
+        width: u32,
+        value: u32,
+    ) -> RuntimeResult<()> {
+        let value = value & bit_field_mask(width);
+        match op {
+            Operand::Register(reg) => {
+                let rotation = offset as u32 % 32;
+                let mask = (bit_field_mask(width) << (32 - width)).rotate_right(rotation);
+                let value = (value << (32 - width)).rotate_right(rotation);
+                let old = self.get_register_value(*reg, Size::Long);
+                self.set_register_value(*reg, (old & !mask) | value, Size::Long);
+            }
+            _ => {
+                let (address, bit_offset, length) =
+                    self.get_bit_field_location(op, offset, width)?;
+                let mut data = 0u64;
+                for i in 0..length {
+                    data = (data << 8) | self.memory.read_byte(address + i)? as u64;
+                }
+                let shift = length as u32 * 8 - bit_offset - width;
+                let mask = (bit_field_mask(width) as u64) << shift;
+                let data = (data & !mask) | ((value as u64) << shift);
+                for i in 0..length {
+                    let byte = (data >> ((length - 1 - i) * 8)) as u32 & 0xFF;
+                    self.set_memory_value(address + i, Size::Byte, byte)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    fn set_bit_field_flags(&mut self, value: u32, width: u32) {
+        //moving the field to the top makes its most significant bit the sign
+        self.set_logic_flags(value << (32 - width), Size::Long);
+    }
     fn set_bit_test_flags(&mut self, value: u32, bitnum: u32, size: Size) -> u32 {
         let mask = 0x1 << (bitnum % size.to_bits() as u32);
         self.set_flag(Flags::Zero, (value & mask) == 0);
@@ -2040,6 +3728,18 @@ impl Interpreter {
         self.cpu.ccr = flags;
     }
 
+    /**
+    Used by the multi precision instructions (ADDX, SUBX, NEGX, ABCD, SBCD, NBCD), the zero flag is only cleared
+    when the result is not zero, so that it stays set only if every part of the chain was zero.
+    N and V are undefined for the BCD instructions on the 68000, here N follows the msb of the result and V is cleared
+     */
+    fn set_extended_flags(&mut self, value: u32, size: Size, carry: bool, overflow: bool) {
+        let zero = self.get_flag(Flags::Zero) && get_value_sized(value, size) == 0;
+        self.set_compare_flags(value, size, carry, overflow);
+        self.set_flag(Flags::Zero, zero);
+        self.set_flag(Flags::Extend, carry);
+    }
+
     pub fn get_condition_value(&self, cond: &Condition) -> bool {
         match cond {
             Condition::True => true,
@@ -2081,10 +3781,50 @@ impl Interpreter {
 
 #[wasm_bindgen]
 impl Interpreter {
-    pub fn wasm_read_memory_bytes(&self, address: usize, size: usize) -> Vec<u8> {
+    pub fn wasm_from_srecords(
+        text: String,
+        interpreter_options: JsValue,
+    ) -> Result<Interpreter, JsValue> {
+        console_error_panic_hook::set_once();
+        let interpreter_options: InterpreterOptions =
+            serde_wasm_bindgen::from_value(interpreter_options).unwrap();
+        match Interpreter::from_srecords(&text, Some(interpreter_options)) {
+            Ok(interpreter) => Ok(interpreter),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+    pub fn wasm_read_memory_bytes(&self, address: usize, size: usize) -> Result<Vec<u8>, JsValue> {
         match self.memory.read_bytes(address, size) {
-            Ok(bytes) => bytes.to_vec(),
-            Err(_) => vec![],
+            Ok(bytes) => Ok(bytes.to_vec()),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+    pub fn wasm_dump_binary(&self, region: JsValue) -> Result<Vec<u8>, JsValue> {
+        let region: MemoryRegion = serde_wasm_bindgen::from_value(region)?;
+        match self.memory.dump_binary(&region) {
+            Ok(bytes) => Ok(bytes),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+    pub fn wasm_dump_intel_hex(&self, region: JsValue) -> Result<String, JsValue> {
+        let region: MemoryRegion = serde_wasm_bindgen::from_value(region)?;
+        match self.memory.dump_intel_hex(&region) {
+            Ok(text) => Ok(text),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+    pub fn wasm_load_binary(&mut self, bytes: Vec<u8>, region: JsValue) -> Result<(), JsValue> {
+        let region: MemoryRegion = serde_wasm_bindgen::from_value(region)?;
+        match self.load_binary(&bytes, &region) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+    pub fn wasm_load_intel_hex(&mut self, text: String, region: JsValue) -> Result<(), JsValue> {
+        let region: Option<MemoryRegion> = serde_wasm_bindgen::from_value(region)?;
+        match self.load_intel_hex(&text, region.as_ref()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
         }
     }
     pub fn wasm_write_memory_bytes(
@@ -2092,7 +3832,7 @@ impl Interpreter {
         address: usize,
         bytes: Vec<u8>,
     ) -> Result<(), JsValue> {
-        match self.memory.write_bytes(address, &bytes) {
+        match self.load_bytes(address, &bytes) {
             Ok(_) => Ok(()),
             Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
         }
@@ -2112,6 +3852,12 @@ impl Interpreter {
             None => JsValue::NULL,
         }
     }
+    pub fn wasm_disassemble(&self, address: usize, length: usize) -> Result<JsValue, JsValue> {
+        match self.disassemble(address, length) {
+            Ok(lines) => Ok(serde_wasm_bindgen::to_value(&lines).unwrap()),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
     pub fn wasm_can_undo(&self) -> bool {
         self.debugger.can_undo()
     }
@@ -2176,6 +3922,9 @@ impl Interpreter {
     pub fn wasm_get_flags_as_number(&self) -> u16 {
         self.cpu.ccr.bits()
     }
+    pub fn wasm_get_sr(&self) -> u16 {
+        self.get_sr()
+    }
     pub fn wasm_undo(&mut self) -> Result<JsValue, JsValue> {
         match self.undo() {
             Ok(step) => Ok(serde_wasm_bindgen::to_value(&step).unwrap()),
@@ -2241,6 +3990,15 @@ impl Interpreter {
             Err(_) => Ok(JsValue::NULL),
         }
     }
+    pub fn wasm_raise_interrupt(&mut self, level: u8) -> Result<bool, JsValue> {
+        match self.raise_interrupt(level) {
+            Ok(accepted) => Ok(accepted),
+            Err(e) => Err(serde_wasm_bindgen::to_value(&e).unwrap()),
+        }
+    }
+    pub fn wasm_get_reset_signals(&self) -> usize {
+        self.get_reset_signals()
+    }
     pub fn wasm_answer_interrupt(&mut self, value: JsValue) -> Result<(), String> {
         match serde_wasm_bindgen::from_value(value.clone()) {
             Ok(answer) => self.answer_interrupt(answer).unwrap(),
@@ -25,6 +25,41 @@ impl LexedRegisterType {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LexedSpecialRegister {
+    SR,
+    CCR,
+    USP,
+    //only used as the base of the memory indirect modes
+    PC,
+    //control registers of the floating point coprocessor
+    FPCR,
+    FPSR,
+    FPIAR,
+    //control registers of the 68010
+    SFC,
+    DFC,
+    VBR,
+}
+
+impl LexedSpecialRegister {
+    pub fn from_string(string: &str) -> Result<LexedSpecialRegister, String> {
+        match string.to_lowercase().as_str() {
+            "sr" => Ok(LexedSpecialRegister::SR),
+            "ccr" => Ok(LexedSpecialRegister::CCR),
+            "usp" => Ok(LexedSpecialRegister::USP),
+            "pc" => Ok(LexedSpecialRegister::PC),
+            "fpcr" => Ok(LexedSpecialRegister::FPCR),
+            "fpsr" => Ok(LexedSpecialRegister::FPSR),
+            "fpiar" => Ok(LexedSpecialRegister::FPIAR),
+            "sfc" => Ok(LexedSpecialRegister::SFC),
+            "dfc" => Ok(LexedSpecialRegister::DFC),
+            "vbr" => Ok(LexedSpecialRegister::VBR),
+            _ => Err(format!("Invalid special register '{}'", string)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub enum LexedSize {
@@ -33,14 +68,20 @@ pub enum LexedSize {
     Long,
     Unspecified,
     Unknown,
+    //only used by the branches, same as byte, or as single precision by the floating point instructions
+    Short,
+    Double,
+    Extended,
 }
 
 impl LexedSize {
     pub fn to_bytes(&self, default: LexedSize) -> u8 {
         match self {
-            LexedSize::Byte => 1,
+            LexedSize::Byte | LexedSize::Short => 1,
             LexedSize::Word => 2,
             LexedSize::Long => 4,
+            LexedSize::Double => 8,
+            LexedSize::Extended => 12,
             LexedSize::Unspecified => default.to_bytes(LexedSize::Unknown),
             _ => 0,
         }
@@ -48,6 +89,15 @@ impl LexedSize {
     pub fn to_bytes_word_default(&self) -> u8 {
         self.to_bytes(LexedSize::Word)
     }
+    /**
+    Size of one element of the data directives, where .s is a single precision float
+     */
+    pub fn to_data_bytes(&self) -> u8 {
+        match self {
+            LexedSize::Short => 4,
+            _ => self.to_bytes_word_default(),
+        }
+    }
     pub fn to_bits(&self, default: LexedSize) -> u8 {
         self.to_bytes(default) * 8
     }
@@ -64,7 +114,15 @@ pub enum LexedOperand {
         mask: u16,
     },
     Register(LexedRegisterType, String),
+    FloatRegister(String),
     RegisterWithSize(LexedRegisterType, String, LexedSize),
+    RegisterPair(Box<LexedOperand>, Box<LexedOperand>),
+    BitField {
+        operand: Box<LexedOperand>,
+        offset: Box<LexedOperand>,
+        width: Box<LexedOperand>,
+    },
+    SpecialRegister(LexedSpecialRegister),
     Indirect(Box<LexedOperand>),
     IndirectDisplacement {
         offset: String,
@@ -74,9 +132,28 @@ pub enum LexedOperand {
         offset: String,
         operands: Vec<LexedOperand>,
     },
+    PCDisplacement {
+        offset: String,
+    },
+    PCIndex {
+        offset: String,
+        index: Box<LexedOperand>,
+    },
+    ScaledIndex {
+        register: Box<LexedOperand>,
+        scale: String,
+    },
+    MemoryIndirect {
+        base_displacement: String,
+        base: Option<Box<LexedOperand>>,
+        index: Option<Box<LexedOperand>>,
+        pre_indexed: bool,
+        outer_displacement: String,
+    },
     PostIndirect(Box<LexedOperand>),
     PreIndirect(Box<LexedOperand>),
     Absolute(String),
+    AbsoluteWithSize(String, LexedSize),
     Label(String),
     Other(String),
 }
@@ -87,9 +164,14 @@ impl LexedOperand {
             LexedOperand::Indirect(_) => true,
             LexedOperand::IndirectDisplacement { .. } => true,
             LexedOperand::IndirectIndex { .. } => true,
+            LexedOperand::PCDisplacement { .. } => true,
+            LexedOperand::PCIndex { .. } => true,
+            LexedOperand::MemoryIndirect { .. } => true,
             LexedOperand::PostIndirect(_) => true,
             LexedOperand::PreIndirect(_) => true,
             LexedOperand::Absolute(_) => true,
+            LexedOperand::AbsoluteWithSize(_, _) => true,
+            LexedOperand::BitField { operand, .. } => operand.affects_memory(),
 
             _ => false,
         }
@@ -125,15 +207,24 @@ pub enum LexedLine {
 #[wasm_bindgen]
 pub enum OperandKind {
     Register,
+    FloatRegister,
     RegisterList,
     RegisterWithSize,
+    RegisterPair,
+    BitField,
+    SpecialRegister,
     Immediate,
     Indirect,
     IndirectDisplacement,
     IndirectIndex,
+    PCDisplacement,
+    PCIndex,
+    ScaledIndex,
+    MemoryIndirect,
     PostIndirect,
     PreIndirect,
     Absolute,
+    AbsoluteWithSize,
 }
 
 #[derive(Debug, Clone)]
@@ -169,11 +260,19 @@ At the same time, the directive should have dc/ds/dcb/etc and not just org
 enum Grammar {
     Directive,
     Register,
+    FloatRegister,
     RegisterWithSize,
+    RegisterPair,
+    BitField,
+    SpecialRegister,
     Indirect,
     RegisterRange,
     IndirectDisplacement,
     IndirectIndex,
+    PCDisplacement,
+    PCIndex,
+    ScaledIndex,
+    MemoryIndirect,
     PostIndirect,
     PreIndirect,
     Immediate,
@@ -184,6 +283,7 @@ enum Grammar {
     Operand,
     OperandArg,
     Absolute,
+    AbsoluteWithSize,
 }
 
 bitflags! {
@@ -205,6 +305,7 @@ impl Grammar {
         match &self {
             Grammar::Directive => r"(.+\s+equ\s+.+)|((org|dc|dcb|ds)\s*.*)".to_string(),
             Grammar::Register => r"(d\d|a\d|sp)".to_string(),
+            Grammar::FloatRegister => r"fp[0-7]".to_string(),
             Grammar::RegisterRange => {
                 let r = Grammar::Register.get_regex();
                 //this accepts strings like: "d0-d5/a0-a6/a0/a4"
@@ -214,11 +315,25 @@ impl Grammar {
                 )
             }
             Grammar::RegisterWithSize => format!(r"({})\.(b|w|l)", Grammar::Register.get_regex()),
+            Grammar::RegisterPair => {
+                let r = Grammar::Register.get_regex();
+                //this accepts strings like: "d1:d0"
+                format!("({}):({})", r, r)
+            }
+            //this accepts strings like: "d0{4:8}" or "(a0){d1:d2}"
+            Grammar::BitField => r"(.+)\{([^{}:]+):([^{}:]+)\}".to_string(),
+            Grammar::SpecialRegister => r"(sr|ccr|usp|fpcr|fpsr|fpiar|sfc|dfc|vbr)".to_string(),
             Grammar::Indirect => format!(r"\({}\)", Grammar::Register.get_regex()),
             Grammar::IndirectDisplacement => {
                 format!(r"([^\r\n\t\f\v,])*\({}\)", Grammar::Register.get_regex())
             }
             Grammar::IndirectIndex => r"([^\r\n\t\f\v,])*\((.+,)+.+\)".to_string(),
+            Grammar::PCDisplacement => r"([^\r\n\t\f\v,])*\(pc\)".to_string(),
+            Grammar::PCIndex => r"([^\r\n\t\f\v,])*\(pc,.+\)".to_string(),
+            //this accepts strings like: "d1.w*4"
+            Grammar::ScaledIndex => format!(r"({})(\.(w|l))?\*\S+", Grammar::Register.get_regex()),
+            //this accepts strings like: "([4, a0], d1*2, 8)" or "([4, a0, d1*2], 8)"
+            Grammar::MemoryIndirect => r"\(\[.*\].*\)".to_string(),
             Grammar::PostIndirect => r"\(\w+\)\+".to_string(), //TODO should i include registers in here or leave it?
             Grammar::PreIndirect => r"-\(\w+\)".to_string(),
             Grammar::Immediate => r"#(('.+')|(\S+))".to_string(), //TODO could #add absolute here but it wouldn't change the end result
@@ -227,6 +342,7 @@ impl Grammar {
             Grammar::Label => r"\w+:$".to_string(),
             Grammar::LabelInclusive => r"\w+:.*".to_string(),
             Grammar::Absolute => r"((%|@|$|)\w+)|('.')|(\d+)".to_string(), //TODO this does not include labels
+            Grammar::AbsoluteWithSize => r"[^\s#\(\)',]+\.(w|l)".to_string(),
             Grammar::OperandArg => r"(\w*\((?:.+,)+.+\)\w*)|(\w+)|(#\S+)".to_string(),
             Grammar::Operand => format!(
                 r"(({})|({})|({})|({})|({})|({})|({})|({}))",
@@ -260,14 +376,23 @@ indirect_displacement
 
 struct AsmRegex {
     register_only: Regex,
+    float_register_only: Regex,
     register_list_only: Regex,
     register_with_size_only: Regex,
+    register_pair_only: Regex,
+    bit_field_only: Regex,
+    special_register_only: Regex,
     immediate_only: Regex,
     indirect_only: Regex,
     indirect_displacement_only: Regex,
     indirect_index_only: Regex,
+    pc_displacement_only: Regex,
+    pc_index_only: Regex,
+    scaled_index_only: Regex,
+    memory_indirect_only: Regex,
     post_indirect_only: Regex,
     pre_indirect_only: Regex,
+    absolute_with_size_only: Regex,
     label_line: Regex,
     directive: Regex,
     operand_arg: Regex,
@@ -282,6 +407,11 @@ impl AsmRegex {
                 &Grammar::Register.get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
             )
             .unwrap(),
+            float_register_only: Regex::new(
+                &Grammar::FloatRegister
+                    .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
             register_list_only: Regex::new(
                 &Grammar::RegisterRange
                     .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
@@ -292,6 +422,18 @@ impl AsmRegex {
                     .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
             )
             .unwrap(),
+            register_pair_only: Regex::new(
+                &Grammar::RegisterPair
+                    .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
+            bit_field_only: Regex::new(&Grammar::BitField.get_opt(GrammarOptions::IS_LINE))
+                .unwrap(),
+            special_register_only: Regex::new(
+                &Grammar::SpecialRegister
+                    .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
             immediate_only: Regex::new(&Grammar::Immediate.get_opt(GrammarOptions::IS_LINE))
                 .unwrap(),
             indirect_only: Regex::new(
@@ -306,10 +448,33 @@ impl AsmRegex {
                 &Grammar::IndirectIndex.get_opt(GrammarOptions::IS_LINE),
             )
             .unwrap(),
+            pc_displacement_only: Regex::new(
+                &Grammar::PCDisplacement
+                    .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
+            pc_index_only: Regex::new(
+                &Grammar::PCIndex.get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
+            scaled_index_only: Regex::new(
+                &Grammar::ScaledIndex
+                    .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
+            memory_indirect_only: Regex::new(
+                &Grammar::MemoryIndirect.get_opt(GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
             post_indirect_only: Regex::new(&Grammar::PostIndirect.get_opt(GrammarOptions::IS_LINE))
                 .unwrap(),
             pre_indirect_only: Regex::new(&Grammar::PreIndirect.get_opt(GrammarOptions::IS_LINE))
                 .unwrap(),
+            absolute_with_size_only: Regex::new(
+                &Grammar::AbsoluteWithSize
+                    .get_opt(GrammarOptions::IGNORE_CASE | GrammarOptions::IS_LINE),
+            )
+            .unwrap(),
             label_line: Regex::new(r"^\S+:.*").unwrap(),
             directive: Regex::new(&format!(
                 r"^\s*({})",
@@ -324,17 +489,27 @@ impl AsmRegex {
     pub fn get_operand_kind(&self, operand: &String) -> OperandKind {
         match operand {
             //TODO order is important
+            _ if self.bit_field_only.is_match(operand) => OperandKind::BitField,
             _ if self.post_indirect_only.is_match(operand) => OperandKind::PostIndirect,
             _ if self.pre_indirect_only.is_match(operand) => OperandKind::PreIndirect,
             _ if self.indirect_only.is_match(operand) => OperandKind::Indirect,
+            //the pc relative and memory indirect modes need to be matched before the generic indirect ones
+            _ if self.memory_indirect_only.is_match(operand) => OperandKind::MemoryIndirect,
+            _ if self.pc_displacement_only.is_match(operand) => OperandKind::PCDisplacement,
+            _ if self.pc_index_only.is_match(operand) => OperandKind::PCIndex,
             _ if self.indirect_index_only.is_match(operand) => OperandKind::IndirectIndex,
             _ if self.indirect_displacement_only.is_match(operand) => {
                 OperandKind::IndirectDisplacement
             }
+            _ if self.scaled_index_only.is_match(operand) => OperandKind::ScaledIndex,
             _ if self.register_with_size_only.is_match(operand) => OperandKind::RegisterWithSize,
             _ if self.register_only.is_match(operand) => OperandKind::Register,
+            _ if self.float_register_only.is_match(operand) => OperandKind::FloatRegister,
+            _ if self.register_pair_only.is_match(operand) => OperandKind::RegisterPair,
             _ if self.register_list_only.is_match(operand) => OperandKind::RegisterList,
+            _ if self.special_register_only.is_match(operand) => OperandKind::SpecialRegister,
             _ if self.immediate_only.is_match(operand) => OperandKind::Immediate,
+            _ if self.absolute_with_size_only.is_match(operand) => OperandKind::AbsoluteWithSize,
             //_ if self.absolute.is_match(operand) => OperandKind::Absolute,
             _ => OperandKind::Absolute,
         }
@@ -349,6 +524,9 @@ impl AsmRegex {
                     "b" | "B" => LexedSize::Byte,
                     "w" | "W" => LexedSize::Word,
                     "l" | "L" => LexedSize::Long,
+                    "s" | "S" => LexedSize::Short,
+                    "d" | "D" => LexedSize::Double,
+                    "x" | "X" => LexedSize::Extended,
                     _ => LexedSize::Unknown,
                 };
                 (first.to_string(), size)
@@ -549,6 +727,32 @@ impl Lexer {
                 };
                 LexedOperand::Register(register_type, operand)
             }
+            OperandKind::FloatRegister => LexedOperand::FloatRegister(operand.to_lowercase()),
+            OperandKind::RegisterPair => match operand.split_once(':') {
+                Some((high, low)) => LexedOperand::RegisterPair(
+                    Box::new(self.parse_operand(&high.to_string())),
+                    Box::new(self.parse_operand(&low.to_string())),
+                ),
+                None => LexedOperand::Other(operand),
+            },
+            OperandKind::BitField => {
+                let field = operand
+                    .strip_suffix('}')
+                    .and_then(|field| field.rsplit_once('{'))
+                    .and_then(|(ea, field)| field.split_once(':').map(|(o, w)| (ea, o, w)));
+                match field {
+                    Some((ea, offset, width)) => LexedOperand::BitField {
+                        operand: Box::new(self.parse_operand(&ea.trim().to_string())),
+                        offset: Box::new(self.parse_operand(&offset.trim().to_string())),
+                        width: Box::new(self.parse_operand(&width.trim().to_string())),
+                    },
+                    None => LexedOperand::Other(operand),
+                }
+            }
+            OperandKind::SpecialRegister => match LexedSpecialRegister::from_string(&operand) {
+                Ok(register) => LexedOperand::SpecialRegister(register),
+                Err(_) => LexedOperand::Other(operand),
+            },
             OperandKind::RegisterList => {
                 let groups = operand.split('/').collect::<Vec<&str>>();
                 let mut mask = 0u16;
@@ -626,7 +830,53 @@ impl Lexer {
                     operand: Box::new(operands[0].to_owned()),
                 }
             }
+            OperandKind::PCDisplacement => {
+                let split = operand.split('(').collect::<Vec<&str>>();
+                if split.len() != 2 {
+                    return LexedOperand::Other(operand);
+                }
+                LexedOperand::PCDisplacement {
+                    offset: split[0].trim().to_string(),
+                }
+            }
+            OperandKind::PCIndex => {
+                let split = operand.split('(').collect::<Vec<&str>>();
+                if split.len() != 2 {
+                    return LexedOperand::Other(operand);
+                }
+                let offset = split[0].trim().to_string();
+                let args = split[1].replace(')', "");
+                let args = self.regex.split_into_separated_args(args.trim(), true);
+                let operands = self.parse_operands(args);
+                match &operands[..] {
+                    [_, index] => LexedOperand::PCIndex {
+                        offset,
+                        index: Box::new(index.to_owned()),
+                    },
+                    _ => LexedOperand::Other(operand),
+                }
+            }
+            OperandKind::ScaledIndex => match operand.split_once('*') {
+                Some((register, scale)) => LexedOperand::ScaledIndex {
+                    register: Box::new(self.parse_operand(&register.to_string())),
+                    scale: scale.to_string(),
+                },
+                None => LexedOperand::Other(operand),
+            },
+            OperandKind::MemoryIndirect => match self.parse_memory_indirect(&operand) {
+                Some(parsed) => parsed,
+                None => LexedOperand::Other(operand),
+            },
             OperandKind::Absolute => LexedOperand::Absolute(operand),
+            OperandKind::AbsoluteWithSize => match operand.rsplit_once('.') {
+                Some((address, "w" | "W")) => {
+                    LexedOperand::AbsoluteWithSize(address.to_string(), LexedSize::Word)
+                }
+                Some((address, "l" | "L")) => {
+                    LexedOperand::AbsoluteWithSize(address.to_string(), LexedSize::Long)
+                }
+                _ => LexedOperand::Other(operand),
+            },
             OperandKind::PostIndirect => {
                 let parsed_operand = operand.replace('(', "").replace(")+", "");
                 let arg = self.parse_operand(&parsed_operand);
@@ -640,6 +890,72 @@ impl Lexer {
         }
     }
 
+    /**
+    Parses "([bd, An, Xn], od)" as pre indexed and "([bd, An], Xn, od)" as post indexed, every
+    part is optional and the base can also be the pc, returns None if the parts are not valid
+     */
+    fn parse_memory_indirect(&self, operand: &str) -> Option<LexedOperand> {
+        let (start, end) = (operand.find('[')?, operand.rfind(']')?);
+        let inner = self
+            .regex
+            .split_into_separated_args(operand[start + 1..end].trim(), true);
+        let outer = operand[end + 1..operand.len() - 1]
+            .trim()
+            .trim_start_matches(',');
+        let outer = self.regex.split_into_separated_args(outer.trim(), true);
+        let mut base_displacement = String::new();
+        let mut outer_displacement = String::new();
+        let mut base = None;
+        let mut index = None;
+        let mut pre_indexed = false;
+        for (i, arg) in inner.iter().enumerate() {
+            if arg.eq_ignore_ascii_case("pc") && base.is_none() && index.is_none() {
+                base = Some(Box::new(LexedOperand::SpecialRegister(
+                    LexedSpecialRegister::PC,
+                )));
+                continue;
+            }
+            match self.parse_operand(arg) {
+                op @ LexedOperand::Register(
+                    LexedRegisterType::Address | LexedRegisterType::SP,
+                    _,
+                ) if base.is_none() && index.is_none() => base = Some(Box::new(op)),
+                op @ (LexedOperand::Register(_, _)
+                | LexedOperand::RegisterWithSize(_, _, _)
+                | LexedOperand::ScaledIndex { .. })
+                    if index.is_none() =>
+                {
+                    index = Some(Box::new(op));
+                    pre_indexed = true;
+                }
+                LexedOperand::Absolute(_) if i == 0 => base_displacement = arg.to_string(),
+                _ => return None,
+            }
+        }
+        for arg in outer.iter() {
+            match self.parse_operand(arg) {
+                op @ (LexedOperand::Register(_, _)
+                | LexedOperand::RegisterWithSize(_, _, _)
+                | LexedOperand::ScaledIndex { .. })
+                    if index.is_none() && outer_displacement.is_empty() =>
+                {
+                    index = Some(Box::new(op))
+                }
+                LexedOperand::Absolute(_) if outer_displacement.is_empty() => {
+                    outer_displacement = arg.to_string()
+                }
+                _ => return None,
+            }
+        }
+        Some(LexedOperand::MemoryIndirect {
+            base_displacement,
+            base,
+            index,
+            pre_indexed,
+            outer_displacement,
+        })
+    }
+
     pub fn make_equ_map(&self, lines: &Vec<String>) -> Vec<(String, String)> {
         let mut equs: Vec<(String, String)> = vec![];
         lines
@@ -697,14 +1013,25 @@ impl Lexer {
                     .collect(),
                 size,
             },
-            LexedLine::Directive { name, args, size } => LexedLine::Directive {
-                name,
-                args: args
-                    .into_iter()
-                    .map(|arg| self.apply_equ_to_expression_string(arg, equ_map))
-                    .collect(),
-                size,
-            },
+            LexedLine::Directive { name, args, size } => {
+                //the name of the EQU is kept, so that the constant can be found after the lexing
+                let kept = if name == EQU { 2 } else { 0 };
+                LexedLine::Directive {
+                    args: args
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, arg)| {
+                            if i < kept {
+                                arg
+                            } else {
+                                self.apply_equ_to_expression_string(arg, equ_map)
+                            }
+                        })
+                        .collect(),
+                    name,
+                    size,
+                }
+            }
             _ => line,
         }
     }
@@ -726,7 +1053,10 @@ impl Lexer {
     ) -> LexedOperand {
         match op {
             LexedOperand::Register(_, _)
+            | LexedOperand::FloatRegister(_)
+            | LexedOperand::SpecialRegister(_)
             | LexedOperand::RegisterRange { .. }
+            | LexedOperand::RegisterPair(_, _)
             | LexedOperand::Other(_)
             | LexedOperand::PostIndirect(_)
             | LexedOperand::PreIndirect(_) => op,
@@ -738,6 +1068,10 @@ impl Lexer {
                 //TODO this is a bit of a hack, after applying the equ, it could change the operand type
                 self.parse_operand(&string)
             }
+            LexedOperand::AbsoluteWithSize(abs, size) => LexedOperand::AbsoluteWithSize(
+                self.apply_equ_to_expression_string(abs, equ_map),
+                size,
+            ),
             LexedOperand::Label(label) => {
                 let string = self.apply_equ_to_expression_string(label, equ_map);
                 self.parse_operand(&string)
@@ -763,9 +1097,47 @@ impl Lexer {
                 let offset = self.apply_equ_to_expression_string(offset, equ_map);
                 LexedOperand::IndirectIndex { offset, operands }
             }
+            LexedOperand::PCDisplacement { offset } => LexedOperand::PCDisplacement {
+                offset: self.apply_equ_to_expression_string(offset, equ_map),
+            },
+            LexedOperand::PCIndex { offset, index } => {
+                let index = self.apply_equ_to_operand(*index, equ_map);
+                let offset = self.apply_equ_to_expression_string(offset, equ_map);
+                LexedOperand::PCIndex {
+                    offset,
+                    index: Box::new(index),
+                }
+            }
             LexedOperand::RegisterWithSize(reg, name, size) => {
                 LexedOperand::RegisterWithSize(reg, name, size)
             }
+            LexedOperand::ScaledIndex { register, scale } => LexedOperand::ScaledIndex {
+                register,
+                scale: self.apply_equ_to_expression_string(scale, equ_map),
+            },
+            LexedOperand::MemoryIndirect {
+                base_displacement,
+                base,
+                index,
+                pre_indexed,
+                outer_displacement,
+            } => LexedOperand::MemoryIndirect {
+                base_displacement: self.apply_equ_to_expression_string(base_displacement, equ_map),
+                base,
+                index,
+                pre_indexed,
+                outer_displacement: self
+                    .apply_equ_to_expression_string(outer_displacement, equ_map),
+            },
+            LexedOperand::BitField {
+                operand,
+                offset,
+                width,
+            } => LexedOperand::BitField {
+                operand: Box::new(self.apply_equ_to_operand(*operand, equ_map)),
+                offset: Box::new(self.apply_equ_to_operand(*offset, equ_map)),
+                width: Box::new(self.apply_equ_to_operand(*width, equ_map)),
+            },
         }
     }
 
@@ -811,12 +1183,15 @@ impl Lexer {
                 let mut parsed_args: Vec<String> = self
                     .regex
                     .split_into_separated_args(&code.replace('\t', " "), false);
-                //lowercase the first arg
-                let first = parsed_args
-                    .first()
-                    .expect("Missing first argument")
-                    .to_lowercase();
-                parsed_args[0] = first;
+                //lowercase the first arg, unless it is the name of an EQU, those are case sensitive
+                let is_equ = matches!(&parsed_args[..], [_, equ, ..] if equ.to_lowercase() == EQU);
+                if !is_equ {
+                    let first = parsed_args
+                        .first()
+                        .expect("Missing first argument")
+                        .to_lowercase();
+                    parsed_args[0] = first;
+                }
                 let line = match &parsed_args[..] {
                     [_, equ, ..] if equ.to_lowercase() == "equ" => LexedLine::Directive {
                         name: equ.to_lowercase(),
@@ -846,6 +1221,16 @@ impl Lexer {
     pub fn get_lines(&self) -> &Vec<ParsedLine> {
         &self.lines
     }
+    /**
+    Lexes a single line as it was written, without replacing the EQU constants,
+    the label and the instruction of the same line are returned separately
+     */
+    pub fn lex_source_line(&mut self, line: &String) -> Vec<LexedLine> {
+        match self.lex_line(line) {
+            LexLineResult::Line(line) => vec![line],
+            LexLineResult::Multiple(lines) => lines,
+        }
+    }
 }
 
 fn parse_register_range(range: &str) -> Result<(LexedRegisterType, u32), String> {
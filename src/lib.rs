@@ -3,10 +3,17 @@ use interpreter::{Interpreter, InterpreterOptions};
 use wasm_bindgen::prelude::*;
 pub mod compiler;
 mod constants;
+pub mod disassembler;
+pub mod encoder;
 pub mod instructions;
+pub mod intel_hex;
 pub mod interpreter;
 pub mod lexer;
+pub mod listing;
+pub mod record_file;
 mod semantic_checker;
+pub mod srecord;
+pub mod symbols;
 mod utils;
 
 mod debugger;
@@ -14,7 +21,9 @@ mod math;
 mod test;
 mod ts_types;
 use crate::{
+    instructions::{CpuModel, FpuModel},
     lexer::{Lexer, ParsedLine},
+    listing::Listing,
     semantic_checker::{SemanticChecker, SemanticError},
 };
 
@@ -22,6 +31,8 @@ use crate::{
 pub struct S68k {
     code: String,
     lines: Vec<ParsedLine>,
+    cpu_model: CpuModel,
+    fpu_model: FpuModel,
 }
 impl S68k {
     pub fn new(code: String) -> S68k {
@@ -30,18 +41,39 @@ impl S68k {
         S68k {
             code,
             lines: lexer.get_lines().clone(),
+            cpu_model: CpuModel::default(),
+            fpu_model: FpuModel::default(),
         }
     }
     pub fn semantic_check(&self) -> Vec<SemanticError> {
-        let semantic_checker = SemanticChecker::new(&self.lines);
+        let semantic_checker = SemanticChecker::new(&self.lines, self.cpu_model, self.fpu_model);
         semantic_checker.get_errors()
     }
+    pub fn semantic_warnings(&self) -> Vec<SemanticError> {
+        let semantic_checker = SemanticChecker::new(&self.lines, self.cpu_model, self.fpu_model);
+        semantic_checker.get_warnings()
+    }
+    pub fn set_cpu_model(&mut self, cpu_model: CpuModel) {
+        self.cpu_model = cpu_model;
+    }
+    pub fn get_cpu_model(&self) -> CpuModel {
+        self.cpu_model
+    }
+    pub fn set_fpu_model(&mut self, fpu_model: FpuModel) {
+        self.fpu_model = fpu_model;
+    }
+    pub fn get_fpu_model(&self) -> FpuModel {
+        self.fpu_model
+    }
     pub fn compile(&self) -> Result<Compiler, String> {
         Compiler::new(&self.lines)
     }
     pub fn get_lexed_lines(&self) -> &Vec<ParsedLine> {
         &self.lines
     }
+    pub fn create_listing(&self, compiled_program: &Compiler) -> Listing {
+        Listing::new(compiled_program, &self.lines)
+    }
     pub fn get_code(&self) -> &String {
         &self.code
     }
@@ -50,7 +82,13 @@ impl S68k {
         pre_processed_program: Compiler,
         options: Option<InterpreterOptions>,
     ) -> Interpreter {
-        Interpreter::new(pre_processed_program, options)
+        //without options the interpreter runs the same model the code was checked against
+        let options = options.unwrap_or(InterpreterOptions {
+            cpu_model: self.cpu_model,
+            fpu_model: self.fpu_model,
+            ..Default::default()
+        });
+        Interpreter::new(pre_processed_program, Some(options))
     }
 }
 
@@ -64,6 +102,8 @@ impl S68k {
         S68k {
             code,
             lines: lexer.get_lines().clone(),
+            cpu_model: CpuModel::default(),
+            fpu_model: FpuModel::default(),
         }
     }
     pub fn wasm_get_lexed_lines(&self) -> Result<JsValue, JsValue> {
@@ -77,14 +117,42 @@ impl S68k {
         console_error_panic_hook::set_once();
         self.compile()
     }
+    pub fn wasm_create_listing(&self, compiled_program: &Compiler) -> JsValue {
+        console_error_panic_hook::set_once();
+        serde_wasm_bindgen::to_value(&self.create_listing(compiled_program)).unwrap()
+    }
+    pub fn wasm_create_listing_text(&self, compiled_program: &Compiler) -> String {
+        console_error_panic_hook::set_once();
+        self.create_listing(compiled_program).to_text()
+    }
     pub fn wasm_get_code(&self) -> String {
         console_error_panic_hook::set_once();
         self.get_code().clone()
     }
+    pub fn wasm_set_cpu_model(&mut self, cpu_model: CpuModel) {
+        console_error_panic_hook::set_once();
+        self.set_cpu_model(cpu_model)
+    }
+    pub fn wasm_get_cpu_model(&self) -> CpuModel {
+        console_error_panic_hook::set_once();
+        self.get_cpu_model()
+    }
+    pub fn wasm_set_fpu_model(&mut self, fpu_model: FpuModel) {
+        console_error_panic_hook::set_once();
+        self.set_fpu_model(fpu_model)
+    }
+    pub fn wasm_get_fpu_model(&self) -> FpuModel {
+        console_error_panic_hook::set_once();
+        self.get_fpu_model()
+    }
     pub fn wasm_semantic_check(&self) -> WasmSemanticErrors {
         console_error_panic_hook::set_once();
         WasmSemanticErrors::new(self.semantic_check())
     }
+    pub fn wasm_semantic_warnings(&self) -> WasmSemanticErrors {
+        console_error_panic_hook::set_once();
+        WasmSemanticErrors::new(self.semantic_warnings())
+    }
     pub fn wasm_create_interpreter(
         &self,
         pre_processed_program: Compiler,
@@ -0,0 +1,284 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    compiler::{Compiler, Directive},
+    lexer::{LexedLine, LexedOperand, Lexer, ParsedLine},
+    symbols::SymbolKind,
+};
+
+//the data bytes shown in each row, the longer data continues in the next rows
+const BYTES_PER_ROW: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListingLine {
+    pub line_index: usize,
+    //None for the lines that don't take space in memory, like the comments or EQU
+    pub address: Option<usize>,
+    pub bytes: Vec<u8>,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListingSymbol {
+    pub name: String,
+    //the address of the label or the value of the EQU, None when the EQU is not a number
+    pub value: Option<usize>,
+    pub expression: Option<String>,
+    pub kind: SymbolKind,
+    pub line_index: usize,
+    //the lines where the symbol is used, sorted and without duplicates
+    pub references: Vec<usize>,
+}
+
+/**
+The assembled program next to its source, with the address and the bytes emitted by each line,
+followed by the labels and EQU constants and the lines that reference them
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct Listing {
+    pub lines: Vec<ListingLine>,
+    pub symbols: Vec<ListingSymbol>,
+}
+
+impl Listing {
+    pub fn new(compiler: &Compiler, lines: &[ParsedLine]) -> Listing {
+        let mut instructions = compiler.get_instructions().iter();
+        let mut directives = compiler.get_directives().iter();
+        let symbols = compiler.get_symbols();
+        let names: HashSet<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        let mut listing_lines: Vec<ListingLine> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            //a label followed by an instruction is lexed as two lines of the same source line
+            if listing_lines.last().map(|l| l.line_index) != Some(line.line_index) {
+                listing_lines.push(ListingLine {
+                    line_index: line.line_index,
+                    address: None,
+                    bytes: Vec::new(),
+                    source: line.line.clone(),
+                });
+            }
+            let listing_line = listing_lines.last_mut().unwrap();
+            let (has_address, bytes) = match &line.parsed {
+                LexedLine::Label { .. } => (true, Vec::new()),
+                LexedLine::Instruction { .. } => match instructions.next() {
+                    Some(instruction) => (true, instruction.get_bytes()),
+                    None => (true, Vec::new()),
+                },
+                LexedLine::Directive { .. } => match directives.next() {
+                    Some(
+                        Directive::DC { data, .. }
+                        | Directive::DS { data, .. }
+                        | Directive::DCB { data, .. },
+                    ) => (true, data.clone()),
+                    _ => (false, Vec::new()),
+                },
+                _ => (false, Vec::new()),
+            };
+            if has_address && listing_line.address.is_none() {
+                listing_line.address = Some(compiler.get_line_address(i));
+            }
+            listing_line.bytes.extend(bytes);
+        }
+        //the references are found in the source as written, the lexed lines have the EQU already replaced
+        let mut lexer = Lexer::new();
+        let mut references: HashMap<&str, Vec<usize>> = HashMap::new();
+        for line in &listing_lines {
+            for parsed in lexer.lex_source_line(&line.source) {
+                let mut expressions = Vec::new();
+                match &parsed {
+                    LexedLine::Instruction { operands, .. } => {
+                        for operand in operands {
+                            operand_expressions(operand, &mut expressions);
+                        }
+                    }
+                    //the first argument is the directive itself, the EQU also has its name before it
+                    LexedLine::Directive { name, args, .. } => {
+                        let skipped = if name == "equ" { 2 } else { 1 };
+                        expressions.extend(args.iter().skip(skipped));
+                    }
+                    _ => {}
+                }
+                for expression in expressions {
+                    for name in expression_identifiers(expression) {
+                        if let Some(name) = names.get(name) {
+                            references.entry(name).or_default().push(line.line_index);
+                        }
+                    }
+                }
+            }
+        }
+        //the symbols are already sorted by name
+        let symbols = symbols
+            .iter()
+            .map(|symbol| {
+                let mut lines = references.remove(symbol.name.as_str()).unwrap_or_default();
+                lines.dedup();
+                ListingSymbol {
+                    name: symbol.name.clone(),
+                    value: symbol.value,
+                    expression: symbol.expression.clone(),
+                    kind: symbol.kind,
+                    line_index: symbol.line,
+                    references: lines,
+                }
+            })
+            .collect();
+        Listing {
+            lines: listing_lines,
+            symbols,
+        }
+    }
+
+    /**
+    The listing as text, the line numbers start from 1 like in the editor
+     */
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        text.push_str(" line  address   data              source\n\n");
+        for line in &self.lines {
+            let mut rows = line.bytes.chunks(BYTES_PER_ROW);
+            let address = match line.address {
+                Some(address) => format!("{:08X}", address),
+                None => String::new(),
+            };
+            text.push_str(&format!(
+                "{:>5}  {:<8}  {:<16}  {}\n",
+                line.line_index + 1,
+                address,
+                format_bytes(rows.next().unwrap_or(&[])),
+                line.source
+            ));
+            //the continuation rows only have the address and the data
+            for (i, row) in rows.enumerate() {
+                let address = line.address.unwrap_or(0) + (i + 1) * BYTES_PER_ROW;
+                text.push_str(&format!(
+                    "{:>5}  {:08X}  {}\n",
+                    "",
+                    address,
+                    format_bytes(row)
+                ));
+            }
+        }
+        text.push_str("\nSymbols\n\n");
+        text.push_str(" name                  value      line\n");
+        for symbol in &self.symbols {
+            //the EQU that are not a number show their expression instead
+            let value = match (symbol.value, &symbol.expression) {
+                (Some(value), _) => format!("{:08X}", value),
+                (None, Some(expression)) => expression.clone(),
+                (None, None) => String::new(),
+            };
+            text.push_str(&format!(
+                " {:<20}  {:<8}  {:>5}\n",
+                symbol.name,
+                value,
+                symbol.line_index + 1
+            ));
+        }
+        text.push_str("\nCross reference\n\n");
+        text.push_str(" name                  defined  used at\n");
+        for symbol in &self.symbols {
+            let lines: Vec<String> = symbol
+                .references
+                .iter()
+                .map(|line| (line + 1).to_string())
+                .collect();
+            text.push_str(&format!(
+                " {:<20}  {:>7}  {}\n",
+                symbol.name,
+                symbol.line_index + 1,
+                lines.join(", ")
+            ));
+        }
+        text.lines()
+            .map(|row| format!("{}\n", row.trim_end()))
+            .collect()
+    }
+}
+
+//the bytes in groups of words, like the machine code
+fn format_bytes(bytes: &[u8]) -> String {
+    bytes
+        .chunks(2)
+        .map(|word| {
+            word.iter()
+                .map(|b| format!("{:02X}", b))
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+//the expressions of the operand where a label can be used
+fn operand_expressions<'a>(operand: &'a LexedOperand, expressions: &mut Vec<&'a String>) {
+    match operand {
+        LexedOperand::Immediate(expression)
+        | LexedOperand::Absolute(expression)
+        | LexedOperand::AbsoluteWithSize(expression, _)
+        | LexedOperand::Label(expression)
+        | LexedOperand::Other(expression)
+        | LexedOperand::PCDisplacement { offset: expression } => expressions.push(expression),
+        LexedOperand::IndirectDisplacement { offset, operand } => {
+            expressions.push(offset);
+            operand_expressions(operand, expressions);
+        }
+        LexedOperand::IndirectIndex { offset, operands } => {
+            expressions.push(offset);
+            for operand in operands {
+                operand_expressions(operand, expressions);
+            }
+        }
+        LexedOperand::PCIndex { offset, index } => {
+            expressions.push(offset);
+            operand_expressions(index, expressions);
+        }
+        LexedOperand::MemoryIndirect {
+            base_displacement,
+            outer_displacement,
+            ..
+        } => {
+            expressions.push(base_displacement);
+            expressions.push(outer_displacement);
+        }
+        LexedOperand::BitField {
+            operand,
+            offset,
+            width,
+        } => {
+            operand_expressions(operand, expressions);
+            operand_expressions(offset, expressions);
+            operand_expressions(width, expressions);
+        }
+        LexedOperand::Indirect(operand)
+        | LexedOperand::PostIndirect(operand)
+        | LexedOperand::PreIndirect(operand) => operand_expressions(operand, expressions),
+        _ => {}
+    }
+}
+
+//the words of the expression that can be a label, the numbers and the strings are skipped
+fn expression_identifiers(expression: &str) -> Vec<&str> {
+    let mut identifiers = Vec::new();
+    let mut in_string = false;
+    let mut start = None;
+    for (i, c) in expression.char_indices().chain([(expression.len(), ' ')]) {
+        if c == '\'' {
+            in_string = !in_string;
+        }
+        let is_word = !in_string && (c.is_alphanumeric() || matches!(c, '_' | '.' | '$' | '%'));
+        match (start, is_word) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let word = &expression[s..i];
+                if word.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '.') {
+                    identifiers.push(word);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    identifiers
+}
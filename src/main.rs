@@ -1,8 +1,13 @@
 use console::Term;
 use core::panic;
 use s68k::{
+    compiler::Compiler,
     instructions::{Interrupt, InterruptResult},
-    interpreter::{Interpreter, InterpreterOptions, InterpreterStatus},
+    interpreter::{
+        ExecutionMode, Interpreter, InterpreterOptions, InterpreterStatus, MemoryRegion,
+    },
+    srecord::SRecordFormat,
+    symbols::write_symbol_map,
     S68k,
 };
 use std::env;
@@ -16,34 +21,13 @@ enum StepKind {
 }
 
 fn main() {
-    let example_code = fs::read_to_string("code-to-run.asm").expect("Unable to read file");
-    let s68k = S68k::new(example_code);
     let args = env::args().collect::<Vec<String>>();
-    if args.contains(&"--lex".to_string()) {
-        println!("\n---------LEXED---------\n");
-        for line in s68k.get_lexed_lines() {
-            println!("{:#?}", line);
-        }
-    }
-    let errors = s68k.semantic_check();
-    if !args.contains(&"--no-errors".to_string()) && !errors.is_empty() {
-        println!("\n---------ERRORS--------\n");
-        for error in errors.iter() {
-            println!("{}", error.get_message());
-        }
-    }
-    if !errors.is_empty() {
-        println!("\n");
-        panic!("Errors found, aborting");
-    }
-    println!("\n----COMPILED-PROGRAM----\n");
-    let compiled_program = s68k.compile().unwrap();
-    //pre_interpreter.debug_print();
-    if args.contains(&"--show-compiled".to_string()) {
-        let mut instructions = compiled_program.get_instructions().clone();
-        instructions.sort_by_key(|i| i.address);
-        println!("{:#?}", instructions);
-    }
+    //a program assembled elsewhere is run from its S-record file, without the source
+    let srecord_file = get_arg_value(&args, "--load-srec");
+    let compiled_program = match srecord_file {
+        Some(_) => None,
+        None => Some(compile_code(&args)),
+    };
 
     //ask user if it wants to run the code (0) or allow to step through it (1)
     let execution_mode = if args.contains(&"--step".to_string()) {
@@ -59,13 +43,20 @@ fn main() {
         }
         value
     };
+    //run the instructions decoded from memory instead of the compiled ones
+    let cpu_execution_mode = if args.contains(&"--fetch-decode".to_string()) {
+        ExecutionMode::FetchDecode
+    } else {
+        ExecutionMode::Precompiled
+    };
     let start = Instant::now();
     let options = InterpreterOptions {
         keep_history: true,
+        execution_mode: cpu_execution_mode,
         ..Default::default()
     };
     if execution_mode == "0" {
-        let mut interpreter = s68k.create_interpreter(compiled_program, Some(options));
+        let mut interpreter = create_interpreter(compiled_program, &srecord_file, options, &args);
         //16 mb of memory
         while !interpreter.has_terminated() {
             let status = interpreter.run().unwrap();
@@ -77,17 +68,22 @@ fn main() {
                 InterpreterStatus::TerminatedWithException => {
                     println!("Program Terminated with exception");
                 }
+                InterpreterStatus::Stopped => {
+                    println!("Program stopped, there is no interrupt source to resume it");
+                    break;
+                }
                 _ => {
                     println!("paused")
                 }
             }
         }
+        dump_memory(&interpreter, &args);
         if !args.contains(&"--no-debug".to_string()) {
             interpreter.debug_status();
             println!("\nExecution took: {:?}", start.elapsed());
         }
     } else if execution_mode == "1" {
-        let mut interpreter = s68k.create_interpreter(compiled_program, Some(options));
+        let mut interpreter = create_interpreter(compiled_program, &srecord_file, options, &args);
         println!("D for step, A for undo, S for print, Q for quit");
         while !interpreter.has_terminated() {
             let step_kind = ask_step_kind();
@@ -116,9 +112,14 @@ fn main() {
                 InterpreterStatus::TerminatedWithException => {
                     println!("Program Terminated with exception");
                 }
+                InterpreterStatus::Stopped => {
+                    println!("Program stopped, there is no interrupt source to resume it");
+                    break;
+                }
                 _ => {}
             }
         }
+        dump_memory(&interpreter, &args);
         if !args.contains(&"--no-debug".to_string()) {
             interpreter.debug_status();
             println!("\nExecution took: {:?}", start.elapsed());
@@ -127,8 +128,10 @@ fn main() {
         let options = InterpreterOptions {
             keep_history: false,
             history_size: 0,
+            execution_mode: cpu_execution_mode,
+            ..Default::default()
         };
-        let mut interpreter = s68k.create_interpreter(compiled_program, Some(options));
+        let mut interpreter = create_interpreter(compiled_program, &srecord_file, options, &args);
         while !interpreter.has_terminated() {
             let status = interpreter.run().unwrap();
             match status {
@@ -139,9 +142,14 @@ fn main() {
                 InterpreterStatus::TerminatedWithException => {
                     println!("Program Terminated with exception");
                 }
+                InterpreterStatus::Stopped => {
+                    println!("Program stopped, there is no interrupt source to resume it");
+                    break;
+                }
                 _ => {}
             }
         }
+        dump_memory(&interpreter, &args);
         if !args.contains(&"--no-debug".to_string()) {
             interpreter.debug_status();
             println!("\nExecution took: {:?}", start.elapsed());
@@ -149,6 +157,151 @@ fn main() {
     }
 }
 
+fn compile_code(args: &[String]) -> Compiler {
+    let example_code = fs::read_to_string("code-to-run.asm").expect("Unable to read file");
+    let s68k = S68k::new(example_code);
+    if args.contains(&"--lex".to_string()) {
+        println!("\n---------LEXED---------\n");
+        for line in s68k.get_lexed_lines() {
+            println!("{:#?}", line);
+        }
+    }
+    let errors = s68k.semantic_check();
+    if !args.contains(&"--no-errors".to_string()) && !errors.is_empty() {
+        println!("\n---------ERRORS--------\n");
+        for error in errors.iter() {
+            println!("{}", error.get_message());
+        }
+    }
+    if !errors.is_empty() {
+        println!("\n");
+        panic!("Errors found, aborting");
+    }
+    println!("\n----COMPILED-PROGRAM----\n");
+    let compiled_program = s68k.compile().unwrap();
+    //pre_interpreter.debug_print();
+    if args.contains(&"--show-compiled".to_string()) {
+        let mut instructions = compiled_program.get_instructions().clone();
+        instructions.sort_by_key(|i| i.address);
+        println!("{:#?}", instructions);
+    }
+    if let Some(path) = get_arg_value(args, "--listing") {
+        let listing = s68k.create_listing(&compiled_program);
+        fs::write(path, listing.to_text()).expect("Unable to write file");
+    }
+    if let Some(path) = get_arg_value(args, "--symbols") {
+        let symbols = compiled_program.get_symbols();
+        fs::write(path, write_symbol_map(&symbols)).expect("Unable to write file");
+    }
+    if let Some(path) = get_arg_value(args, "--write-srec") {
+        //the address width is picked by the extension, S19 by default
+        let format = if path.ends_with(".s37") {
+            SRecordFormat::S37
+        } else if path.ends_with(".s28") {
+            SRecordFormat::S28
+        } else {
+            SRecordFormat::S19
+        };
+        match compiled_program.to_srecords(format) {
+            Ok(text) => fs::write(&path, text).expect("Unable to write file"),
+            Err(e) => panic!("Unable to write the S-records: {}", e.get_message()),
+        }
+    }
+    compiled_program
+}
+
+fn create_interpreter(
+    compiled_program: Option<Compiler>,
+    srecord_file: &Option<String>,
+    options: InterpreterOptions,
+    args: &[String],
+) -> Interpreter {
+    let mut interpreter = match (compiled_program, srecord_file) {
+        (Some(compiled_program), _) => Interpreter::new(compiled_program, Some(options)),
+        (None, Some(path)) => {
+            let text = fs::read_to_string(path).expect("Unable to read file");
+            match Interpreter::from_srecords(&text, Some(options)) {
+                Ok(interpreter) => interpreter,
+                Err(e) => panic!("Unable to load the S-records: {}", e.get_message()),
+            }
+        }
+        (None, None) => panic!("There is no program to run"),
+    };
+    load_memory(&mut interpreter, args);
+    interpreter
+}
+
+//preloads the lookup tables or test inputs before running the program
+fn load_memory(interpreter: &mut Interpreter, args: &[String]) {
+    let region = |length: Option<usize>| {
+        get_arg_value(args, "--load-base").map(|address| MemoryRegion {
+            address: parse_number(&address),
+            length: get_arg_value(args, "--load-length")
+                .map(|length| parse_number(&length))
+                .or(length)
+                .expect("--load-length is required"),
+            fill: get_arg_value(args, "--fill")
+                .map(|fill| parse_number(&fill) as u8)
+                .unwrap_or(0xFF),
+        })
+    };
+    if let Some(path) = get_arg_value(args, "--load-bin") {
+        let bytes = fs::read(path).expect("Unable to read file");
+        let region = region(Some(bytes.len())).expect("--load-base is required");
+        if let Err(e) = interpreter.load_binary(&bytes, &region) {
+            panic!("Unable to load the binary file: {:?}", e);
+        }
+    }
+    if let Some(path) = get_arg_value(args, "--load-hex") {
+        let text = fs::read_to_string(path).expect("Unable to read file");
+        if let Err(e) = interpreter.load_intel_hex(&text, region(None).as_ref()) {
+            panic!("Unable to load the Intel HEX file: {}", e.get_message());
+        }
+    }
+}
+
+//exports the results of the program for comparison
+fn dump_memory(interpreter: &Interpreter, args: &[String]) {
+    let region = || MemoryRegion {
+        address: parse_number(
+            &get_arg_value(args, "--dump-base").expect("--dump-base is required"),
+        ),
+        length: parse_number(
+            &get_arg_value(args, "--dump-length").expect("--dump-length is required"),
+        ),
+        fill: get_arg_value(args, "--fill")
+            .map(|fill| parse_number(&fill) as u8)
+            .unwrap_or(0xFF),
+    };
+    let memory = interpreter.get_memory();
+    if let Some(path) = get_arg_value(args, "--dump-bin") {
+        match memory.dump_binary(&region()) {
+            Ok(bytes) => fs::write(path, bytes).expect("Unable to write file"),
+            Err(e) => panic!("Unable to dump the memory: {:?}", e),
+        }
+    }
+    if let Some(path) = get_arg_value(args, "--dump-hex") {
+        match memory.dump_intel_hex(&region()) {
+            Ok(text) => fs::write(path, text).expect("Unable to write file"),
+            Err(e) => panic!("Unable to dump the memory: {:?}", e),
+        }
+    }
+}
+
+//the numbers can be written in hexadecimal with $ or 0x
+fn parse_number(value: &str) -> usize {
+    let parsed = match value.strip_prefix('$').or_else(|| value.strip_prefix("0x")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse::<usize>(),
+    };
+    parsed.unwrap_or_else(|_| panic!("Invalid number: {}", value))
+}
+
+fn get_arg_value(args: &[String], name: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == name)?;
+    args.get(index + 1).cloned()
+}
+
 fn ask_step_kind() -> StepKind {
     //D for next, A for previous, S for print, Q for quit
     let mut step_kind = Term::stdout()
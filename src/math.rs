@@ -41,6 +41,58 @@ pub fn overflowing_sub_sized(op1: u32, op2: u32, size: Size) -> (u32, bool) {
     }
 }
 
+/**
+Adds op1, op2 and the extend bit, returning the carry out of the most significant bit
+ */
+pub fn overflowing_add_extended_sized(op1: u32, op2: u32, extend: bool, size: Size) -> (u32, bool) {
+    let (result, carry_1) = overflowing_add_sized(op1, op2, size);
+    let (result, carry_2) = overflowing_add_sized(result, extend as u32, size);
+    (result, carry_1 || carry_2)
+}
+
+/**
+Subtracts op2 and the extend bit from op1, returning the borrow out of the most significant bit
+ */
+pub fn overflowing_sub_extended_sized(op1: u32, op2: u32, extend: bool, size: Size) -> (u32, bool) {
+    let (result, borrow_1) = overflowing_sub_sized(op1, op2, size);
+    let (result, borrow_2) = overflowing_sub_sized(result, extend as u32, size);
+    (result, borrow_1 || borrow_2)
+}
+
+/**
+Packed BCD addition of two bytes plus the extend bit, returns the decimal adjusted
+result and the decimal carry
+ */
+pub fn bcd_add(op1: u8, op2: u8, extend: bool) -> (u8, bool) {
+    let mut result = (op1 & 0x0F) as u32 + (op2 & 0x0F) as u32 + extend as u32;
+    if result > 9 {
+        result += 6;
+    }
+    result += (op1 & 0xF0) as u32 + (op2 & 0xF0) as u32;
+    let carry = result > 0x99;
+    if carry {
+        result -= 0xA0;
+    }
+    (result as u8, carry)
+}
+
+/**
+Packed BCD subtraction of op2 and the extend bit from op1, returns the decimal adjusted
+result and the decimal borrow
+ */
+pub fn bcd_sub(op1: u8, op2: u8, extend: bool) -> (u8, bool) {
+    let mut result = (op1 & 0x0F) as i32 - (op2 & 0x0F) as i32 - extend as i32;
+    if result < 0 {
+        result -= 6;
+    }
+    result += (op1 & 0xF0) as i32 - (op2 & 0xF0) as i32;
+    let borrow = result < 0;
+    if borrow {
+        result += 0xA0;
+    }
+    (result as u8, borrow)
+}
+
 pub fn overflowing_sub_signed_sized(op1: u32, op2: u32, size: Size) -> (u32, bool) {
     match size {
         Size::Byte => {
@@ -74,6 +126,137 @@ pub fn get_value_sized(value: u32, size: Size) -> u32 {
     }
 }
 
+/**
+Mask of the lowest width bits, the width goes from 1 to 32
+ */
+pub fn bit_field_mask(width: u32) -> u32 {
+    u32::MAX >> (32 - width)
+}
+
+/**
+Multiplies the value by 2^exp, in steps so that the intermediate powers don't overflow
+ */
+pub fn scale_by_power_of_two(mut value: f64, mut exp: i32) -> f64 {
+    while exp > 1000 {
+        value *= 2f64.powi(1000);
+        exp -= 1000;
+    }
+    while exp < -1000 {
+        value *= 2f64.powi(-1000);
+        exp += 1000;
+    }
+    value * 2f64.powi(exp)
+}
+
+/**
+Unbiased exponent of a finite non zero number, so that the value is mantissa * 2^exponent with 1 <= |mantissa| < 2
+ */
+pub fn get_fp_exponent(value: f64) -> i32 {
+    let exponent = ((value.to_bits() >> 52) & 0x7FF) as i32;
+    if exponent == 0 {
+        //denormals are normalized first
+        return get_fp_exponent(value * 2f64.powi(64)) - 64;
+    }
+    exponent - 1023
+}
+
+/**
+Rounds to an integer with the rounding mode of the FPCR: to nearest, to zero, to minus infinity or to plus infinity
+ */
+pub fn round_fp(value: f64, mode: u32) -> f64 {
+    match mode & 0b11 {
+        0 => value.round_ties_even(),
+        1 => value.trunc(),
+        2 => value.floor(),
+        _ => value.ceil(),
+    }
+}
+
+/**
+Value of the constant rom of the coprocessor at the offset, the powers of ten that don't fit in a double are infinite
+and the offsets without a constant read as 0
+ */
+pub fn get_fp_constant(offset: u8) -> f64 {
+    use std::f64::consts;
+    match offset {
+        0x00 => consts::PI,
+        0x0B => consts::LOG10_2,
+        0x0C => consts::E,
+        0x0D => consts::LOG2_E,
+        0x0E => consts::LOG10_E,
+        0x30 => consts::LN_2,
+        0x31 => consts::LN_10,
+        //10^0, 10^1, 10^2, 10^4, 10^8 ... 10^4096
+        0x32 => 1.0,
+        0x33..=0x3F => 10f64.powi(1 << (offset - 0x33)),
+        _ => 0.0,
+    }
+}
+
+/**
+IEEE remainder of the division, the quotient is rounded to the nearest integer (ties to even)
+ */
+pub fn ieee_remainder(dividend: f64, divisor: f64) -> f64 {
+    let remainder = dividend % divisor;
+    let half = divisor.abs() / 2.0;
+    let odd_quotient = (dividend / divisor).trunc() % 2.0 != 0.0;
+    if remainder.abs() > half || (remainder.abs() == half && odd_quotient) {
+        remainder - divisor.abs().copysign(remainder)
+    } else {
+        remainder
+    }
+}
+
+/**
+Converts a number to the 96 bit extended precision memory format: the sign and 15 bit exponent, 16 unused bits
+and a 64 bit mantissa with an explicit integer bit
+ */
+pub fn f64_to_extended(value: f64) -> [u8; 12] {
+    let bits = value.to_bits();
+    let sign = ((bits >> 63) as u16) << 15;
+    let exponent = ((bits >> 52) & 0x7FF) as i32;
+    let fraction = bits & 0x000F_FFFF_FFFF_FFFF;
+    let (exponent, mantissa) = match exponent {
+        //infinity has an empty fraction while the NaNs keep it
+        0x7FF => (0x7FFF, fraction << 11),
+        0 if fraction == 0 => (0, 0),
+        //the double denormals are normal numbers in extended precision
+        0 => {
+            let shift = fraction.leading_zeros();
+            ((16383 - 1011 - shift as i32) as u16, fraction << shift)
+        }
+        _ => (
+            (exponent - 1023 + 16383) as u16,
+            (1 << 63) | (fraction << 11),
+        ),
+    };
+    let mut bytes = [0u8; 12];
+    bytes[0..2].copy_from_slice(&(sign | exponent).to_be_bytes());
+    bytes[4..12].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+/**
+Converts the 96 bit extended precision memory format to a number, the precision that doesn't fit in a double is lost
+ */
+pub fn extended_to_f64(bytes: &[u8]) -> f64 {
+    let sign_exponent = u16::from_be_bytes([bytes[0], bytes[1]]);
+    let mut mantissa_bytes = [0u8; 8];
+    mantissa_bytes.copy_from_slice(&bytes[4..12]);
+    let mantissa = u64::from_be_bytes(mantissa_bytes);
+    let sign = if sign_exponent & 0x8000 != 0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let exponent = (sign_exponent & 0x7FFF) as i32;
+    match exponent {
+        0x7FFF if mantissa << 1 == 0 => sign * f64::INFINITY,
+        0x7FFF => f64::NAN.copysign(sign),
+        _ => sign * scale_by_power_of_two(mantissa as f64, exponent - 16383 - 63),
+    }
+}
+
 pub fn has_add_overflowed(op1: u32, op2: u32, result: u32, size: Size) -> bool {
     let s1 = get_sign(op1, size);
     let s2 = get_sign(op2, size);
@@ -134,3 +317,30 @@ pub fn rotate(dir: &ShiftDirection, value: u32, size: Size) -> (u32, bool) {
         }
     }
 }
+
+/**
+Rotates by one through the extend bit, the bit shifted out becomes the new extend
+and the old extend is shifted in on the other side
+ */
+pub fn rotate_extend(dir: &ShiftDirection, value: u32, size: Size, extend: bool) -> (u32, bool) {
+    match dir {
+        ShiftDirection::Left => {
+            let bit = get_sign(value, size);
+            let rotate = match size {
+                Size::Byte => ((value as u8) << 1) as u32,
+                Size::Word => ((value as u16) << 1) as u32,
+                Size::Long => value << 1,
+            };
+            (rotate | extend as u32, bit)
+        }
+        ShiftDirection::Right => {
+            let bit = (value & 0x01) != 0;
+            let mask = if extend {
+                get_sign_mask(0xffffffff, size)
+            } else {
+                0x0
+            };
+            ((get_value_sized(value, size) >> 1) | mask, bit)
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Serialize;
+
+//the data bytes written in each data record
+pub const BYTES_PER_RECORD: usize = 16;
+//the memory of the m68k is 16mb, the records cannot place data past it
+pub const ADDRESS_SPACE_SIZE: usize = 0x01000000;
+
+/**
+The errors of the S-record and Intel HEX files, the lines start from 0
+ */
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum RecordError {
+    InvalidRecord {
+        line: usize,
+        message: String,
+    },
+    ChecksumMismatch {
+        line: usize,
+        expected: u8,
+        found: u8,
+    },
+    //the data or the entry point does not fit in the address width of the format, or in the memory
+    AddressOutOfRange {
+        address: usize,
+    },
+}
+
+impl RecordError {
+    pub fn get_message(&self) -> String {
+        match self {
+            RecordError::InvalidRecord { line, message } => {
+                format!("Invalid record at line {}: {}", line + 1, message)
+            }
+            RecordError::ChecksumMismatch {
+                line,
+                expected,
+                found,
+            } => format!(
+                "Checksum mismatch at line {}, expected ${:02X} but found ${:02X}",
+                line + 1,
+                expected,
+                found
+            ),
+            RecordError::AddressOutOfRange { address } => {
+                format!("Address ${:X} is out of range", address)
+            }
+        }
+    }
+}
+
+pub type RecordResult<T> = Result<T, RecordError>;
@@ -7,9 +7,12 @@ use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 use crate::{
-    instructions::Label,
-    lexer::{LexedLine, LexedOperand, LexedRegisterType, LexedSize, ParsedLine},
-    utils::{num_to_signed_base, parse_absolute_expression},
+    compiler::Compiler,
+    instructions::{CpuModel, FpCondition, FpFormat, FpOperation, FpuModel, Label},
+    lexer::{
+        LexedLine, LexedOperand, LexedRegisterType, LexedSize, LexedSpecialRegister, ParsedLine,
+    },
+    utils::{num_to_signed_base, parse_absolute_expression, parse_float_expression},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,11 +20,33 @@ use crate::{
 pub struct SemanticError {
     line: ParsedLine,
     error: String,
+    is_warning: bool,
 }
 
 impl SemanticError {
     pub fn new(line: ParsedLine, error: String) -> Self {
-        Self { line, error }
+        Self {
+            line,
+            error,
+            is_warning: false,
+        }
+    }
+    pub fn warning(line: ParsedLine, error: String) -> Self {
+        Self {
+            line,
+            error,
+            is_warning: true,
+        }
+    }
+    pub fn is_warning(&self) -> bool {
+        self.is_warning
+    }
+    fn get_kind(&self) -> &str {
+        if self.is_warning {
+            "Warning"
+        } else {
+            "Error"
+        }
     }
     pub fn get_line(&self) -> &ParsedLine {
         &self.line
@@ -30,11 +55,17 @@ impl SemanticError {
         self.line.line_index
     }
     pub fn get_message(&self) -> String {
-        format!("Error on line {}: {}", self.line.line_index + 1, self.error)
+        format!(
+            "{} on line {}: {}",
+            self.get_kind(),
+            self.line.line_index + 1,
+            self.error
+        )
     }
     pub fn get_message_with_line(&self) -> String {
         format!(
-            "Error on line {}, \"{}\": {}",
+            "{} on line {}, \"{}\": {}",
+            self.get_kind(),
             self.line.line_index + 1,
             self.line.line,
             self.error
@@ -56,6 +87,9 @@ impl SemanticError {
     pub fn wasm_get_line_index(&self) -> usize {
         self.get_line_index()
     }
+    pub fn wasm_is_warning(&self) -> bool {
+        self.is_warning()
+    }
     pub fn wasm_get_error(&self) -> String {
         self.error.clone()
     }
@@ -74,6 +108,9 @@ bitflags! {
         const INDIRECT_PRE_DECREMENT = 1<<7;
         const ADDRESS = 1<<8;
         const REG_LIST = 1<<9;
+        const PC_DISPLACEMENT = 1<<10;
+        const PC_INDEX = 1<<11;
+        const REG_PAIR = 1<<12;
     }
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -82,7 +119,10 @@ bitflags! {
         const NONE = 0;
         const NO_D_REG = AdrMode::D_REG.bits();
         const NO_A_REG = AdrMode::A_REG.bits();
-        const NO_IMMEDIATE = AdrMode::IMMEDIATE.bits();
+        //the pc relative modes are read only, so they are excluded together with the immediate from the destinations
+        const NO_IMMEDIATE = AdrMode::IMMEDIATE.bits()
+            | AdrMode::PC_DISPLACEMENT.bits()
+            | AdrMode::PC_INDEX.bits();
         const NO_ADDRESS = AdrMode::ADDRESS.bits();
         const NO_INDIRECT = AdrMode::INDIRECT.bits()
             | AdrMode::INDIRECT_DISPLACEMENT.bits()
@@ -94,6 +134,19 @@ bitflags! {
         const ONLY_REG = !(AdrMode::D_REG.bits() | AdrMode::A_REG.bits());
         const ONLY_A_REG = !AdrMode::A_REG.bits();
         const ONLY_D_REG = !AdrMode::D_REG.bits();
+        const ONLY_D_REG_OR_PAIR = !(AdrMode::D_REG.bits() | AdrMode::REG_PAIR.bits());
+        const ONLY_D_REG_OR_CONTROL = !(AdrMode::D_REG.bits()
+            | AdrMode::INDIRECT.bits()
+            | AdrMode::INDIRECT_DISPLACEMENT.bits()
+            | AdrMode::INDIRECT_INDEX.bits()
+            | AdrMode::ADDRESS.bits()
+            | AdrMode::PC_DISPLACEMENT.bits()
+            | AdrMode::PC_INDEX.bits());
+        const ONLY_D_REG_OR_CONTROL_ALTERABLE = !(AdrMode::D_REG.bits()
+            | AdrMode::INDIRECT.bits()
+            | AdrMode::INDIRECT_DISPLACEMENT.bits()
+            | AdrMode::INDIRECT_INDEX.bits()
+            | AdrMode::ADDRESS.bits());
         const ONLY_INDIRECT = !(AdrMode::INDIRECT.bits()
             | AdrMode::INDIRECT_DISPLACEMENT.bits()
             | AdrMode::INDIRECT_INDEX.bits()
@@ -124,8 +177,22 @@ bitflags! {
             | AdrMode::INDIRECT_PRE_DECREMENT.bits()
             | AdrMode::ADDRESS.bits()
         );
+        const ONLY_INDIRECT_OR_ABSOLUTE_OR_PC = !(AdrMode::INDIRECT.bits()
+            | AdrMode::INDIRECT_DISPLACEMENT.bits()
+            | AdrMode::INDIRECT_INDEX.bits()
+            | AdrMode::INDIRECT_POST_INCREMENT.bits()
+            | AdrMode::INDIRECT_PRE_DECREMENT.bits()
+            | AdrMode::ADDRESS.bits()
+            | AdrMode::PC_DISPLACEMENT.bits()
+            | AdrMode::PC_INDEX.bits()
+        );
         const ONLY_REG_LIST = !AdrMode::REG_LIST.bits();
-        const NO_A_REG_OR_IMMEDIATE = AdrMode::A_REG.bits() | AdrMode::IMMEDIATE.bits();
+        const NO_A_REG_OR_IMMEDIATE = AdrMode::A_REG.bits()
+            | AdrMode::IMMEDIATE.bits()
+            | AdrMode::PC_DISPLACEMENT.bits()
+            | AdrMode::PC_INDEX.bits();
+        const ONLY_D_REG_OR_PRE_DECREMENT = !(AdrMode::D_REG.bits() | AdrMode::INDIRECT_PRE_DECREMENT.bits());
+        const ONLY_INDIRECT_OR_DISPLACEMENT = !(AdrMode::INDIRECT.bits() | AdrMode::INDIRECT_DISPLACEMENT.bits());
     }
 }
 //TODO refactor this
@@ -140,6 +207,9 @@ impl AdrMode {
             AdrMode::INDIRECT_INDEX => "(An, Dn)",
             AdrMode::IMMEDIATE => "Im",
             AdrMode::ADDRESS => "Ea/<LABEL>",
+            AdrMode::PC_DISPLACEMENT => "d16(PC)",
+            AdrMode::PC_INDEX => "d8(PC, Xn)",
+            AdrMode::REG_PAIR => "Dh:Dl",
 
             _ => "UNKNOWN",
         }
@@ -159,15 +229,23 @@ impl Rules {
             Rules::ONLY_REG => "Dn/An",
             Rules::ONLY_A_REG => "An",
             Rules::ONLY_D_REG => "Dn",
+            Rules::ONLY_D_REG_OR_PAIR => "Dn/Dh:Dl",
+            Rules::ONLY_D_REG_OR_CONTROL => {
+                "Dn/(An)/d16(An)/(An, Dn)/Ea/<label>/d16(PC)/d8(PC, Xn)"
+            }
+            Rules::ONLY_D_REG_OR_CONTROL_ALTERABLE => "Dn/(An)/d16(An)/(An, Dn)/Ea/<label>",
             Rules::ONLY_INDIRECT => "(An)",
             Rules::ONLY_D_REG_OR_INDIRECT => "Dn/(An)",
             Rules::ONLY_D_REG_OR_INDIRECT_OR_ADDRESS => "Dn/(An)/Ea",
             Rules::ONLY_ADDRESS => "Ea/<label>",
             Rules::ONLY_IMMEDIATE => "Im",
             Rules::ONLY_INDIRECT_OR_ABSOLUTE => "(An)/Ea/<label>",
+            Rules::ONLY_INDIRECT_OR_ABSOLUTE_OR_PC => "(An)/Ea/<label>/d16(PC)/d8(PC, Xn)",
             Rules::ONLY_POST_INCREMENT => "(An)",
             Rules::ONLY_REG_LIST => "<reg list>",
             Rules::NO_A_REG_OR_IMMEDIATE => "Dn/(An)/(An)/Ea/<label>",
+            Rules::ONLY_D_REG_OR_PRE_DECREMENT => "Dn/-(An)",
+            Rules::ONLY_INDIRECT_OR_DISPLACEMENT => "(An)/d16(An)",
             _ => "UNKNOWN",
         }
         .to_string()
@@ -178,6 +256,13 @@ pub enum SizeRules {
     NoSize,
     AnySize,
     OnlyLongOrWord,
+    OnlyByte,
+    OnlyWord,
+    OnlyLong,
+    OnlyShortOrWord,
+    FloatFormat,
+    OnlyExtended,
+    AnySizeOrFloat,
 }
 
 impl SizeRules {
@@ -186,6 +271,13 @@ impl SizeRules {
             SizeRules::NoSize => "",
             SizeRules::AnySize => "b, w, l",
             SizeRules::OnlyLongOrWord => "w, l",
+            SizeRules::OnlyByte => "b",
+            SizeRules::OnlyWord => "w",
+            SizeRules::OnlyLong => "l",
+            SizeRules::OnlyShortOrWord => "s, b, w",
+            SizeRules::FloatFormat => "b, w, l, s, d, x",
+            SizeRules::OnlyExtended => "x",
+            SizeRules::AnySizeOrFloat => "b, w, l, s, d",
         }
         .to_string()
     }
@@ -194,15 +286,26 @@ impl SizeRules {
 pub struct SemanticChecker {
     labels: HashMap<String, Label>,
     errors: Vec<SemanticError>,
+    warnings: Vec<SemanticError>,
     lines: Vec<ParsedLine>,
+    //the real addresses, unlike the placeholders in the labels, None if they could not be computed
+    line_addresses: Option<(Vec<usize>, HashMap<String, Label>)>,
+    current_address: Option<usize>,
+    cpu_model: CpuModel,
+    fpu_model: FpuModel,
 }
 
 impl SemanticChecker {
-    pub fn new(lines: &[ParsedLine]) -> SemanticChecker {
+    pub fn new(lines: &[ParsedLine], cpu_model: CpuModel, fpu_model: FpuModel) -> SemanticChecker {
         let mut syntax_checker = SemanticChecker {
+            cpu_model,
+            fpu_model,
             errors: Vec::new(),
+            warnings: Vec::new(),
             lines: Vec::new(),
             labels: HashMap::new(),
+            line_addresses: None,
+            current_address: None,
         };
         syntax_checker.check(lines);
         syntax_checker
@@ -232,7 +335,12 @@ impl SemanticChecker {
                 _ => {}
             }
         }
-        for line in lines.iter() {
+        self.line_addresses = Compiler::get_line_addresses(lines).ok();
+        for (i, line) in lines.iter().enumerate() {
+            self.current_address = match &self.line_addresses {
+                Some((addresses, _)) => addresses.get(i).copied(),
+                None => None,
+            };
             self.check_one(line);
         }
     }
@@ -256,6 +364,9 @@ impl SemanticChecker {
     pub fn get_errors(&self) -> Vec<SemanticError> {
         self.errors.clone()
     }
+    pub fn get_warnings(&self) -> Vec<SemanticError> {
+        self.warnings.clone()
+    }
 
     fn check_instruction(&mut self, line: &ParsedLine) {
         match &line.parsed {
@@ -290,10 +401,97 @@ impl SemanticChecker {
                         self.verify_size(SizeRules::AnySize, line);
                         self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
                     }
-                    "move" => {
-                        self.verify_two_args(operands, Rules::NONE, Rules::NO_IMMEDIATE, line);
+                    "addx" | "subx" | "abcd" | "sbcd" => {
+                        self.verify_two_args(operands, Rules::ONLY_D_REG_OR_PRE_DECREMENT, Rules::ONLY_D_REG_OR_PRE_DECREMENT, line);
+                        match &operands[..] {
+                            [LexedOperand::Register(_, _), LexedOperand::PreIndirect(_)]
+                            | [LexedOperand::PreIndirect(_), LexedOperand::Register(_, _)] => {
+                                self.errors.push(SemanticError::new(
+                                    line.clone(),
+                                    "Invalid operands addressing mode, both operands must be either \"Dn\" or \"-(An)\"".to_string(),
+                                ));
+                            }
+                            _ => {}
+                        };
+                        match name {
+                            "abcd" | "sbcd" => self.verify_size(SizeRules::OnlyByte, line),
+                            _ => self.verify_size(SizeRules::AnySize, line),
+                        }
+                    }
+                    "nbcd" => {
+                        self.verify_one_arg(operands, Rules::ONLY_D_REG_OR_INDIRECT_OR_ADDRESS, line);
+                        self.verify_size(SizeRules::OnlyByte, line);
+                    }
+                    "move" => match &operands[..] {
+                        [src, LexedOperand::SpecialRegister(LexedSpecialRegister::SR)] => {
+                            self.verify_arg_rule(src, Rules::NO_A_REG, line, 1);
+                            self.verify_size(SizeRules::OnlyWord, line);
+                            self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
+                        }
+                        [LexedOperand::SpecialRegister(LexedSpecialRegister::SR), dest] => {
+                            self.verify_arg_rule(dest, Rules::NO_A_REG_OR_IMMEDIATE, line, 2);
+                            self.verify_size(SizeRules::OnlyWord, line);
+                        }
+                        [src, LexedOperand::SpecialRegister(LexedSpecialRegister::CCR)] => {
+                            self.verify_arg_rule(src, Rules::NO_A_REG, line, 1);
+                            self.verify_size(SizeRules::OnlyWord, line);
+                            self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
+                        }
+                        [LexedOperand::SpecialRegister(LexedSpecialRegister::CCR), dest] => {
+                            self.verify_arg_rule(dest, Rules::NO_A_REG_OR_IMMEDIATE, line, 2);
+                            self.verify_size(SizeRules::OnlyWord, line);
+                        }
+                        [src, LexedOperand::SpecialRegister(LexedSpecialRegister::USP)] => {
+                            self.verify_arg_rule(src, Rules::ONLY_A_REG, line, 1);
+                            self.verify_size(SizeRules::OnlyLong, line);
+                        }
+                        [LexedOperand::SpecialRegister(LexedSpecialRegister::USP), dest] => {
+                            self.verify_arg_rule(dest, Rules::ONLY_A_REG, line, 2);
+                            self.verify_size(SizeRules::OnlyLong, line);
+                        }
+                        _ => {
+                            self.verify_two_args(operands, Rules::NONE, Rules::NO_IMMEDIATE, line);
+                            self.verify_size(SizeRules::AnySize, line);
+                            self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
+                        }
+                    },
+                    "movec" => {
+                        self.verify_cpu_model(CpuModel::M68010, line);
+                        match &operands[..] {
+                            [LexedOperand::SpecialRegister(register), other]
+                            | [other, LexedOperand::SpecialRegister(register)] => {
+                                if !matches!(
+                                    register,
+                                    LexedSpecialRegister::SFC
+                                        | LexedSpecialRegister::DFC
+                                        | LexedSpecialRegister::USP
+                                        | LexedSpecialRegister::VBR
+                                ) {
+                                    self.errors.push(SemanticError::new(
+                                        line.clone(),
+                                        format!("Special register \"{:?}\" is not a control register, expected SFC, DFC, USP or VBR", register),
+                                    ));
+                                }
+                                let position = match operands[0] {
+                                    LexedOperand::SpecialRegister(_) => 2,
+                                    _ => 1,
+                                };
+                                self.verify_arg_rule(other, Rules::ONLY_REG, line, position);
+                            }
+                            _ => self.errors.push(SemanticError::new(
+                                line.clone(),
+                                "Invalid operands for movec, expected a control register and a register".to_string(),
+                            )),
+                        }
+                        self.verify_size(SizeRules::OnlyLong, line);
+                    }
+                    "moves" => {
+                        self.verify_cpu_model(CpuModel::M68010, line);
+                        match &operands[..] {
+                            [LexedOperand::Register(_, _), _] => self.verify_two_args(operands, Rules::ONLY_REG, Rules::ONLY_INDIRECT_OR_ABSOLUTE, line),
+                            _ => self.verify_two_args(operands, Rules::ONLY_INDIRECT_OR_ABSOLUTE, Rules::ONLY_REG, line),
+                        }
                         self.verify_size(SizeRules::AnySize, line);
-                        self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
                     }
                     "adda" | "suba" => {
                         self.verify_two_args(operands, Rules::NONE, Rules::ONLY_A_REG, line);
@@ -301,11 +499,27 @@ impl SemanticChecker {
                         self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
                     }
 
+                    "divs" | "divu" | "muls" | "mulu" if *size == LexedSize::Long => {
+                        self.verify_cpu_model(CpuModel::M68020, line);
+                        self.verify_two_args(operands, Rules::NO_A_REG, Rules::ONLY_D_REG_OR_PAIR, line);
+                        self.verify_size_if_immediate(operands, line, size, LexedSize::Long);
+                    }
+                    "divsl" | "divul" => {
+                        self.verify_cpu_model(CpuModel::M68020, line);
+                        self.verify_two_args(operands, Rules::NO_A_REG, Rules::ONLY_D_REG_OR_PAIR, line);
+                        self.verify_size(SizeRules::OnlyLong, line);
+                        self.verify_size_if_immediate(operands, line, size, LexedSize::Long);
+                    }
                     "divs" | "divu" | "muls" | "mulu" => {
                         self.verify_two_args(operands, Rules::NO_A_REG, Rules::ONLY_D_REG, line);
                         self.verify_size(SizeRules::NoSize, line);
                         self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
                     }
+                    "chk" => {
+                        self.verify_two_args(operands, Rules::NO_A_REG, Rules::ONLY_D_REG, line);
+                        self.verify_size(SizeRules::OnlyWord, line);
+                        self.verify_size_if_immediate(operands, line, size, LexedSize::Word);
+                    }
                     "swap" => {
                         self.verify_one_arg(operands, Rules::ONLY_D_REG, line);
                         self.verify_size(SizeRules::NoSize, line);
@@ -326,10 +540,66 @@ impl SemanticChecker {
                         );
                         self.verify_size(SizeRules::AnySize, line);
                     }
+                    "negx" => {
+                        self.verify_one_arg(
+                            operands,
+                            Rules::ONLY_D_REG_OR_INDIRECT_OR_ADDRESS,
+                            line,
+                        );
+                        self.verify_size(SizeRules::AnySize, line);
+                    }
                     "ext" => {
                         self.verify_one_arg(operands, Rules::ONLY_D_REG, line);
                         self.verify_size(SizeRules::OnlyLongOrWord, line);
                     }
+                    "bftst" | "bfchg" | "bfclr" | "bfset" => {
+                        self.verify_cpu_model(CpuModel::M68020, line);
+                        let rule = match name {
+                            "bftst" => Rules::ONLY_D_REG_OR_CONTROL,
+                            _ => Rules::ONLY_D_REG_OR_CONTROL_ALTERABLE,
+                        };
+                        match &operands[..] {
+                            [field] => self.verify_bit_field(field, rule, line, 1),
+                            _ => self.errors.push(SemanticError::new(
+                                line.clone(),
+                                format!("Expected one operand, received {}", operands.len()),
+                            )),
+                        }
+                        self.verify_size(SizeRules::NoSize, line);
+                    }
+                    "bfextu" | "bfexts" | "bfffo" => {
+                        self.verify_cpu_model(CpuModel::M68020, line);
+                        match &operands[..] {
+                            [field, dest] => {
+                                self.verify_bit_field(field, Rules::ONLY_D_REG_OR_CONTROL, line, 1);
+                                self.verify_arg_rule(dest, Rules::ONLY_D_REG, line, 2);
+                            }
+                            _ => self.errors.push(SemanticError::new(
+                                line.clone(),
+                                format!("Expected two operands, received \"{}\"", operands.len()),
+                            )),
+                        }
+                        self.verify_size(SizeRules::NoSize, line);
+                    }
+                    "bfins" => {
+                        self.verify_cpu_model(CpuModel::M68020, line);
+                        match &operands[..] {
+                            [src, field] => {
+                                self.verify_arg_rule(src, Rules::ONLY_D_REG, line, 1);
+                                self.verify_bit_field(field, Rules::ONLY_D_REG_OR_CONTROL_ALTERABLE, line, 2);
+                            }
+                            _ => self.errors.push(SemanticError::new(
+                                line.clone(),
+                                format!("Expected two operands, received \"{}\"", operands.len()),
+                            )),
+                        }
+                        self.verify_size(SizeRules::NoSize, line);
+                    }
+                    "extb" => {
+                        self.verify_cpu_model(CpuModel::M68020, line);
+                        self.verify_one_arg(operands, Rules::ONLY_D_REG, line);
+                        self.verify_size(SizeRules::OnlyLong, line);
+                    }
                     "tst" => {
                         self.verify_one_arg(operands, Rules::NO_IMMEDIATE, line);
                         self.verify_size(SizeRules::AnySize, line);
@@ -345,7 +615,8 @@ impl SemanticChecker {
                     //other
                     | "bsr" | "bra" => {
                         self.verify_one_arg(operands, Rules::ONLY_ADDRESS, line);
-                        self.verify_size(SizeRules::NoSize, line);
+                        self.verify_size(SizeRules::OnlyShortOrWord, line);
+                        self.verify_branch_displacement(name, operands, size, line);
                     }
                     "scc" | "scs" | "seq" | "sne" | "sge" | "sgt" | "sle" | "sls" | "slt"
                     | "shi" | "smi" | "spl" | "svc" | "svs" | "slo" | "shs" | "sf" | "st" => {
@@ -375,6 +646,27 @@ impl SemanticChecker {
                         self.verify_one_arg(operands, Rules::NO_A_REG_OR_IMMEDIATE, line);
                         self.verify_size(SizeRules::AnySize, line);
                     }
+                    "tas" => {
+                        self.verify_one_arg(operands, Rules::NO_A_REG_OR_IMMEDIATE, line);
+                        self.verify_size(SizeRules::NoSize, line);
+                    }
+                    "movep" => {
+                        match &operands[..] {
+                            [LexedOperand::Register(_, _), _] => self.verify_two_args(operands, Rules::ONLY_D_REG, Rules::ONLY_INDIRECT_OR_DISPLACEMENT, line),
+                            _ => self.verify_two_args(operands, Rules::ONLY_INDIRECT_OR_DISPLACEMENT, Rules::ONLY_D_REG, line),
+                        }
+                        self.verify_size(SizeRules::OnlyLongOrWord, line);
+                    }
+                    "andi" | "ori" | "eori" if matches!(&operands[..], [_, LexedOperand::SpecialRegister(LexedSpecialRegister::SR)]) => {
+                        self.verify_arg_rule(&operands[0], Rules::ONLY_IMMEDIATE, line, 1);
+                        self.verify_size(SizeRules::OnlyWord, line);
+                        self.verify_value_bounds_if_immediate(operands, 0, line, 0, 0xFFFF);
+                    }
+                    "andi" | "ori" | "eori" if matches!(&operands[..], [_, LexedOperand::SpecialRegister(LexedSpecialRegister::CCR)]) => {
+                        self.verify_arg_rule(&operands[0], Rules::ONLY_IMMEDIATE, line, 1);
+                        self.verify_size(SizeRules::OnlyByte, line);
+                        self.verify_value_bounds_if_immediate(operands, 0, line, 0, 0xFF);
+                    }
                     "addi" | "andi" | "ori" | "eori" | "subi" | "cmpi" => {
                         self.verify_two_args(operands, Rules::ONLY_IMMEDIATE, Rules::NO_A_REG_OR_IMMEDIATE, line);
                         self.verify_size(SizeRules::AnySize, line);
@@ -409,11 +701,11 @@ impl SemanticChecker {
                         self.verify_size(SizeRules::AnySize, line);
                     }
                     "lea" => {
-                        self.verify_two_args(operands, Rules::ONLY_INDIRECT_OR_ABSOLUTE, Rules::ONLY_A_REG, line);
+                        self.verify_two_args(operands, Rules::ONLY_INDIRECT_OR_ABSOLUTE_OR_PC, Rules::ONLY_A_REG, line);
                         self.verify_size(SizeRules::NoSize, line);
                     }
                     "pea" => {
-                        self.verify_one_arg(operands, Rules::ONLY_INDIRECT_OR_ABSOLUTE, line);
+                        self.verify_one_arg(operands, Rules::ONLY_INDIRECT_OR_ABSOLUTE_OR_PC, line);
                         self.verify_size(SizeRules::NoSize, line);
                     }
                     "addq" | "subq" => {
@@ -445,7 +737,7 @@ impl SemanticChecker {
                                 self.verify_arg_rule(op2, Rules::ONLY_INDIRECT_OR_ABSOLUTE, line, 2);
                             }
                             [op1, LexedOperand::RegisterRange { .. } | LexedOperand::Register(_, _)] => {
-                                self.verify_arg_rule(op1, Rules::ONLY_INDIRECT_OR_ABSOLUTE, line, 1);
+                                self.verify_arg_rule(op1, Rules::ONLY_INDIRECT_OR_ABSOLUTE_OR_PC, line, 1);
                             }
                             _ => {
                                 self.errors.push(SemanticError::new(
@@ -458,7 +750,7 @@ impl SemanticChecker {
                     "jmp" => {
                         self.verify_one_arg(
                             operands,
-                            Rules::ONLY_INDIRECT_OR_ABSOLUTE,
+                            Rules::ONLY_INDIRECT_OR_ABSOLUTE_OR_PC,
                             line,
                         );
                         self.verify_size(SizeRules::NoSize, line);
@@ -466,7 +758,7 @@ impl SemanticChecker {
                     "jsr" => {
                         self.verify_one_arg(
                             operands,
-                            Rules::ONLY_INDIRECT_OR_ABSOLUTE,
+                            Rules::ONLY_INDIRECT_OR_ABSOLUTE_OR_PC,
                             line,
                         );
                         self.verify_size(SizeRules::NoSize, line);
@@ -478,11 +770,11 @@ impl SemanticChecker {
                             [LexedOperand::Immediate(value)] => {
                                 match self.get_immediate_value(value) {
                                     Ok(value) => {
-                                        if value != 15 {
+                                        if !(0..=15).contains(&value) {
                                             self.errors.push(SemanticError::new(
                                                 line.clone(),
                                                 format!(
-                                                    "Only implemented TRAP is 15 for IO, received \"{}\"",
+                                                    "TRAP vector must be between 0 and 15, received \"{}\"",
                                                     value
                                                 ),
                                             ));
@@ -503,6 +795,44 @@ impl SemanticChecker {
                             ));
                         }
                     }
+                    "rtr" => {
+                        self.verify_size(SizeRules::NoSize, line);
+                        if !operands.is_empty() {
+                            self.errors.push(SemanticError::new(
+                                line.clone(),
+                                "RTR instruction does not accept operands".to_string(),
+                            ));
+                        }
+                    }
+                    "rte" => {
+                        self.verify_size(SizeRules::NoSize, line);
+                        if !operands.is_empty() {
+                            self.errors.push(SemanticError::new(
+                                line.clone(),
+                                "RTE instruction does not accept operands".to_string(),
+                            ));
+                        }
+                    }
+                    "rtd" => {
+                        self.verify_cpu_model(CpuModel::M68010, line);
+                        self.verify_one_arg(operands, Rules::ONLY_IMMEDIATE, line);
+                        self.verify_value_bounds_if_immediate(operands, 0, line, -32768, 32767);
+                        self.verify_size(SizeRules::NoSize, line);
+                    }
+                    "stop" => {
+                        self.verify_one_arg(operands, Rules::ONLY_IMMEDIATE, line);
+                        self.verify_size(SizeRules::NoSize, line);
+                        self.verify_value_bounds_if_immediate(operands, 0, line, 0, 0xFFFF);
+                    }
+                    "trapv" | "illegal" | "reset" => {
+                        self.verify_size(SizeRules::NoSize, line);
+                        if !operands.is_empty() {
+                            self.errors.push(SemanticError::new(
+                                line.clone(),
+                                format!("{} instruction does not accept operands", name.to_uppercase()),
+                            ));
+                        }
+                    }
                     "nop" => {
                         self.verify_size(SizeRules::NoSize, line);
                         if !operands.is_empty() {
@@ -512,15 +842,21 @@ impl SemanticChecker {
                             ));
                         }
                     }
-                    "lsl" | "lsr" | "asr" | "asl" | "rol" | "ror" => {
-                        self.verify_two_args(
-                            operands,
-                            Rules::NO_A_REG,
-                            Rules::NO_A_REG_OR_IMMEDIATE,
-                            line,
-                        );
-                        self.verify_value_bounds_if_immediate(operands, 0, line, 0, 8);
-                        self.verify_size(SizeRules::AnySize, line);
+                    "lsl" | "lsr" | "asr" | "asl" | "rol" | "ror" | "roxl" | "roxr" => {
+                        if operands.len() == 1 {
+                            //memory form, shifts the word by one bit
+                            self.verify_one_arg(operands, Rules::ONLY_INDIRECT_OR_ABSOLUTE, line);
+                            self.verify_size(SizeRules::OnlyWord, line);
+                        } else {
+                            self.verify_two_args(
+                                operands,
+                                Rules::NO_A_REG,
+                                Rules::NO_A_REG_OR_IMMEDIATE,
+                                line,
+                            );
+                            self.verify_value_bounds_if_immediate(operands, 0, line, 0, 8);
+                            self.verify_size(SizeRules::AnySize, line);
+                        }
                     }
                     "btst" | "bclr" | "bchg" | "bset" => {
                         self.verify_two_args(
@@ -532,7 +868,10 @@ impl SemanticChecker {
                         self.verify_size(SizeRules::NoSize, line);
                         self.verify_value_bounds_if_immediate(operands, 0, line, 0, 0xFF);
                     }
-
+                    _ if is_floating_point_instruction(name) => {
+                        self.verify_fpu_model(line);
+                        self.check_floating_point_instruction(name, operands, size, line);
+                    }
                     _ => self.errors.push(SemanticError::new(
                         line.clone(),
                         format!("Unknown instruction: \"{}\"", name),
@@ -546,6 +885,189 @@ impl SemanticChecker {
         }
     }
 
+    fn check_floating_point_instruction(
+        &mut self,
+        name: &str,
+        operands: &[LexedOperand],
+        size: &LexedSize,
+        line: &ParsedLine,
+    ) {
+        match (name, operands) {
+            ("fmove", [LexedOperand::SpecialRegister(register), dest])
+            | ("fmove", [dest, LexedOperand::SpecialRegister(register)]) => {
+                let rule = match register {
+                    LexedSpecialRegister::FPIAR => Rules::NONE,
+                    LexedSpecialRegister::FPCR | LexedSpecialRegister::FPSR => Rules::NO_A_REG,
+                    _ => {
+                        self.errors.push(SemanticError::new(
+                            line.clone(),
+                            format!("Special register \"{:?}\" can't be used with fmove, only FPCR, FPSR and FPIAR", register),
+                        ));
+                        return;
+                    }
+                };
+                //the control register is the source when it is the first operand
+                if matches!(operands[0], LexedOperand::SpecialRegister(_)) {
+                    self.verify_arg_rule(dest, rule | Rules::NO_IMMEDIATE, line, 2);
+                } else {
+                    self.verify_arg_rule(dest, rule, line, 1);
+                }
+                self.verify_size(SizeRules::OnlyLong, line);
+            }
+            ("fmove", [src, dest]) => {
+                self.verify_size(SizeRules::FloatFormat, line);
+                match (src, dest) {
+                    (LexedOperand::FloatRegister(_), LexedOperand::FloatRegister(_)) => {}
+                    (_, LexedOperand::FloatRegister(_)) => self.verify_fp_source(src, size, line),
+                    (LexedOperand::FloatRegister(_), _) => {
+                        self.verify_arg_rule(dest, Rules::NO_A_REG_OR_IMMEDIATE, line, 2);
+                        self.verify_fp_data_register(dest, size, line);
+                    }
+                    _ => self.errors.push(SemanticError::new(
+                        line.clone(),
+                        "Invalid operands for fmove, at least one of the operands must be a floating point register".to_string(),
+                    )),
+                }
+            }
+            ("fmovecr", [offset, dest]) => {
+                self.verify_arg_rule(offset, Rules::ONLY_IMMEDIATE, line, 1);
+                self.verify_value_bounds_if_immediate(operands, 0, line, 0, 0x7F);
+                self.verify_fp_register(dest, line, 2);
+                //the constants are always loaded in extended precision
+                self.verify_size(SizeRules::OnlyExtended, line);
+            }
+            ("fcmp", [src, dest]) => {
+                self.verify_fp_source(src, size, line);
+                self.verify_fp_register(dest, line, 2);
+                self.verify_size(SizeRules::FloatFormat, line);
+            }
+            ("ftst", [src]) => {
+                self.verify_fp_source(src, size, line);
+                self.verify_size(SizeRules::FloatFormat, line);
+            }
+            ("fnop", []) => self.verify_size(SizeRules::NoSize, line),
+            _ if name.parse::<FpOperation>().is_ok() => {
+                let operation = name.parse::<FpOperation>().unwrap();
+                match operands {
+                    [src, dest] => {
+                        self.verify_fp_source(src, size, line);
+                        self.verify_fp_register(dest, line, 2);
+                    }
+                    [dest] if !operation.is_dyadic() => self.verify_fp_register(dest, line, 1),
+                    _ => self.errors.push(SemanticError::new(
+                        line.clone(),
+                        format!(
+                            "Expected \"<ea>,FPn\" or \"FPn\" as operands, received \"{}\" operands",
+                            operands.len()
+                        ),
+                    )),
+                }
+                self.verify_size(SizeRules::FloatFormat, line);
+            }
+            _ if name.starts_with("fdb") => {
+                self.verify_two_args(operands, Rules::ONLY_D_REG, Rules::ONLY_ADDRESS, line);
+                self.verify_size(SizeRules::NoSize, line);
+            }
+            _ if name.starts_with("fb") => {
+                self.verify_one_arg(operands, Rules::ONLY_ADDRESS, line);
+                self.verify_size(SizeRules::OnlyLongOrWord, line);
+            }
+            _ if name.starts_with("fs") => {
+                self.verify_one_arg(operands, Rules::NO_A_REG_OR_IMMEDIATE, line);
+                self.verify_size(SizeRules::OnlyByte, line);
+            }
+            _ => self.errors.push(SemanticError::new(
+                line.clone(),
+                format!(
+                    "Invalid operands for \"{}\", received \"{}\" operands",
+                    name,
+                    operands.len()
+                ),
+            )),
+        }
+    }
+    /**
+    The source of a floating point instruction can be a FPn register, a floating point immediate or
+    any operand except an address register, data registers only hold the formats that fit in 32 bits
+     */
+    fn verify_fp_source(&mut self, src: &LexedOperand, size: &LexedSize, line: &ParsedLine) {
+        match src {
+            LexedOperand::FloatRegister(_) => {}
+            LexedOperand::Immediate(value) => {
+                let format = match size {
+                    LexedSize::Byte => FpFormat::Byte,
+                    LexedSize::Word => FpFormat::Word,
+                    LexedSize::Long => FpFormat::Long,
+                    LexedSize::Short => FpFormat::Single,
+                    LexedSize::Double => FpFormat::Double,
+                    _ => FpFormat::Extended,
+                };
+                if let Err(e) = parse_float_expression(&value[1..], format, &self.labels) {
+                    self.errors.push(SemanticError::new(
+                        line.clone(),
+                        format!("Invalid immediate: {}", e),
+                    ));
+                    return;
+                }
+                //the integer formats are stored in the same bits as the integer instructions
+                if let Some(integer_size) = format.to_integer_size() {
+                    let bits = integer_size.to_bits() as i64;
+                    if let Ok(parsed) = self.get_immediate_value(value) {
+                        if num_to_signed_base(parsed, bits).is_err() {
+                            self.errors.push(SemanticError::new(
+                                line.clone(),
+                                format!(
+                                    "Immediate value \"{}\" is not a valid {} bits number, received \"{}\"",
+                                    value, bits, parsed
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => {
+                self.verify_arg_rule(src, Rules::NO_A_REG, line, 1);
+                self.verify_fp_data_register(src, size, line);
+            }
+        }
+    }
+    fn verify_fp_data_register(
+        &mut self,
+        operand: &LexedOperand,
+        size: &LexedSize,
+        line: &ParsedLine,
+    ) {
+        let is_data_register =
+            matches!(operand, LexedOperand::Register(LexedRegisterType::Data, _));
+        let fits_register = matches!(
+            size,
+            LexedSize::Byte | LexedSize::Word | LexedSize::Long | LexedSize::Short
+        );
+        if is_data_register && !fits_register {
+            self.errors.push(SemanticError::new(
+                line.clone(),
+                "Data registers can only be used with the b, w, l and s formats".to_string(),
+            ));
+        }
+    }
+    fn verify_fp_register(
+        &mut self,
+        operand: &LexedOperand,
+        line: &ParsedLine,
+        arg_position: usize,
+    ) {
+        match operand {
+            LexedOperand::FloatRegister(_) => {}
+            _ => self.errors.push(SemanticError::new(
+                line.clone(),
+                format!(
+                    "Expected a floating point register \"FPn\" as operand {}",
+                    arg_position
+                ),
+            )),
+        }
+    }
+
     fn verify_directive(&mut self, line: &ParsedLine) {
         match &line.parsed {
             LexedLine::Directive { args, name, size } => match name.as_str() {
@@ -566,12 +1088,24 @@ impl SemanticChecker {
                     }
                 }
                 "dc" => {
-                    self.verify_size(SizeRules::AnySize, line);
+                    self.verify_size(SizeRules::AnySizeOrFloat, line);
                     match &args[..] {
                         [_, ..] => {
                             for (i, arg) in args[1..].iter().enumerate() {
                                 match arg {
                                     _ if arg.starts_with('\'') && arg.ends_with('\'') => {}
+                                    _ if matches!(size, LexedSize::Short | LexedSize::Double) => {
+                                        let format = match size {
+                                            LexedSize::Short => FpFormat::Single,
+                                            _ => FpFormat::Double,
+                                        };
+                                        if let Err(e) = parse_float_expression(arg, format, &self.labels) {
+                                            self.errors.push(SemanticError::new(
+                                                line.clone(),
+                                                format!("Invalid argument \"{}\" for directive dc at position {}: {}", arg, i + 1, e),
+                                            ));
+                                        }
+                                    }
                                     _ => {
                                         match self.get_absolute_value(arg) {
                                             Ok(_) => {}
@@ -746,6 +1280,75 @@ impl SemanticChecker {
         }
     }
 
+    /**
+    The displacement of a branch is from the instruction address + 2, it must fit in a byte
+    for the short form and in a word otherwise
+     */
+    fn verify_branch_displacement(
+        &mut self,
+        name: &str,
+        operands: &[LexedOperand],
+        size: &LexedSize,
+        line: &ParsedLine,
+    ) {
+        let (address, labels) = match (self.current_address, &self.line_addresses) {
+            (Some(address), Some((_, labels))) => (address, labels),
+            _ => return,
+        };
+        let target = match operands {
+            [LexedOperand::Label(value) | LexedOperand::Absolute(value)] => {
+                match parse_absolute_expression(value, labels) {
+                    Ok(target) => target,
+                    Err(_) => return,
+                }
+            }
+            _ => return,
+        };
+        let displacement = target - (address as i64 + 2);
+        let fits_byte = (-128..=127).contains(&displacement);
+        match size {
+            //a displacement of 0 in the opcode means that a word displacement follows
+            LexedSize::Short | LexedSize::Byte if displacement == 0 => {
+                self.errors.push(SemanticError::new(
+                    line.clone(),
+                    "Branch to the next instruction can't use the short form, it has a displacement of 0"
+                        .to_string(),
+                ));
+            }
+            LexedSize::Short | LexedSize::Byte if !fits_byte => {
+                let suffix = if *size == LexedSize::Short { "s" } else { "b" };
+                self.errors.push(SemanticError::new(
+                    line.clone(),
+                    format!(
+                        "Branch target out of range for .{} (-128..127), received displacement {}",
+                        suffix, displacement
+                    ),
+                ));
+            }
+            LexedSize::Word | LexedSize::Unspecified
+                if !(-32768..=32767).contains(&displacement) =>
+            {
+                self.errors.push(SemanticError::new(
+                    line.clone(),
+                    format!(
+                        "Branch target out of range for .w (-32768..32767), received displacement {}",
+                        displacement
+                    ),
+                ));
+            }
+            LexedSize::Word if fits_byte => {
+                self.warnings.push(SemanticError::warning(
+                    line.clone(),
+                    format!(
+                        "Branch target is in range of the short form, \"{}.s\" could be used",
+                        name
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+
     fn verify_value_bounds_if_immediate(
         &mut self,
         args: &[LexedOperand],
@@ -809,10 +1412,182 @@ impl SemanticChecker {
             _ => None,
         }
     }
+    fn verify_bit_field(
+        &mut self,
+        arg: &LexedOperand,
+        rule: Rules,
+        line: &ParsedLine,
+        arg_position: usize,
+    ) {
+        match arg {
+            LexedOperand::BitField {
+                operand,
+                offset,
+                width,
+            } => {
+                self.verify_arg_rule(operand, rule, line, arg_position);
+                self.verify_bit_field_value(offset, "offset", line, 0, 31);
+                self.verify_bit_field_value(width, "width", line, 1, 32);
+            }
+            _ => self.errors.push(SemanticError::new(
+                line.clone(),
+                format!(
+                    "Expected a bit field \"<ea>{{offset:width}}\" as operand {}",
+                    arg_position
+                ),
+            )),
+        }
+    }
+    fn verify_bit_field_value(
+        &mut self,
+        value: &LexedOperand,
+        kind: &str,
+        line: &ParsedLine,
+        min: i64,
+        max: i64,
+    ) {
+        match value {
+            LexedOperand::Register(LexedRegisterType::Data, _) => {
+                if let Err(e) = self.get_addressing_mode(value) {
+                    self.errors.push(SemanticError::new(line.clone(), e));
+                }
+            }
+            LexedOperand::Absolute(num) | LexedOperand::Immediate(num) => {
+                match self.get_absolute_value(num.trim_start_matches('#')) {
+                    Ok(n) if n < min || n > max => self.errors.push(SemanticError::new(
+                        line.clone(),
+                        format!(
+                            "Bit field {} \"{}\" out of range, must be between \"{}\" and \"{}\"",
+                            kind, n, min, max
+                        ),
+                    )),
+                    Ok(_) => {}
+                    Err(e) => self.errors.push(SemanticError::new(
+                        line.clone(),
+                        format!("Invalid bit field {}: {}", kind, e),
+                    )),
+                }
+            }
+            _ => self.errors.push(SemanticError::new(
+                line.clone(),
+                format!(
+                    "Invalid bit field {}, must be a number or a data register",
+                    kind
+                ),
+            )),
+        }
+    }
+    fn verify_full_format(&self, name: &str) -> Result<(), String> {
+        if self.cpu_model < CpuModel::M68020 {
+            return Err(format!(
+                "{} requires the 68020 cpu model, but the selected model is {}",
+                name,
+                self.cpu_model.get_name()
+            ));
+        }
+        Ok(())
+    }
+    fn verify_index_register(&self, index: &LexedOperand) -> Result<(), String> {
+        match index {
+            LexedOperand::RegisterWithSize(_, _, LexedSize::Byte) => {
+                Err("Byte size in register is not allowed for index indirect".to_string())
+            }
+            LexedOperand::Register(_, _) | LexedOperand::RegisterWithSize(_, _, _) => Ok(()),
+            LexedOperand::ScaledIndex { register, scale } => {
+                self.verify_index_register(register)?;
+                match self.get_absolute_value(scale) {
+                    Ok(1) => Ok(()),
+                    Ok(2 | 4 | 8) => self.verify_full_format("Index scaling"),
+                    _ => Err(format!(
+                        "Invalid index scale \"{}\", must be 1, 2, 4 or 8",
+                        scale
+                    )),
+                }
+            }
+            _ => Err("Invalid index register, only \"Dn/An\" allowed".to_string()),
+        }
+    }
+    //the 68000 only has the brief extension word with an 8 bit displacement
+    fn verify_index_displacement(&self, displacement: i64) -> Result<(), String> {
+        let (min, max) = if self.cpu_model < CpuModel::M68020 {
+            (-128, 127)
+        } else {
+            (i32::MIN as i64, u32::MAX as i64)
+        };
+        if displacement < min || displacement > max {
+            return Err(format!(
+                "Invalid offset \"{}\", must be between {} and {}",
+                displacement, min, max
+            ));
+        }
+        Ok(())
+    }
+    /**
+    The displacement of a pc relative offset, a plain number is used as is while a label
+    is converted to the distance from the extension word, None if it can't be computed
+     */
+    fn get_pc_displacement(&self, offset: &str) -> Option<i64> {
+        if offset.is_empty() {
+            return Some(0);
+        }
+        if let Ok(value) = parse_absolute_expression(offset, &HashMap::new()) {
+            return Some(value);
+        }
+        match (self.current_address, &self.line_addresses) {
+            (Some(address), Some((_, labels))) => parse_absolute_expression(offset, labels)
+                .ok()
+                .map(|target| target - (address as i64 + 2)),
+            _ => None,
+        }
+    }
+    fn verify_cpu_model(&mut self, model: CpuModel, line: &ParsedLine) {
+        if self.cpu_model < model {
+            let name = match &line.parsed {
+                LexedLine::Instruction { name, .. } => name.to_string(),
+                _ => line.line.trim().to_string(),
+            };
+            self.errors.push(SemanticError::new(
+                line.clone(),
+                format!(
+                    "Instruction \"{}\" requires the {} cpu model, but the selected model is {}",
+                    name,
+                    model.get_name(),
+                    self.cpu_model.get_name()
+                ),
+            ));
+        }
+    }
+    fn verify_fpu_model(&mut self, line: &ParsedLine) {
+        if self.fpu_model == FpuModel::None {
+            let name = match &line.parsed {
+                LexedLine::Instruction { name, .. } => name.to_string(),
+                _ => line.line.trim().to_string(),
+            };
+            self.errors.push(SemanticError::new(
+                line.clone(),
+                format!(
+                    "Instruction \"{}\" requires a floating point coprocessor (68881/68882), but none is selected",
+                    name
+                ),
+            ));
+        }
+    }
     fn verify_size(&mut self, rule: SizeRules, line: &ParsedLine) {
         match &line.parsed {
             LexedLine::Instruction { size, .. } | LexedLine::Directive { size, .. } => match rule {
-                _ if *size == LexedSize::Unknown => {
+                _ if *size == LexedSize::Unknown
+                    || (*size == LexedSize::Short
+                        && !matches!(
+                            rule,
+                            SizeRules::OnlyShortOrWord
+                                | SizeRules::FloatFormat
+                                | SizeRules::AnySizeOrFloat
+                        ))
+                    || (*size == LexedSize::Double
+                        && !matches!(rule, SizeRules::FloatFormat | SizeRules::AnySizeOrFloat))
+                    || (*size == LexedSize::Extended
+                        && !matches!(rule, SizeRules::FloatFormat | SizeRules::OnlyExtended)) =>
+                {
                     self.errors.push(SemanticError::new(
                         line.clone(),
                         format!(
@@ -841,6 +1616,47 @@ impl SemanticChecker {
                         ));
                     }
                 }
+                SizeRules::OnlyWord => {
+                    if *size != LexedSize::Word && *size != LexedSize::Unspecified {
+                        self.errors.push(SemanticError::new(
+                            line.clone(),
+                            "Invalid size, instruction must be word".to_string(),
+                        ));
+                    }
+                }
+                SizeRules::OnlyLong => {
+                    if *size != LexedSize::Long && *size != LexedSize::Unspecified {
+                        self.errors.push(SemanticError::new(
+                            line.clone(),
+                            "Invalid size, instruction must be long".to_string(),
+                        ));
+                    }
+                }
+                SizeRules::OnlyShortOrWord => {
+                    if *size == LexedSize::Long {
+                        self.errors.push(SemanticError::new(
+                            line.clone(),
+                            "Invalid size, branch must be short, byte or word".to_string(),
+                        ));
+                    }
+                }
+                SizeRules::OnlyByte => {
+                    if *size != LexedSize::Byte && *size != LexedSize::Unspecified {
+                        self.errors.push(SemanticError::new(
+                            line.clone(),
+                            "Invalid size, instruction must be byte".to_string(),
+                        ));
+                    }
+                }
+                SizeRules::OnlyExtended => {
+                    if *size != LexedSize::Extended && *size != LexedSize::Unspecified {
+                        self.errors.push(SemanticError::new(
+                            line.clone(),
+                            "Invalid size, instruction must be extended".to_string(),
+                        ));
+                    }
+                }
+                SizeRules::FloatFormat | SizeRules::AnySizeOrFloat => {}
                 SizeRules::AnySize => {
                     match *size {
                         LexedSize::Byte => {
@@ -884,7 +1700,35 @@ impl SemanticChecker {
                 },
                 LexedRegisterType::SP => Ok(AdrMode::A_REG),
             },
+            LexedOperand::SpecialRegister(register) => Err(format!(
+                "Special register \"{:?}\" can only be used with move, andi, ori and eori",
+                register
+            )),
             LexedOperand::RegisterRange { .. } => Ok(AdrMode::REG_LIST),
+            LexedOperand::FloatRegister(_) => Err(
+                "Floating point registers can only be used with the floating point instructions"
+                    .to_string(),
+            ),
+            LexedOperand::BitField { .. } => Err(
+                "Bit field operands can only be used with the bit field instructions".to_string(),
+            ),
+            LexedOperand::RegisterPair(high, low) => match (high.as_ref(), low.as_ref()) {
+                (
+                    LexedOperand::Register(LexedRegisterType::Data, high),
+                    LexedOperand::Register(LexedRegisterType::Data, low),
+                ) => {
+                    if high == low {
+                        return Err("The registers of a pair must be different".to_string());
+                    }
+                    match (high[1..].parse::<i8>(), low[1..].parse::<i8>()) {
+                        (Ok(h), Ok(l)) if (0..8).contains(&h) && (0..8).contains(&l) => {
+                            Ok(AdrMode::REG_PAIR)
+                        }
+                        _ => Err("Invalid data register".to_string()),
+                    }
+                }
+                _ => Err("Invalid register pair, only data registers allowed".to_string()),
+            },
             LexedOperand::Immediate(num) => match self.get_immediate_value(num) {
                 Ok(_) => Ok(AdrMode::IMMEDIATE),
                 Err(e) => Err(format!("Invalid immediate: {}", e)),
@@ -941,31 +1785,40 @@ impl SemanticChecker {
             LexedOperand::IndirectIndex {
                 operands, offset, ..
             } => {
-                if !offset.is_empty() {
+                let offset_value = if offset.is_empty() {
+                    0
+                } else {
                     match parse_absolute_expression(offset, &self.labels) {
-                        Ok(num) => {
-                            if num < -128 || num > 127 {
-                                return Err(format!(
-                                    "Invalid offset \"{}\", must be between -128 and 127",
-                                    num
-                                ));
-                            }
-                        }
+                        Ok(num) => num,
                         Err(_) => {
                             return Err(format!("Offset \"{}\" is not a valid expression", offset))
                         }
                     }
-                }
-                match operands[..] {
-                    [LexedOperand::Register(LexedRegisterType::Address, _), LexedOperand::Register(_, _) | LexedOperand::RegisterWithSize(_, _, _)] =>
-                    {
-                        let op2 = &operands[1];
-                        if let LexedOperand::RegisterWithSize(_, _, size) = op2 {
-                            if *size == LexedSize::Byte {
-                                return Err(
-                                    "Byte size in register is not allowed for index indirect"
-                                        .to_string(),
-                                );
+                };
+                match &operands[..] {
+                    [LexedOperand::Register(
+                        LexedRegisterType::Address | LexedRegisterType::SP,
+                        _,
+                    ), index] => {
+                        self.verify_index_register(index)?;
+                        self.verify_index_displacement(offset_value)?;
+                        Ok(AdrMode::INDIRECT_INDEX)
+                    }
+                    //(bd, An, Xn), (bd, An) and (bd, Xn) with the base displacement inside the parenthesis
+                    [LexedOperand::Absolute(displacement), rest @ ..] if offset.is_empty() => {
+                        self.verify_full_format("Base displacement inside the parenthesis")?;
+                        if parse_absolute_expression(displacement, &self.labels).is_err() {
+                            return Err(format!(
+                                "Displacement \"{}\" is not a valid expression",
+                                displacement
+                            ));
+                        }
+                        match rest {
+                            [LexedOperand::Register(LexedRegisterType::Address | LexedRegisterType::SP, _)] => {}
+                            [LexedOperand::Register(LexedRegisterType::Address | LexedRegisterType::SP, _), index]
+                            | [index] => self.verify_index_register(index)?,
+                            _ => {
+                                return Err("Invalid operands for index indirect, only \"(bd, An, Xn)\" allowed".to_string())
                             }
                         }
                         Ok(AdrMode::INDIRECT_INDEX)
@@ -976,6 +1829,70 @@ impl SemanticChecker {
                     ),
                 }
             }
+            LexedOperand::MemoryIndirect {
+                base_displacement,
+                base,
+                index,
+                outer_displacement,
+                ..
+            } => {
+                self.verify_full_format("Memory indirect addressing")?;
+                for displacement in [base_displacement, outer_displacement] {
+                    if !displacement.is_empty()
+                        && parse_absolute_expression(displacement, &self.labels).is_err()
+                    {
+                        return Err(format!(
+                            "Displacement \"{}\" is not a valid expression",
+                            displacement
+                        ));
+                    }
+                }
+                if let Some(index) = index {
+                    self.verify_index_register(index)?;
+                }
+                match base.as_deref() {
+                    Some(LexedOperand::SpecialRegister(LexedSpecialRegister::PC)) => {
+                        Ok(AdrMode::PC_INDEX)
+                    }
+                    Some(LexedOperand::Register(
+                        LexedRegisterType::Address | LexedRegisterType::SP,
+                        _,
+                    ))
+                    | None => Ok(AdrMode::INDIRECT_INDEX),
+                    _ => Err("Invalid base for memory indirect, only An or PC allowed".to_string()),
+                }
+            }
+            LexedOperand::ScaledIndex { .. } => Err(
+                "Scaled index registers can only be used inside the indexed addressing modes"
+                    .to_string(),
+            ),
+            //a label offset is the target address, its distance from the pc is checked by the compiler
+            LexedOperand::PCDisplacement { offset } => {
+                if !offset.is_empty() && parse_absolute_expression(offset, &self.labels).is_err() {
+                    return Err(format!("Offset \"{}\" is not a valid expression", offset));
+                }
+                Ok(AdrMode::PC_DISPLACEMENT)
+            }
+            LexedOperand::PCIndex { offset, index } => {
+                if !offset.is_empty() && parse_absolute_expression(offset, &self.labels).is_err() {
+                    return Err(format!("Offset \"{}\" is not a valid expression", offset));
+                }
+                if let Some(displacement) = self.get_pc_displacement(offset) {
+                    self.verify_index_displacement(displacement)?;
+                }
+                match index.as_ref() {
+                    LexedOperand::Register(_, _)
+                    | LexedOperand::RegisterWithSize(_, _, _)
+                    | LexedOperand::ScaledIndex { .. } => {
+                        self.verify_index_register(index)?;
+                        Ok(AdrMode::PC_INDEX)
+                    }
+                    _ => Err(
+                        "Invalid operands for pc relative index, only \"(PC, Dn/An)\" allowed"
+                            .to_string(),
+                    ),
+                }
+            }
             LexedOperand::Label(name) => {
                 if self.labels.contains_key(name) {
                     Ok(AdrMode::ADDRESS)
@@ -988,6 +1905,38 @@ impl SemanticChecker {
                 Ok(_) => Ok(AdrMode::ADDRESS),
                 Err(e) => Err(format!("Invalid absolute: {}", e)),
             },
+            LexedOperand::AbsoluteWithSize(data, size) => {
+                if let Err(e) = self.get_absolute_value(data) {
+                    return Err(format!("Invalid absolute: {}", e));
+                }
+                //the labels only have their real address if the line addresses could be computed
+                let value = match &self.line_addresses {
+                    Some((_, labels)) => parse_absolute_expression(data, labels).ok(),
+                    None => None,
+                };
+                match (size, value) {
+                    //a .w address is sign extended, so both $8000 and $FFFF8000 are valid for the upper 32kb
+                    (LexedSize::Word, Some(value))
+                        if !(-0x8000..=0xFFFF).contains(&value)
+                            && !(0xFFFF8000..=0xFFFFFFFF).contains(&value) =>
+                    {
+                        Err(format!(
+                            "Absolute address \"{}\" out of range for .w, must be between $0 and $FFFF, or $FFFF8000 and $FFFFFFFF",
+                            data
+                        ))
+                    }
+                    (LexedSize::Long, Some(value))
+                        if !(-0x80000000..=0xFFFFFFFF).contains(&value) =>
+                    {
+                        Err(format!(
+                            "Absolute address \"{}\" out of range for .l, must fit in 32 bits",
+                            data
+                        ))
+                    }
+                    (LexedSize::Word | LexedSize::Long, _) => Ok(AdrMode::ADDRESS),
+                    _ => Err("Invalid size for absolute address, only .w and .l are allowed".to_string()),
+                }
+            }
             LexedOperand::Other(_) => Err("Unknown operand".to_string()),
         }
     }
@@ -1001,3 +1950,18 @@ impl SemanticChecker {
         }
     }
 }
+
+/**
+Whether the name is one of the floating point coprocessor instructions, including the conditional ones
+ */
+fn is_floating_point_instruction(name: &str) -> bool {
+    match name {
+        "fmove" | "fmovecr" | "fcmp" | "ftst" | "fnop" => true,
+        _ if name.parse::<FpOperation>().is_ok() => true,
+        _ if name.starts_with("fdb") => name[3..].parse::<FpCondition>().is_ok(),
+        _ if name.starts_with("fb") || name.starts_with("fs") => {
+            name[2..].parse::<FpCondition>().is_ok()
+        }
+        _ => false,
+    }
+}
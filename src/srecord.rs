@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    compiler::MemorySegment,
+    record_file::{RecordError, RecordResult, ADDRESS_SPACE_SIZE, BYTES_PER_RECORD},
+    utils::parse_hex_bytes,
+};
+
+/**
+The address width of the records, S19 uses 16 bit addresses (S1/S9), S28 24 bit (S2/S8)
+and S37 32 bit (S3/S7)
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[wasm_bindgen]
+pub enum SRecordFormat {
+    S19,
+    S28,
+    S37,
+}
+
+impl SRecordFormat {
+    fn address_bytes(&self) -> usize {
+        match self {
+            SRecordFormat::S19 => 2,
+            SRecordFormat::S28 => 3,
+            SRecordFormat::S37 => 4,
+        }
+    }
+    fn max_address(&self) -> usize {
+        (1usize << (self.address_bytes() * 8)) - 1
+    }
+    //the record type of the data and of the termination record with the entry point
+    fn record_types(&self) -> (u8, u8) {
+        match self {
+            SRecordFormat::S19 => (1, 9),
+            SRecordFormat::S28 => (2, 8),
+            SRecordFormat::S37 => (3, 7),
+        }
+    }
+}
+
+/**
+The memory image read from an S-record file, with the entry point of the termination record
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct SRecordImage {
+    pub header: String,
+    pub segments: Vec<MemorySegment>,
+    pub entry: Option<usize>,
+}
+
+fn format_record(record_type: u8, address: usize, address_bytes: usize, data: &[u8]) -> String {
+    let mut bytes = vec![(address_bytes + data.len() + 1) as u8];
+    bytes.extend_from_slice(&address.to_be_bytes()[8 - address_bytes..]);
+    bytes.extend_from_slice(data);
+    bytes.push(checksum(&bytes));
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    format!("S{}{}", record_type, hex)
+}
+
+//the ones' complement of the low byte of the sum of the count, address and data bytes
+fn checksum(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+/**
+Writes the segments as S-records, a S0 header, the data records, the S5/S6 record count
+and the termination record with the entry point
+ */
+pub fn write_srecords(
+    segments: &[MemorySegment],
+    entry: usize,
+    format: SRecordFormat,
+    header: &str,
+) -> RecordResult<String> {
+    let (data_type, termination_type) = format.record_types();
+    let address_bytes = format.address_bytes();
+    if entry > format.max_address() {
+        return Err(RecordError::AddressOutOfRange { address: entry });
+    }
+    let mut records = vec![format_record(0, 0, 2, header.as_bytes())];
+    let mut count = 0;
+    for segment in segments {
+        for (i, chunk) in segment.data.chunks(BYTES_PER_RECORD).enumerate() {
+            let address = segment.address + i * BYTES_PER_RECORD;
+            if address + chunk.len() - 1 > format.max_address() {
+                return Err(RecordError::AddressOutOfRange { address });
+            }
+            records.push(format_record(data_type, address, address_bytes, chunk));
+            count += 1;
+        }
+    }
+    //the count record is optional, it is omitted when it does not fit in S6
+    if count <= 0xFFFF {
+        records.push(format_record(5, count, 2, &[]));
+    } else if count <= 0xFFFFFF {
+        records.push(format_record(6, count, 3, &[]));
+    }
+    records.push(format_record(termination_type, entry, address_bytes, &[]));
+    let mut text = records.join("\n");
+    text.push('\n');
+    Ok(text)
+}
+
+/**
+Reads the S-records into the memory segments, the contiguous data records are merged and the
+segments sorted by address
+ */
+pub fn parse_srecords(text: &str) -> RecordResult<SRecordImage> {
+    let mut header = String::new();
+    let mut blocks: Vec<(usize, Vec<u8>)> = Vec::new();
+    let mut entry = None;
+    let mut count = 0;
+    for (line, record) in text.lines().enumerate() {
+        let record = record.trim();
+        if record.is_empty() {
+            continue;
+        }
+        let invalid = |message: &str| RecordError::InvalidRecord {
+            line,
+            message: message.to_string(),
+        };
+        let record_type = match record.strip_prefix('S').and_then(|r| r.chars().next()) {
+            Some(c) => c
+                .to_digit(10)
+                .ok_or_else(|| invalid("unknown record type"))?,
+            None => return Err(invalid("records must start with S")),
+        };
+        let bytes = parse_hex_bytes(&record[2..])
+            .ok_or_else(|| invalid("the record contains non hexadecimal characters"))?;
+        if bytes.len() < 2 || bytes[0] as usize != bytes.len() - 1 {
+            return Err(invalid("the byte count does not match the record length"));
+        }
+        let (body, found) = bytes.split_at(bytes.len() - 1);
+        let expected = checksum(body);
+        if expected != found[0] {
+            return Err(RecordError::ChecksumMismatch {
+                line,
+                expected,
+                found: found[0],
+            });
+        }
+        let address_bytes = match record_type {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err(invalid("S4 is a reserved record type")),
+        };
+        if body.len() < address_bytes + 1 {
+            return Err(invalid("the record is shorter than its address"));
+        }
+        let address = body[1..=address_bytes]
+            .iter()
+            .fold(0usize, |address, b| (address << 8) | *b as usize);
+        let data = &body[address_bytes + 1..];
+        match record_type {
+            0 => header = String::from_utf8_lossy(data).to_string(),
+            1..=3 => {
+                if address + data.len() > ADDRESS_SPACE_SIZE {
+                    return Err(RecordError::AddressOutOfRange { address });
+                }
+                count += 1;
+                match blocks.last_mut() {
+                    Some((start, last)) if *start + last.len() == address => {
+                        last.extend_from_slice(data)
+                    }
+                    _ => blocks.push((address, data.to_vec())),
+                }
+            }
+            5 | 6 => {
+                if address != count {
+                    return Err(invalid(&format!(
+                        "the file has {} data records but the count record says {}",
+                        count, address
+                    )));
+                }
+            }
+            //the records after the termination one are ignored
+            _ => {
+                if address >= ADDRESS_SPACE_SIZE {
+                    return Err(RecordError::AddressOutOfRange { address });
+                }
+                entry = Some(address);
+                break;
+            }
+        }
+    }
+    Ok(SRecordImage {
+        header,
+        segments: MemorySegment::merge(blocks),
+        entry,
+    })
+}
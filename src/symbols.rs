@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SymbolKind {
+    //a label of an instruction
+    Code,
+    //a label of the data directives, or of a line that has no instruction
+    Data,
+    //an EQU constant
+    Constant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    //the address of the label or the value of the constant, None when the EQU is not a number, like a register
+    pub value: Option<usize>,
+    //the text that replaces the EQU in the code
+    pub expression: Option<String>,
+    pub line: usize,
+    pub kind: SymbolKind,
+}
+
+impl SymbolKind {
+    //the symbol types of the nm output, text, data and absolute
+    fn to_nm_type(self) -> char {
+        match self {
+            SymbolKind::Code => 'T',
+            SymbolKind::Data => 'D',
+            SymbolKind::Constant => 'A',
+        }
+    }
+}
+
+/**
+Writes the symbols in the nm format, one "value type name" line per symbol sorted by value,
+the constants without a numeric value are left out
+ */
+pub fn write_symbol_map(symbols: &[Symbol]) -> String {
+    let mut symbols: Vec<(usize, &Symbol)> = symbols
+        .iter()
+        .filter_map(|symbol| symbol.value.map(|value| (value, symbol)))
+        .collect();
+    symbols.sort_by(|(a, a_symbol), (b, b_symbol)| (a, &a_symbol.name).cmp(&(b, &b_symbol.name)));
+    symbols
+        .iter()
+        .map(|(value, symbol)| {
+            format!(
+                "{:08X} {} {}\n",
+                value,
+                symbol.kind.to_nm_type(),
+                symbol.name
+            )
+        })
+        .collect()
+}
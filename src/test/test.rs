@@ -8,8 +8,22 @@ use crate::S68k;
 //TODO add better tests for all cases and if i find bugs etc
 #[cfg(test)]
 mod tests {
+    use crate::compiler::MemorySegment;
+    use crate::disassembler::{decode_instruction, disassemble, DecodeError};
+    use crate::encoder::encode_instruction;
+    use crate::instructions::{CpuModel, FpuModel, Instruction, Operand, RegisterOperand, Size};
+    use crate::intel_hex::{parse_intel_hex, write_intel_hex};
     use crate::interpreter;
-    use crate::test::test::lex_and_run;
+    use crate::interpreter::{
+        ExecutionMode, Flags, Interpreter, InterpreterOptions, InterpreterStatus, MemoryRegion,
+        RuntimeError, SystemFlags,
+    };
+    use crate::lexer::LexedLine;
+    use crate::record_file::RecordError;
+    use crate::srecord::{parse_srecords, SRecordFormat};
+    use crate::symbols::{write_symbol_map, SymbolKind};
+    use crate::test::test::{lex_and_run, lex_only};
+    use crate::S68k;
 
     #[test]
     fn equ_substitution() {
@@ -60,6 +74,1557 @@ and.w d0, -(a0)");
         );
     }
 
+    #[test]
+    fn extended_arithmetic_chains_carry() {
+        let interpreter = lex_and_run(
+            "
+    move.l #1, d0
+    move.l #$FFFFFFFF, d1
+    move.l #0, d2
+    move.l #1, d3
+    add.l d3, d1
+    addx.l d2, d0
+    move.l #0, d4
+    move.l #0, d5
+    sub.l d3, d5
+    subx.l d2, d4
+    move.l #$2000, a0
+    move.l #$2010, a1
+    move.l #$00000001, $1FF8
+    move.l #$FFFFFFFF, $1FFC
+    move.l #$00000002, $2008
+    move.l #$00000001, $200C
+    move.b #0, d6
+    add.b d6, d6
+    subx.l -(a0), -(a1)
+    subx.l -(a0), -(a1)
+    move.l #1, d7
+    negx.l d7
+    ",
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+            2
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Long),
+            0
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(4), Size::Long),
+            0xFFFFFFFF
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(5), Size::Long),
+            0xFFFFFFFF
+        );
+        let mem = interpreter.get_memory();
+        assert_eq!(mem.read_long(0x2008).unwrap(), 0);
+        assert_eq!(mem.read_long(0x200C).unwrap(), 2);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(7), Size::Long),
+            0xFFFFFFFF
+        );
+        assert!(interpreter.get_flag(Flags::Extend));
+    }
+
+    #[test]
+    fn extended_arithmetic_only_clears_zero() {
+        let interpreter = lex_and_run(
+            "
+    move.l #5, d1
+    move.l #5, d3
+    move.l #0, d0
+    move.l #0, d2
+    sub.l d3, d1
+    subx.l d2, d0
+    ",
+        );
+        assert!(interpreter.get_flag(Flags::Zero));
+        let interpreter = lex_and_run(
+            "
+    move.l #6, d1
+    move.l #5, d3
+    move.l #0, d0
+    move.l #0, d2
+    sub.l d3, d1
+    subx.l d2, d0
+    ",
+        );
+        assert!(!interpreter.get_flag(Flags::Zero));
+    }
+
+    #[test]
+    fn bcd_arithmetic() {
+        let interpreter = lex_and_run(
+            "
+    move.b #$19, d0
+    move.b #$28, d1
+    abcd d0, d1
+    move.b #$99, d2
+    move.b #$01, d3
+    abcd d3, d2
+    move.b #$47, d4
+    move.b #$19, d5
+    sbcd d5, d4
+    move.l #$2002, a0
+    move.l #$3002, a1
+    move.w #$1234, $2000
+    move.w #$0899, $3000
+    move.b #0, d7
+    add.b d7, d7
+    abcd -(a0), -(a1)
+    abcd -(a0), -(a1)
+    move.b #$01, d6
+    nbcd d6
+    ",
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Byte),
+            0x47
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Byte),
+            0x00
+        );
+        //the extend bit set by the previous abcd is subtracted too
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(4), Size::Byte),
+            0x27
+        );
+        assert_eq!(interpreter.get_memory().read_word(0x3000).unwrap(), 0x2133);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(6), Size::Byte),
+            0x99
+        );
+        assert!(interpreter.get_flag(Flags::Extend));
+    }
+
+    #[test]
+    fn rotate_through_extend_and_memory_shifts() {
+        let interpreter = lex_and_run(
+            "
+    move.l #$80000001, d0
+    move.l #$00000000, d1
+    lsl.l #1, d0
+    roxl.l #1, d1
+    move.w #$8001, $2000
+    lsr $2000
+    roxr.w $2000
+    move.w #$4000, $2002
+    asl.w $2002
+    ",
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+            0x00000002
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Long),
+            0x00000001
+        );
+        let mem = interpreter.get_memory();
+        assert_eq!(mem.read_word(0x2000).unwrap(), 0xA000);
+        assert_eq!(mem.read_word(0x2002).unwrap(), 0x8000);
+        assert!(interpreter.get_flag(Flags::Overflow));
+    }
+
+    #[test]
+    fn status_register_and_stack_swap() {
+        let interpreter = lex_and_run(
+            "
+    move.l #$3000, a0
+    move.l a0, usp
+    move sr, d0
+    ori #$0700, sr
+    move sr, d1
+    andi #$d8ff, sr
+    move.l sp, d2
+    move.w #$1234, -(sp)
+    ",
+        );
+        let cpu = interpreter.get_cpu();
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Word),
+            0x2000
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Word),
+            0x2700
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Long),
+            0x3000
+        );
+        assert_eq!(interpreter.get_sr(), 0x0000);
+        assert_eq!(cpu.get_usp(), 0x2FFE);
+        assert_eq!(cpu.get_ssp(), 0x01000000);
+        assert_eq!(interpreter.get_memory().read_word(0x2FFE).unwrap(), 0x1234);
+        assert_eq!(
+            Flags::from_ccr(0b10101).get_status(&SystemFlags::from_bits_truncate(0x2700)),
+            "T:0 S:1 I:7 X:1 N:0 Z:1 V:0 C:1"
+        );
+
+        let s68k = S68k::new("\n    andi #$dfff, sr\n    move #$2000, sr\n".to_string());
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.step().unwrap();
+        assert!(matches!(
+            interpreter.step(),
+            Err(RuntimeError::PrivilegeViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn condition_code_register() {
+        let interpreter = lex_and_run(
+            "
+    move #$1f, ccr
+    move ccr, d0
+    andi #$fe, ccr
+    move ccr, d1
+    eori #$04, ccr
+    move ccr, d2
+    bsr save_flags
+    move ccr, d3
+    bra end
+save_flags:
+    move ccr, -(sp)
+    move #0, ccr
+    rtr
+end:
+    ",
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Word),
+            0x1f
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Word),
+            0x1e
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Word),
+            0x1a
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(3), Size::Word),
+            0x1a
+        );
+
+        let s68k = S68k::new("\n    move #$1f, ccr\n".to_string());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let options = InterpreterOptions {
+            keep_history: true,
+            ..Default::default()
+        };
+        let mut interpreter = s68k.create_interpreter(compiled, Some(options));
+        interpreter.step().unwrap();
+        assert!(interpreter.get_flag(Flags::Extend));
+        interpreter.undo().unwrap();
+        assert!(!interpreter.get_flag(Flags::Extend));
+    }
+
+    #[test]
+    fn exception_processing() {
+        let options = InterpreterOptions {
+            exception_processing: true,
+            ..Default::default()
+        };
+        let s68k = S68k::new(
+            "
+    move.l #address_handler, $0C
+    move.l #division_handler, $14
+    move.l #privilege_handler, $20
+    move.l #10, d0
+    move.l #0, d1
+    divu d1, d0
+    move.l #$2001, a0
+    move.w (a0), d2
+    andi #$dfff, sr
+    move #$2000, sr
+    move.l #1, d7
+    bra end
+address_handler:
+    move.l 2(sp), d5
+    add.l #8, sp
+    add.l #2, 2(sp)
+    rte
+division_handler:
+    move.l #1, d4
+    rte
+privilege_handler:
+    move.l 2(sp), d6
+    add.l #4, 2(sp)
+    rte
+end:
+    "
+            .to_string(),
+        );
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, Some(options));
+        assert_eq!(interpreter.run().unwrap(), InterpreterStatus::Terminated);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(4), Size::Long),
+            1
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(5), Size::Long),
+            0x2001
+        );
+        //the stacked pc of a privilege violation is the one of the faulting instruction
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(6), Size::Long),
+            0x1032
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(7), Size::Long),
+            1
+        );
+        assert!(!interpreter.get_cpu().is_supervisor());
+    }
+
+    #[test]
+    fn user_trap_handlers() {
+        let interpreter = lex_and_run(
+            "
+    move.l #trap_3, $8C
+    move.l #trap_15, $BC
+    move.l #3, d0
+    trap #3
+    move.l #15, d0
+    trap #15
+    bra end
+trap_3:
+    move.l d0, d1
+    rte
+trap_15:
+    move.l d0, d2
+    rte
+end:
+    ",
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Long),
+            3
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Long),
+            15
+        );
+        assert_eq!(interpreter.get_sp(), 0x01000000);
+    }
+
+    #[test]
+    fn chk_trapv_and_illegal() {
+        let run = |code: &str| {
+            let s68k = S68k::new(code.to_string());
+            assert!(s68k.semantic_check().is_empty());
+            let compiled = s68k.compile().expect("To compile correctly");
+            let mut interpreter = s68k.create_interpreter(compiled, None);
+            interpreter.run()
+        };
+        let result =
+            run("\n    move.w #5, d0\n    chk #10, d0\n    move.w #-3, d0\n    chk #10, d0\n");
+        assert!(matches!(
+            result,
+            Err(RuntimeError::ChkOutOfBounds {
+                bound: 10,
+                value: -3,
+                address: 0x100C
+            })
+        ));
+        let result = run("\n    move.b #$7f, d0\n    add.b #1, d0\n    trapv\n");
+        assert!(matches!(
+            result,
+            Err(RuntimeError::TrapOnOverflow { address: 0x1008 })
+        ));
+        let result = run("\n    nop\n    illegal\n");
+        assert!(matches!(
+            result,
+            Err(RuntimeError::IllegalInstruction { address: 0x1002 })
+        ));
+    }
+
+    #[test]
+    fn tas_and_movep() {
+        let interpreter = lex_and_run(
+            "
+    move.b #0, $2000
+    tas $2000
+    move ccr, d0
+    tas $2000
+    move ccr, d1
+    move.l #$2100, a0
+    move.l #$11223344, d2
+    movep.l d2, 1(a0)
+    movep.w 3(a0), d3
+    ",
+        );
+        let mem = interpreter.get_memory();
+        assert_eq!(mem.read_byte(0x2000).unwrap(), 0x80);
+        //Z set by the first tas, N by the second
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Byte),
+            0x04
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Byte),
+            0x08
+        );
+        let bytes = [0x2101, 0x2103, 0x2105, 0x2107].map(|a| mem.read_byte(a).unwrap());
+        assert_eq!(bytes, [0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(3), Size::Word),
+            0x2233
+        );
+    }
+
+    #[test]
+    fn stop_until_interrupt_and_reset() {
+        let s68k = S68k::new(
+            "
+    move.l #handler, $70
+    reset
+    stop #$2300
+    move.l #1, d1
+    bra done
+handler:
+    move.l #7, d0
+    rte
+done:
+    nop
+    "
+            .to_string(),
+        );
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        assert_eq!(interpreter.run().unwrap(), InterpreterStatus::Stopped);
+        assert_eq!(interpreter.get_reset_signals(), 1);
+        assert_eq!(interpreter.get_sr(), 0x2300);
+        assert!(interpreter.step().is_err());
+        //level 2 is masked by the stop, level 4 vectors through autovector 28
+        assert!(!interpreter.raise_interrupt(2).unwrap());
+        assert!(interpreter.raise_interrupt(4).unwrap());
+        assert_eq!(interpreter.get_sr(), 0x2400);
+        assert_eq!(*interpreter.get_status(), InterpreterStatus::Running);
+        assert_eq!(interpreter.run().unwrap(), InterpreterStatus::Terminated);
+        assert_eq!(interpreter.get_sr(), 0x2300);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+            7
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Long),
+            1
+        );
+
+        //the interrupt is undone on its own, without the stop before it
+        let options = InterpreterOptions {
+            keep_history: true,
+            ..Default::default()
+        };
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, Some(options));
+        assert_eq!(interpreter.run().unwrap(), InterpreterStatus::Stopped);
+        let stopped_pc = interpreter.get_pc();
+        assert!(interpreter.raise_interrupt(4).unwrap());
+        assert_eq!(*interpreter.get_status(), InterpreterStatus::Running);
+        interpreter.undo().unwrap();
+        assert_eq!(interpreter.get_pc(), stopped_pc);
+        assert_eq!(interpreter.get_sr(), 0x2300);
+        //the cpu is stopped again, waiting for the interrupt
+        assert_eq!(*interpreter.get_status(), InterpreterStatus::Stopped);
+        assert!(interpreter.step().is_err());
+    }
+
+    #[test]
+    fn pc_relative_addressing() {
+        let interpreter = lex_and_run(
+            "
+    lea table(pc), a0
+    move.w table+2(pc), d0
+    moveq #4, d1
+    jmp jumps(pc,d1.w)
+jumps:
+    bra.w first
+    bra.w second
+first:
+    move.l #1, d2
+    bra done
+second:
+    move.l #2, d2
+done:
+    move.w 2(pc), d3
+table:
+    dc.w $1234, $5678
+    ",
+        );
+        let table = interpreter.get_register_value(RegisterOperand::Address(0), Size::Long);
+        assert_eq!(
+            interpreter.get_memory().read_word(table as usize).unwrap(),
+            0x1234
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Word),
+            0x5678
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Long),
+            2
+        );
+        //a plain number is the displacement from the extension word
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(3), Size::Word),
+            0x1234
+        );
+        for code in ["move.w d0, table(pc)", "clr.w 0(pc,d0.w)", "lsl table(pc)"] {
+            let s68k = S68k::new(format!("\n    {}\ntable:\n    dc.w 1\n", code));
+            assert!(!s68k.semantic_check().is_empty(), "{}", code);
+        }
+        //the plain displacements must fit in the extension word, signed or unsigned
+        let compile = |code: &str| S68k::new(format!("    {}\n", code)).compile();
+        assert!(compile("move.w $FFFE(pc), d0").is_ok());
+        assert!(compile("move.w -32768(pc), d0").is_ok());
+        assert!(compile("move.w $12345(pc), d0").is_err());
+        assert!(compile("move.w -32769(pc), d0").is_err());
+    }
+
+    #[test]
+    fn branch_size_and_displacement() {
+        let s68k = S68k::new(
+            "
+    bra.s near
+    bra.w near
+    bne.s far
+    beq.l near
+    move.s d0, d1
+near:
+    nop
+    ds.b 200
+far:
+    nop
+    "
+            .to_string(),
+        );
+        let errors = s68k.semantic_check();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0]
+            .get_message()
+            .contains("out of range for .s (-128..127)"));
+        let warnings = s68k.semantic_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].is_warning());
+        assert_eq!(warnings[0].get_line_index(), 2);
+        let interpreter =
+            lex_and_run("\n    moveq #1, d0\n    bra.s skip\n    moveq #2, d0\nskip:\n");
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+            1
+        );
+
+        //the branch to the next instruction keeps the word displacement it was given, without padding
+        let s68k = S68k::new("    bra next\nnext:\n    nop\n".to_string());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let instructions = compiled.get_instructions();
+        assert_eq!(instructions[0].words, [0x6000, 0x0002]);
+        assert_eq!(instructions[1].address, 0x1004);
+        //and the short form can't have a displacement of 0
+        let s68k = S68k::new("    bra.s next\nnext:\n    nop\n".to_string());
+        let errors = s68k.semantic_check();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].get_message().contains("can't use the short form"));
+        assert!(s68k.compile().is_err());
+    }
+
+    #[test]
+    fn absolute_short_and_long() {
+        let code = "
+    move.w #$1234, $2000.w
+    move.w $2000.l, d0
+    move.w #$55, $8000.w
+    move.w $FF8000, d1
+    lea $8000.w, a0
+    ";
+        let s68k = S68k::new(code.to_string());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let instructions = compiled.get_instructions();
+        assert!(matches!(
+            instructions[0].instruction,
+            Instruction::MOVE(_, Operand::AbsoluteShort(0x2000), _)
+        ));
+        assert!(matches!(
+            instructions[1].instruction,
+            Instruction::MOVE(Operand::AbsoluteLong(0x2000), _, _)
+        ));
+        let interpreter = lex_and_run(code);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Word),
+            0x1234
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Word),
+            0x55
+        );
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Address(0), Size::Long),
+            0xFFFF8000
+        );
+        let s68k = S68k::new("\n    move.w d0, $12345.w\n".to_string());
+        assert_eq!(s68k.semantic_check().len(), 1);
+    }
+
+    #[test]
+    fn m68020_integer_extensions() {
+        let code = "
+    move.l #$80, d0
+    extb.l d0
+    move.l #$12345678, d1
+    move.l d1, d2
+    mulu.l #$10, d2:d1
+    move.l #-3, d3
+    muls.l #100000, d3
+    move.l #-7, d4
+    divs.l #2, d4
+    move.l #0, d5
+    move.l #$10, d6
+    divu.l #$100, d5:d6
+    move.l #100, d7
+    divul.l #7, d5:d7
+    ";
+        let mut s68k = S68k::new(code.to_string());
+        assert_eq!(s68k.semantic_check().len(), 6);
+        s68k.set_cpu_model(CpuModel::M68020);
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.run().unwrap();
+        let reg = |i| interpreter.get_register_value(RegisterOperand::Data(i), Size::Long);
+        assert_eq!(reg(0), 0xFFFFFF80);
+        assert_eq!((reg(2), reg(1)), (0x1, 0x23456780));
+        assert_eq!(reg(3), (-300000i32) as u32);
+        assert_eq!(reg(4), (-3i32) as u32);
+        assert_eq!(reg(6), 0);
+        assert_eq!((reg(7), reg(5)), (14, 2));
+    }
+
+    #[test]
+    fn bit_field_instructions() {
+        let code = "
+    move.l #$12345678, d0
+    bfextu d0{4:8}, d1
+    bfexts d0{28:8}, d2
+    move.l #$F0, d3
+    bfins d3, d0{0:4}
+    lea data, a0
+    bfset (a0){7:32}
+    bfffo (a0){0:16}, d4
+    move.l #-4, d5
+    bfextu 1(a0){d5:8}, d6
+    bfclr (a0){8:16}
+    bfchg d7{0:32}
+    bra end
+data: dc.b 0, 0, 0, 0, 0, 0
+end:
+    ";
+        let mut s68k = S68k::new(code.to_string());
+        assert_eq!(s68k.semantic_check().len(), 8);
+        s68k.set_cpu_model(CpuModel::M68020);
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let data = compiled.get_labels_map().get("data").unwrap().address;
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.run().unwrap();
+        let reg = |i| interpreter.get_register_value(RegisterOperand::Data(i), Size::Long);
+        assert_eq!(reg(1), 0x23);
+        assert_eq!(reg(2), 0xFFFFFF81);
+        assert_eq!(reg(0), 0x02345678);
+        assert_eq!(reg(4), 7);
+        assert_eq!(reg(6), 0x1F);
+        assert_eq!(reg(7), 0xFFFFFFFF);
+        let memory = interpreter.get_memory();
+        let bytes = (0..6)
+            .map(|i| memory.read_byte(data + i).unwrap())
+            .collect::<Vec<u8>>();
+        assert_eq!(bytes, vec![0x01, 0x00, 0x00, 0xFF, 0xFE, 0x00]);
+
+        let mut s68k =
+            S68k::new("\n    bfextu d0{0:33}, d1\n    bfins d1, (a0)+{0:8}\n".to_string());
+        s68k.set_cpu_model(CpuModel::M68020);
+        assert_eq!(s68k.semantic_check().len(), 2);
+    }
+
+    #[test]
+    fn scaled_index_and_memory_indirect() {
+        let code = "
+    lea table, a0
+    lea ptr, a1
+    move.l a0, (a1)
+    move.l #2, d1
+    move.l (a0, d1.w*4), d2
+    move.l ([a1], d1.w*4, 4), d3
+    move.l ([-8, a1, d1*4]), d4
+    move.l (4, a0.l), d5
+    move.l ([ptr, pc]), d6
+    move.l #$55, ([a1], 12)
+    bra end
+table: dc.l $11111111, $22222222, $33333333, $44444444
+ptr: dc.l 0
+end:
+    ";
+        let mut s68k = S68k::new(code.to_string());
+        assert_eq!(s68k.semantic_check().len(), 6);
+        s68k.set_cpu_model(CpuModel::M68020);
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let table = compiled.get_labels_map().get("table").unwrap().address;
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.run().unwrap();
+        let reg = |i| interpreter.get_register_value(RegisterOperand::Data(i), Size::Long);
+        assert_eq!(reg(2), 0x33333333);
+        assert_eq!(reg(3), 0x44444444);
+        assert_eq!(reg(4), 0x11111111);
+        assert_eq!(reg(5), 0x22222222);
+        assert_eq!(reg(6), 0x11111111);
+        assert_eq!(
+            interpreter.get_memory().read_long(table + 12).unwrap(),
+            0x55
+        );
+
+        //the 68000 interpreter does not run the modes of the full extension word
+        for code in ["move.l (a0, d1.w*4), d2", "move.l ([a1], 4), d3"] {
+            let mut s68k = S68k::new(format!("\n    {}\n", code));
+            s68k.set_cpu_model(CpuModel::M68020);
+            let compiled = s68k.compile().expect("To compile correctly");
+            let mut interpreter = s68k.create_interpreter(compiled, Some(Default::default()));
+            assert!(
+                matches!(
+                    interpreter.run(),
+                    Err(RuntimeError::IllegalInstruction { address: 0x1000 })
+                ),
+                "{}",
+                code
+            );
+        }
+
+        let s68k = S68k::new("\n    move.l 200(a0, d0.w), d1\n".to_string());
+        assert_eq!(s68k.semantic_check().len(), 1);
+        let mut s68k = S68k::new("\n    move.l 100(a0, d0.w*3), d1\n".to_string());
+        s68k.set_cpu_model(CpuModel::M68020);
+        assert_eq!(s68k.semantic_check().len(), 1);
+    }
+
+    #[test]
+    fn floating_point_coprocessor() {
+        let code = "
+    fmove.l #3, fp0
+    fmove.d #1.5, fp1
+    fadd.x fp1, fp0
+    fmul.s #2.0, fp0
+    fsqrt fp0, fp2
+    fmove.s val, fp3
+    fdiv fp3, fp2
+    fmove.l fp2, d0
+    fmove.d fp0, res
+    fcmp fp0, fp2
+    fbgt greater
+    move.l #1, d1
+greater:
+    fmove.s #$3F800000, fp4
+    fsub fp4, fp4
+    fdiv fp4, fp3
+    fmove.l fpsr, d2
+    fmovecr #0, fp5
+    bra end
+val: dc.s 0.25
+res: dc.d 0
+end:
+    ";
+        let mut s68k = S68k::new(code.to_string());
+        assert_eq!(s68k.semantic_check().len(), 16);
+        s68k.set_fpu_model(FpuModel::M68881);
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let res = compiled.get_labels_map().get("res").unwrap().address;
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.run().unwrap();
+        let cpu = interpreter.get_cpu();
+        assert_eq!(cpu.get_fp_register(0), 9.0);
+        assert_eq!(cpu.get_fp_register(2), 12.0);
+        assert!(cpu.get_fp_register(3).is_infinite());
+        assert_eq!(cpu.get_fp_register(5), std::f64::consts::PI);
+        let reg = |i| interpreter.get_register_value(RegisterOperand::Data(i), Size::Long);
+        assert_eq!(reg(0), 12);
+        assert_eq!(reg(1), 0);
+        //infinity condition code, divide by zero exception and its accrued bit
+        assert_eq!(reg(2), (1 << 25) | (1 << 10) | (1 << 4));
+        let bytes = interpreter.get_memory().read_bytes(res, 8).unwrap();
+        assert_eq!(bytes, 9.0f64.to_be_bytes());
+
+        let mut s68k =
+            S68k::new("\n    fmove.d d0, fp0\n    fadd a0, fp0\n    fmove.x fp0, #1\n".to_string());
+        s68k.set_fpu_model(FpuModel::M68882);
+        assert_eq!(s68k.semantic_check().len(), 3);
+
+        //the integer immediates must fit their format and the constant rom is only extended
+        let mut s68k = S68k::new(
+            "
+    fmove.b #300, fp0
+    fmove.w #-40000, fp0
+    fmove.l #$FFFFFFFF, fp0
+    fmove.b #-128, fp0
+    fmovecr.d #0, fp0
+    fmovecr.l #0, fp0
+    fmovecr.x #0, fp0
+    fmovecr #0, fp0
+"
+            .to_string(),
+        );
+        s68k.set_fpu_model(FpuModel::M68881);
+        let errors = s68k.semantic_check();
+        let lines: Vec<usize> = errors.iter().map(|e| e.get_line_index()).collect();
+        assert_eq!(lines, vec![1, 2, 5, 6]);
+
+        let mut s68k = S68k::new("\n    fmove.w #-2, fp0\n".to_string());
+        s68k.set_fpu_model(FpuModel::M68881);
+        let compiled = s68k.compile().expect("To compile correctly");
+        let options = InterpreterOptions {
+            keep_history: true,
+            fpu_model: FpuModel::M68881,
+            ..Default::default()
+        };
+        let mut interpreter = s68k.create_interpreter(compiled, Some(options));
+        interpreter.step().unwrap();
+        assert_eq!(interpreter.get_cpu().get_fp_register(0), -2.0);
+        interpreter.undo().unwrap();
+        assert!(interpreter.get_cpu().get_fp_register(0).is_nan());
+
+        //without a coprocessor the fpu instructions are illegal when they run
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, Some(InterpreterOptions::new()));
+        assert!(matches!(
+            interpreter.step(),
+            Err(RuntimeError::IllegalInstruction { address: 0x1000 })
+        ));
+    }
+
+    #[test]
+    fn m68010_control_registers() {
+        let code = "
+    lea table, a0
+    movec a0, vbr
+    move.l #handler, $80(a0)
+    movec vbr, d0
+    moveq #13, d1
+    movec d1, sfc
+    movec sfc, d2
+    moves.l d0, result
+    moves.w result+2, a1
+    move.l #7, -(sp)
+    bsr sub
+    trap #0
+    bra end
+sub:
+    move.l 4(sp), d4
+    rtd #4
+handler:
+    move.l #1, d6
+    rte
+table: ds.l 64
+result: dc.l 0
+end:
+    ";
+        let mut s68k = S68k::new(code.to_string());
+        assert_eq!(s68k.semantic_check().len(), 7);
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::IllegalInstruction { .. })
+        ));
+
+        s68k.set_cpu_model(CpuModel::M68010);
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let table = compiled.get_labels_map().get("table").unwrap().address as u32;
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.run().unwrap();
+        assert_eq!(interpreter.get_cpu().get_vbr(), table);
+        let reg = |reg| interpreter.get_register_value(reg, Size::Long);
+        assert_eq!(reg(RegisterOperand::Data(0)), table);
+        //only the 3 bits of the function code are kept
+        assert_eq!(reg(RegisterOperand::Data(2)), 5);
+        assert_eq!(reg(RegisterOperand::Address(1)), table & 0xFFFF);
+        assert_eq!(reg(RegisterOperand::Data(4)), 7);
+        assert_eq!(reg(RegisterOperand::Data(6)), 1);
+        assert_eq!(reg(RegisterOperand::Address(7)), 0x01000000);
+
+        //move from sr is privileged from the 68010
+        let mut s68k = S68k::new("\n    andi #$dfff, sr\n    move sr, d0\n".to_string());
+        s68k.set_cpu_model(CpuModel::M68010);
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        assert!(matches!(
+            interpreter.run(),
+            Err(RuntimeError::PrivilegeViolation { .. })
+        ));
+    }
+
+    #[test]
+    fn machine_code_encoding() {
+        let compiled = lex_only(
+            "
+start:
+    move.l d0, d1
+    moveq #1, d0
+    move.w #$1234, (a0)+
+    lea 8(a1), a2
+    add.l d1, -(a3)
+    addq.w #8, d2
+    bne start
+    bra.w start
+    dbra d0, start
+    jsr (a0)
+    nop
+    rts
+    ",
+        );
+        let expected: [&[u16]; 12] = [
+            &[0x2200],
+            &[0x7001],
+            &[0x30FC, 0x1234],
+            &[0x45E9, 0x0008],
+            &[0xD3A3],
+            &[0x5042],
+            &[0x66EE],
+            &[0x6000, 0xFFEC],
+            &[0x51C8, 0xFFE8],
+            &[0x4E90],
+            &[0x4E71],
+            &[0x4E75],
+        ];
+        let instructions = compiled.get_instructions();
+        for (ins, words) in instructions.iter().zip(expected) {
+            assert_eq!(ins.words, words, "{:?}", ins.instruction);
+        }
+        assert_eq!(instructions.last().unwrap().address, 0x101E);
+        let segments = compiled.get_memory_segments();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].address, 0x1000);
+        assert_eq!(segments[0].data.len(), 0x20);
+
+        //the branch over the data needs the word form, which moves the target of the first one
+        let compiled = lex_only(
+            "
+    bra near
+    bra far
+near:
+    ds.b 200
+far:
+    nop
+    ",
+        );
+        let instructions = compiled.get_instructions();
+        assert_eq!(instructions[0].words, [0x6004]);
+        assert_eq!(instructions[1].words, [0x6000, 0x00CA]);
+        assert_eq!(
+            compiled.get_labels_map().get("far").unwrap().address,
+            0x10CE
+        );
+        //the program is loaded in memory
+        let interpreter = lex_and_run(
+            "
+    move.l d0, d1
+    rts
+",
+        );
+        assert_eq!(
+            interpreter.get_memory().read_long(0x1000).unwrap(),
+            0x22004E75
+        );
+    }
+
+    #[test]
+    fn disassemble_machine_code() {
+        let code = "
+start:
+    move.l d0, d1
+    movea.w (a0)+, a1
+    moveq #-1, d2
+    movem.l d0-d3/a6, -(sp)
+    movem.w (sp)+, d0/a0-a1
+    addi.b #$12, (a2)
+    subq.l #8, 4(a3)
+    cmp.w 2(a0, d1.w*2), d3
+    eor.l d4, $1234.w
+    lsl.w #8, d5
+    roxr.l d1, d2
+    asr.w (a0)
+    bchg #3, d6
+    bfextu (a1){2:32}, d0
+    divsl.l d1, d2:d3
+    mulu.l #$10, d2:d1
+    move.l ([a1], d1.w*4, 4), d3
+    move.l start(pc), d4
+    link a6, #-8
+    dbra d0, start
+    bne start
+    fadd.d #1.5, fp0
+    fmove.l fp2, d0
+    fmove.l fpsr, d2
+    fbgt start
+    extb.l d1
+    movec vbr, d0
+    stop #$2700
+";
+        let mut s68k = S68k::new(code.to_string());
+        s68k.set_cpu_model(CpuModel::M68020);
+        s68k.set_fpu_model(FpuModel::M68881);
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        //every instruction decodes back to one that encodes to the same words
+        for line in compiled.get_instructions() {
+            let (instruction, words) = decode_instruction(&line.get_bytes(), line.address)
+                .unwrap_or_else(|e| panic!("{:?} at {:?}", e, line.instruction));
+            assert_eq!(words, line.words, "{:?}", line.instruction);
+            let size = match &line.parsed_line.parsed {
+                LexedLine::Instruction { size, .. } => size,
+                _ => panic!("Not an instruction"),
+            };
+            let encoded = encode_instruction(&instruction, line.address, size).unwrap();
+            assert_eq!(encoded, line.words, "{:?}", instruction);
+        }
+        let segment = &compiled.get_memory_segments()[0];
+        let lines = disassemble(&segment.data, segment.address);
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts.len(), compiled.get_instructions().len());
+        assert_eq!(texts[0], "move.l d0, d1");
+        assert_eq!(texts[2], "moveq #-1, d2");
+        assert_eq!(texts[3], "movem.l d0-d3/a6, -(a7)");
+        assert_eq!(texts[4], "movem.w (a7)+, d0/a0-a1");
+        assert_eq!(texts[6], "subq.l #8, 4(a3)");
+        assert_eq!(texts[7], "cmp.w 2(a0,d1.w*2), d3");
+        assert_eq!(texts[8], "eor.l d4, $1234.w");
+        assert_eq!(texts[11], "asr.w (a0)");
+        assert_eq!(texts[13], "bfextu (a1){2:32}, d0");
+        assert_eq!(texts[14], "divsl.l d1, d2:d3");
+        assert_eq!(texts[16], "move.l ([0,a1],d1.w*4,4), d3");
+        assert_eq!(texts[19], "dbra d0, $1000");
+        assert_eq!(texts[21], "fadd.d #1.5, fp0");
+        assert_eq!(texts[27], "stop #$2700");
+
+        //the memory holds what the cpu sees, including the opcodes written as data
+        let interpreter = lex_and_run(
+            "
+    bra code
+table: dc.w $4E71, $7001, $FFFF
+code:
+    rts
+",
+        );
+        let lines = interpreter.disassemble(0x1002, 6).unwrap();
+        assert_eq!(lines[0].text, "nop");
+        assert_eq!(lines[1].text, "moveq #1, d0");
+        assert!(lines[1].instruction.is_some());
+        assert_eq!(lines[2].text, "dc.w $FFFF");
+        assert!(lines[2].instruction.is_none());
+        assert!(interpreter.disassemble(0x1002, 0x1000000).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_invalid_addressing_modes() {
+        let decode = |words: &[u16]| {
+            let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+            decode_instruction(&bytes, 0x1000)
+        };
+        let illegal = [
+            //lea d0, a0
+            vec![0x41C0],
+            //jmp d0
+            vec![0x4EC0],
+            //clr.l a0
+            vec![0x4288],
+            //bset #4, #8
+            vec![0x08FC, 0x0004, 0x0008],
+            //btst with a bit number over a byte
+            vec![0x0800, 0x0104],
+            //asl.w a0, the memory shift
+            vec![0xE1C8],
+            //movem.w d0, (a0)+ and movem.w -(a0), d0
+            vec![0x4898, 0x0001],
+            vec![0x4CA0, 0x0001],
+            //move.b a0, d0
+            vec![0x1008],
+            //moves.l and mulu.l with reserved extension bits
+            vec![0x0E90, 0x0801],
+            vec![0x4C00, 0x1008],
+            //fmovecr with an effective address
+            vec![0xF208, 0x5C00],
+            //fmove.d d0, fp0
+            vec![0xF200, 0x5400],
+        ];
+        for words in illegal {
+            assert!(
+                matches!(decode(&words), Err(DecodeError::IllegalInstruction(_))),
+                "{:04X?}",
+                words
+            );
+        }
+        //the same instructions with the modes they allow
+        let valid = [
+            vec![0x41D0],
+            vec![0x4ED0],
+            vec![0x4280],
+            vec![0x08D0, 0x0004],
+            vec![0xE1D0],
+            vec![0x48A0, 0x0001],
+            vec![0x4C98, 0x0001],
+            vec![0x4C00, 0x1000],
+            vec![0xF200, 0x5C00],
+        ];
+        for words in valid {
+            assert!(decode(&words).is_ok(), "{:04X?}", words);
+        }
+    }
+
+    #[test]
+    fn fetch_decode_execution() {
+        let run = |code: &str, execution_mode: ExecutionMode| {
+            let s68k = S68k::new(code.to_string());
+            assert!(s68k.semantic_check().is_empty());
+            let compiled = s68k.compile().expect("To compile correctly");
+            let options = InterpreterOptions {
+                execution_mode,
+                ..Default::default()
+            };
+            let mut interpreter = s68k.create_interpreter(compiled, Some(options));
+            let result = interpreter.run_with_limit(1000);
+            (interpreter, result)
+        };
+        let code = "
+    bra start
+table: dc.w $7605, $4E75
+routine:
+    moveq #5, d1
+    rts
+start:
+    move.l routine, $2000
+    jsr $2000
+    jsr table
+    moveq #1, d4
+patch:
+    moveq #1, d2
+    move.w #$7409, patch
+    dbra d4, patch
+";
+        //the copied routine, the code in the data and the patched instruction run from memory
+        let (interpreter, result) = run(code, ExecutionMode::FetchDecode);
+        assert_eq!(result.unwrap(), InterpreterStatus::Terminated);
+        let reg = |i| interpreter.get_register_value(RegisterOperand::Data(i), Size::Long);
+        assert_eq!(reg(1), 5);
+        assert_eq!(reg(2), 9);
+        assert_eq!(reg(3), 5);
+
+        //jumping right after the last instruction ends the program in both modes
+        for execution_mode in [ExecutionMode::Precompiled, ExecutionMode::FetchDecode] {
+            let (interpreter, result) = run(
+                "
+    moveq #1, d0
+    bra end
+    moveq #2, d0
+end:
+",
+                execution_mode,
+            );
+            assert_eq!(result.unwrap(), InterpreterStatus::Terminated);
+            assert_eq!(
+                interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+                1
+            );
+        }
+
+        //the stack writes of pea replace the decoded routine like any other write
+        let (interpreter, result) = run(
+            "
+    bra start
+routine:
+    moveq #5, d1
+    rts
+start:
+    move.l routine, $2000
+    jsr $2000
+    move.l sp, a1
+    lea $2002, sp
+    pea $7209
+    jsr $2000
+    move.l a1, sp
+",
+            ExecutionMode::FetchDecode,
+        );
+        assert_eq!(result.unwrap(), InterpreterStatus::Terminated);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(1), Size::Long),
+            9
+        );
+
+        //so do the return addresses of bsr and jsr, the routine runs them as "ori.b #$xx, d0"
+        for call in ["bsr", "jsr"] {
+            let code = format!(
+                "
+    bra start
+routine:
+    moveq #7, d2
+    nop
+    rts
+call:
+    jmp $2000
+start:
+    move.l routine, $2000
+    move.w routine+4, $2004
+    jsr $2000
+    moveq #0, d2
+    move.l sp, a1
+    lea $2004, sp
+    {} call
+    move.l a1, sp
+",
+                call
+            );
+            let (interpreter, result) = run(&code, ExecutionMode::FetchDecode);
+            assert_eq!(result.unwrap(), InterpreterStatus::Terminated);
+            let reg = |i| interpreter.get_register_value(RegisterOperand::Data(i), Size::Long);
+            assert_eq!(reg(2), 0, "{}", call);
+            assert_ne!(reg(0), 0, "{}", call);
+        }
+
+        //the precompiled mode keeps running the instructions of the program
+        let code = "
+    moveq #1, d4
+patch:
+    moveq #1, d2
+    move.w #$7409, patch
+    dbra d4, patch
+";
+        let (interpreter, _) = run(code, ExecutionMode::Precompiled);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Long),
+            1
+        );
+        let (interpreter, _) = run(code, ExecutionMode::FetchDecode);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(2), Size::Long),
+            9
+        );
+
+        //the words that are not an instruction are illegal
+        let (_, result) = run(
+            "
+    bra code
+data: dc.w $A000
+code:
+    jmp data
+",
+            ExecutionMode::FetchDecode,
+        );
+        assert!(matches!(
+            result,
+            Err(RuntimeError::IllegalInstruction { address: 0x1002 })
+        ));
+    }
+
+    #[test]
+    fn srecord_export_and_import() {
+        let s68k = S68k::new(
+            "
+    org $1000
+value: dc.l 7
+    org $3000
+START:
+    move.l value, d0
+    add.l #3, d0
+"
+            .to_string(),
+        );
+        assert!(s68k.semantic_check().is_empty());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let text = compiled.to_srecords(SRecordFormat::S28).unwrap();
+        let records: Vec<&str> = text.lines().collect();
+        assert_eq!(records[0], "S00700007336386BAC");
+        assert_eq!(records[1], "S20800100000000007E0");
+        assert_eq!(records[3], "S5030002FA");
+        //START is the entry point
+        assert_eq!(records[4], "S804003000CB");
+        assert!(compiled.to_srecords(SRecordFormat::S19).is_ok());
+
+        let mut interpreter = Interpreter::from_srecords(&text, None).unwrap();
+        assert_eq!(interpreter.get_pc(), 0x3000);
+        let status = interpreter.run_with_limit(1000).unwrap();
+        assert_eq!(status, InterpreterStatus::Terminated);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+            10
+        );
+
+        //the program ends at the end of its code, before the gap and the data after it
+        let s68k = S68k::new(
+            "
+    org $1000
+START:
+    move.l value, d0
+    add.l #3, d0
+    org $3000
+value: dc.l 7
+"
+            .to_string(),
+        );
+        let compiled = s68k.compile().expect("To compile correctly");
+        let text = compiled.to_srecords(SRecordFormat::S28).unwrap();
+        let mut interpreter = Interpreter::from_srecords(&text, None).unwrap();
+        let status = interpreter.run_with_limit(1000).unwrap();
+        assert_eq!(status, InterpreterStatus::Terminated);
+        assert_eq!(interpreter.get_pc(), 0x100A);
+        assert_eq!(
+            interpreter.get_register_value(RegisterOperand::Data(0), Size::Long),
+            10
+        );
+
+        let image =
+            parse_srecords("S1130000285F245F2212226A000424290008237C2A\nS9030000FC").unwrap();
+        assert_eq!(image.segments[0].address, 0);
+        assert_eq!(image.segments[0].data.len(), 16);
+        assert_eq!(image.entry, Some(0));
+        assert!(matches!(
+            parse_srecords("S1130000285F245F2212226A000424290008237C2B"),
+            Err(RecordError::ChecksumMismatch {
+                line: 0,
+                expected: 0x2A,
+                found: 0x2B
+            })
+        ));
+        let far = S68k::new("    org $10000\n    moveq #1, d0".to_string());
+        assert!(matches!(
+            far.compile().unwrap().to_srecords(SRecordFormat::S19),
+            Err(RecordError::AddressOutOfRange { address: 0x10000 })
+        ));
+    }
+
+    #[test]
+    fn memory_dump_and_load() {
+        let code = "
+    lea $2000, a0
+    moveq #0, d0
+    moveq #7, d1
+loop:
+    add.b (a0)+, d0
+    dbra d1, loop
+    move.l d0, $3000
+";
+        let s68k = S68k::new(code.to_string());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        //the rest of the table is set to the fill byte instead of the empty memory
+        let table = MemoryRegion {
+            address: 0x2000,
+            length: 8,
+            fill: 0,
+        };
+        interpreter.load_binary(&[1, 2, 3], &table).unwrap();
+        assert!(matches!(
+            interpreter.load_binary(&[0; 9], &table),
+            Err(RuntimeError::OutOfBounds(_))
+        ));
+        interpreter.run_with_limit(1000).unwrap();
+        let result = MemoryRegion {
+            address: 0x2FF0,
+            length: 0x30,
+            fill: 0xFF,
+        };
+        let memory = interpreter.get_memory();
+        assert_eq!(
+            memory.dump_binary(&table).unwrap(),
+            [1, 2, 3, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(memory.read_long(0x3000).unwrap(), 6);
+        //the lines with only the fill byte are left out
+        let hex = memory.dump_intel_hex(&result).unwrap();
+        assert_eq!(
+            hex,
+            ":1030000000000006FFFFFFFFFFFFFFFFFFFFFFFFC6\n:00000001FF\n"
+        );
+
+        let s68k = S68k::new(code.to_string());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let mut interpreter = s68k.create_interpreter(compiled, None);
+        interpreter.load_intel_hex(&hex, Some(&result)).unwrap();
+        assert_eq!(interpreter.get_memory().read_long(0x3000).unwrap(), 6);
+        assert!(matches!(
+            interpreter.load_intel_hex(":00000001FE", None),
+            Err(RecordError::ChecksumMismatch {
+                line: 0,
+                expected: 0xFF,
+                found: 0xFE
+            })
+        ));
+        //nothing is written when one of the records is outside of the region
+        let segments = [
+            MemorySegment {
+                address: 0x2000,
+                data: vec![0xAB; 4],
+            },
+            MemorySegment {
+                address: 0x3000,
+                data: vec![0xCD; 4],
+            },
+        ];
+        let before = interpreter.get_memory().read_long(0x2000).unwrap();
+        assert!(matches!(
+            interpreter.load_intel_hex(&write_intel_hex(&segments, None), Some(&table)),
+            Err(RecordError::AddressOutOfRange { address: 0x3000 })
+        ));
+        assert_eq!(interpreter.get_memory().read_long(0x2000).unwrap(), before);
+
+        //the records do not cross a 64kb boundary
+        let segments = [MemorySegment {
+            address: 0xFFF8,
+            data: vec![0xAB; 16],
+        }];
+        let hex = write_intel_hex(&segments, Some(0x1000));
+        let records: Vec<&str> = hex.lines().collect();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[1], ":020000040001F9");
+        assert_eq!(records[3], ":0400000500001000E7");
+        let image = parse_intel_hex(&hex).unwrap();
+        assert_eq!(image.segments.len(), 1);
+        assert_eq!(image.segments[0].address, 0xFFF8);
+        assert_eq!(image.segments[0].data, vec![0xAB; 16]);
+        assert_eq!(image.start, Some(0x1000));
+    }
+
+    #[test]
+    fn assembler_listing() {
+        let s68k = S68k::new(
+            "* sums the table
+    org $2000
+table: dc.b 1, 2, 3, 'hello world!', 0
+START:
+    lea table, a0
+loop: add.b (a0)+, d0
+    cmp.l #table+3, a0
+    bne loop
+"
+            .to_string(),
+        );
+        let compiled = s68k.compile().expect("To compile correctly");
+        let listing = s68k.create_listing(&compiled);
+        assert_eq!(listing.lines.len(), 8);
+        assert_eq!(listing.lines[0].address, None);
+        assert_eq!(listing.lines[2].address, Some(0x2000));
+        assert_eq!(listing.lines[2].bytes.len(), 16);
+        //the label and the instruction on the same line are shown together
+        assert_eq!(listing.lines[5].address, Some(0x2014));
+        assert_eq!(listing.lines[5].bytes, [0xD0, 0x18]);
+        let table = listing.symbols.iter().find(|s| s.name == "table").unwrap();
+        assert_eq!(table.line_index, 2);
+        assert_eq!(table.references, [4, 6]);
+
+        let text = listing.to_text();
+        let rows: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            rows[2],
+            "    1                              * sums the table"
+        );
+        assert_eq!(
+            rows[4],
+            "    3  00002000  0102 0368 656C 6C6F  table: dc.b 1, 2, 3, 'hello world!', 0"
+        );
+        assert_eq!(rows[5], "       00002008  2077 6F72 6C64 2100");
+        assert!(rows.contains(&" table                 00002000      3"));
+        assert!(rows.contains(&" table                       3  5, 7"));
+        assert!(rows.contains(&" loop                        6  8"));
+
+        //the reserved space is listed as zeros, as long as the space it takes
+        let s68k = S68k::new("buffer: ds.b 40\n    ds.w 3\n    moveq #1, d0".to_string());
+        let compiled = s68k.compile().expect("To compile correctly");
+        let listing = s68k.create_listing(&compiled);
+        assert_eq!(listing.lines[0].bytes, [0; 40]);
+        assert_eq!(listing.lines[1].address, Some(0x1028));
+        assert_eq!(listing.lines[1].bytes.len(), 6);
+        assert_eq!(listing.lines[2].address, Some(0x102E));
+
+        //the labels in an EQU are used by the EQU line, not by the lines that use the constant
+        let s68k = S68k::new(
+            "SIZE equ tend-table
+table: dc.w 1, 2
+tend:
+    move.w #SIZE, d0
+    lea table, a0
+    move.l #SIZE*2, d1
+"
+            .to_string(),
+        );
+        let compiled = s68k.compile().expect("To compile correctly");
+        let listing = s68k.create_listing(&compiled);
+        let references = |name: &str| {
+            let symbol = listing.symbols.iter().find(|s| s.name == name).unwrap();
+            symbol.references.clone()
+        };
+        assert_eq!(references("table"), [0, 4]);
+        assert_eq!(references("tend"), [0]);
+        //the constants are listed with the labels, like in the symbol map, with their case
+        assert_eq!(references("SIZE"), [3, 5]);
+        let size = listing.symbols.iter().find(|s| s.name == "SIZE").unwrap();
+        assert_eq!(size.kind, SymbolKind::Constant);
+        assert_eq!(size.value, Some(4));
+        assert!(listing
+            .to_text()
+            .contains(" SIZE                  00000004      1"));
+    }
+
+    #[test]
+    fn symbol_table_and_map() {
+        let compiled = lex_only(
+            "TEN equ #10
+counter equ d1
+    org $2000
+table: dc.w 1, 2
+table_end:
+START:
+    move.l TEN, counter
+    lea table, a0
+loop:
+    dbra counter, loop
+",
+        );
+        let symbols = compiled.get_symbols();
+        let find = |name: &str| symbols.iter().find(|s| s.name == name).unwrap();
+        //the constants keep their case, like the labels
+        assert_eq!(find("TEN").kind, SymbolKind::Constant);
+        assert_eq!(find("TEN").value, Some(10));
+        assert_eq!(find("TEN").line, 0);
+        //the EQU that is not a number has no value
+        assert_eq!(find("counter").value, None);
+        assert_eq!(find("counter").expression, Some("d1".to_string()));
+        assert_eq!(find("table").kind, SymbolKind::Data);
+        assert_eq!(find("table").value, Some(0x2000));
+        assert_eq!(find("START").kind, SymbolKind::Code);
+        assert_eq!(find("table_end").value, Some(0x2004));
+        //the end of the table is data even if the next instruction is at the same address
+        assert_eq!(find("table_end").kind, SymbolKind::Data);
+        assert_eq!(find("loop").line, 8);
+        assert_eq!(
+            write_symbol_map(&symbols),
+            "0000000A A TEN
+00002000 D table
+00002004 T START
+00002004 D table_end
+0000200E T loop
+"
+        );
+    }
+
     #[test]
     fn test_complex_code() {
         lex_and_run(
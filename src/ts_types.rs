@@ -69,12 +69,23 @@ export type RuntimeError = { type: "Raw", value: string } |
 { type: "OutOfBounds", value: string } |
 { type: "DivisionByZero" } |
 { type: "IncorrectAddressingMode", value: string } |
+{ type: "PrivilegeViolation", value: { address: number } } |
+{ type: "ChkOutOfBounds", value: { bound: number, value: number, address: number } } |
+{ type: "TrapOnOverflow", value: { address: number } } |
+{ type: "IllegalInstruction", value: { address: number } } |
 { type: "Unimplemented" } |
 { type: "AddressError", value : { address: number, size: Size } }
 
 
 "#;
 
+#[wasm_bindgen(typescript_custom_section)]
+pub const IRecordError: &'static str = r#"
+export type RecordError = { type: "InvalidRecord", value: { line: number, message: string } } |
+{ type: "ChecksumMismatch", value: { line: number, expected: number, found: number } } |
+{ type: "AddressOutOfRange", value: { address: number } }
+"#;
+
 #[wasm_bindgen(typescript_custom_section)]
 pub const IRegisterOperand: &'static str = r#"
 export type RegisterOperand = { type: "Address", value: number } |
@@ -87,6 +98,62 @@ export type InstructionLine = {
     instruction: any //TODO add instruction types
     address: number
     parsed_line: ParsedLine
+    words: number[]
+}
+"#;
+#[wasm_bindgen(typescript_custom_section)]
+pub const IMemorySegment: &'static str = r#"
+export type MemorySegment = {
+    address: number
+    data: number[]
+}
+"#;
+#[wasm_bindgen(typescript_custom_section)]
+pub const IMemoryRegion: &'static str = r#"
+export type MemoryRegion = {
+    address: number
+    length: number
+    fill: number
+}
+"#;
+#[wasm_bindgen(typescript_custom_section)]
+pub const IDisassembledLine: &'static str = r#"
+export type DisassembledLine = {
+    address: number
+    words: number[]
+    instruction: any | null
+    text: string
+}
+"#;
+#[wasm_bindgen(typescript_custom_section)]
+pub const ISymbol: &'static str = r#"
+export type Symbol = {
+    name: string
+    value: number | null
+    expression: string | null
+    line: number
+    kind: "Code" | "Data" | "Constant"
+}
+"#;
+#[wasm_bindgen(typescript_custom_section)]
+pub const IListing: &'static str = r#"
+export type ListingLine = {
+    line_index: number
+    address: number | null
+    bytes: number[]
+    source: string
+}
+export type ListingSymbol = {
+    name: string
+    value: number | null
+    expression: string | null
+    kind: "Code" | "Data" | "Constant"
+    line_index: number
+    references: number[]
+}
+export type Listing = {
+    lines: ListingLine[]
+    symbols: ListingSymbol[]
 }
 "#;
 #[wasm_bindgen(typescript_custom_section)]
@@ -148,7 +215,11 @@ export type LexedLine = {
 pub const IInterpreterOptions: &'static str = r#"
 export type InterpreterOptions = {
     keep_history: boolean
-    history_size: number    
+    history_size: number
+    exception_processing?: boolean
+    cpu_model?: "M68000" | "M68010" | "M68020"
+    fpu_model?: "None" | "M68881" | "M68882"
+    execution_mode?: "Precompiled" | "FetchDecode"
 }
 "#;
 #[wasm_bindgen(typescript_custom_section)]
@@ -199,6 +270,39 @@ export type MutationOperation = {
         to: number,
         from: number,
     }
+} | {
+    type: "WriteStatusRegister",
+    value: {
+        old: number
+    }
+} | {
+    type: "WriteShadowStackPointer",
+    value: {
+        old: number
+    }
+} | {
+    type: "WriteControlRegister",
+    value: {
+        register: "SFC" | "DFC" | "USP" | "VBR",
+        old: number
+    }
+} | {
+    type: "WriteFpRegister",
+    value: {
+        register: number,
+        old: number
+    }
+} | {
+    type: "WriteFpControlRegister",
+    value: {
+        register: "FPCR" | "FPSR" | "FPIAR",
+        old: number
+    }
+} | {
+    type: "WriteInterpreterStatus",
+    value: {
+        old: "Running" | "Interrupt" | "Terminated" | "TerminatedWithException" | "Stopped"
+    }
 }
 "#;
 #[wasm_bindgen(typescript_custom_section)]
@@ -206,6 +310,12 @@ pub const ILexedOperand: &'static str = r#"
 export type LexedOperand = {
     type: "Register",
     value: [type: LexedRegisterType, name: string]
+} | {
+    type: "FloatRegister",
+    value: string
+} | {
+    type: "SpecialRegister",
+    value: LexedSpecialRegister
 } | {
     type: "PreIndirect",
     value: LexedOperand
@@ -218,6 +328,34 @@ export type LexedOperand = {
 } | {
     type: "Absolute",
     value: string
+} | {
+    type: "AbsoluteWithSize",
+    value: [address: string, size: LexedSize]
+} | {
+    type: "RegisterPair",
+    value: [high: LexedOperand, low: LexedOperand]
+} | {
+    type: "ScaledIndex",
+    value: {
+        register: LexedOperand,
+        scale: string,
+    }
+} | {
+    type: "MemoryIndirect",
+    value: {
+        base_displacement: string,
+        base: LexedOperand | null,
+        index: LexedOperand | null,
+        pre_indexed: boolean,
+        outer_displacement: string,
+    }
+} | {
+    type: "BitField",
+    value: {
+        operand: LexedOperand,
+        offset: LexedOperand,
+        width: LexedOperand,
+    }
 } | {
     type: "Label",
     value: string
@@ -236,6 +374,17 @@ export type LexedOperand = {
         offset: String,
         operands: LexedOperand[]
     }
+} | {
+    type: "PCDisplacement",
+    value: {
+        offset: String
+    }
+} | {
+    type: "PCIndex",
+    value: {
+        offset: String,
+        index: LexedOperand
+    }
 }
 "#;
 #[wasm_bindgen(typescript_custom_section)]
@@ -245,3 +394,7 @@ export enum LexedRegisterType {
     LexedAddress = "Address",
 }
 "#;
+#[wasm_bindgen(typescript_custom_section)]
+pub const ILexedSpecialRegister: &'static str = r#"
+export type LexedSpecialRegister = "SR" | "CCR" | "USP" | "PC" | "FPCR" | "FPSR" | "FPIAR" | "SFC" | "DFC" | "VBR"
+"#;
@@ -3,7 +3,10 @@ use std::{collections::HashMap, str::FromStr};
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::instructions::Label;
+use crate::{
+    instructions::{FpFormat, Label},
+    math::sign_extend_to_long,
+};
 
 pub fn num_to_signed_base(num: i64, base: i64) -> Result<i64, &'static str> {
     let bound = 1i64 << (base - 1);
@@ -213,6 +216,48 @@ pub fn parse_absolute_expression(
     Ok(result)
 }
 
+/**
+Parses the value of a floating point constant, a decimal number or expression is read as the value itself,
+while a number written in another base is the raw bit pattern of the single or double precision float
+ */
+pub fn parse_float_expression(
+    str: &str,
+    format: FpFormat,
+    labels: &HashMap<String, Label>,
+) -> Result<f64, String> {
+    let str = str.trim();
+    if let Some(size) = format.to_integer_size() {
+        let value = parse_absolute_expression(str, labels)?;
+        return Ok(sign_extend_to_long(value as u32, size) as f64);
+    }
+    let value = if !str.starts_with(['$', '%', '@']) {
+        match str.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => parse_absolute_expression(str, labels)? as f64,
+        }
+    } else {
+        let bits = match str.strip_prefix('$') {
+            Some(hex) => u64::from_str_radix(hex, 16)
+                .map_err(|e| format!("Invalid hexadecimal number: {}, {}", str, e))?,
+            None => parse_absolute_expression(str, labels)? as u64,
+        };
+        match format {
+            FpFormat::Single => f32::from_bits(bits as u32) as f64,
+            FpFormat::Double => f64::from_bits(bits),
+            _ => {
+                return Err(format!(
+                    "Extended precision constants can't be written as raw bits: {}",
+                    str
+                ))
+            }
+        }
+    };
+    match format {
+        FpFormat::Single => Ok(value as f32 as f64),
+        _ => Ok(value),
+    }
+}
+
 pub fn parse_string_into_padded_bytes(str: &str, chunk_size: usize) -> Vec<u8> {
     //TODO to decide if i should use utf-8 or ascii
     let mut bytes = str.as_bytes().to_vec(); //full utf-8 bytes
@@ -226,6 +271,19 @@ pub fn parse_string_into_padded_bytes(str: &str, chunk_size: usize) -> Vec<u8> {
     bytes
 }
 
+/**
+Parses pairs of hexadecimal digits into bytes, None if a digit is invalid or the last byte is incomplete
+ */
+pub fn parse_hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
 pub fn parse_string_into_u32_chunks(str: &str, align_left: bool) -> Vec<u32> {
     let mut chunks = str.as_bytes().chunks_exact(4);
     let mut result: Vec<u32> = chunks
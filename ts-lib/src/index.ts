@@ -2,6 +2,9 @@ import {
     Compiler as RawCompiler,
     Condition,
     Cpu as RawCpu,
+    CpuModel,
+    DisassembledLine,
+    FpuModel,
     ExecutionStep,
     Flags,
     InstructionLine,
@@ -11,10 +14,15 @@ import {
     Interrupt,
     InterruptResult,
     Label,
+    Listing,
+    ListingLine,
+    ListingSymbol,
     StackFrame,
     LexedLine,
     LexedOperand,
     LexedRegisterType,
+    MemoryRegion,
+    MemorySegment,
     MutationOperation,
     ParsedLine,
     Register as RawRegister,
@@ -22,8 +30,11 @@ import {
     RuntimeError,
     S68k as RawS68k,
     SemanticError as RawSemanticError,
+    RecordError,
+    SRecordFormat,
     Size,
-    Step
+    Step,
+    Symbol
 } from './pkg/s68k'
 
 export type CompilationResult = { ok: false, errors: SemanticError[] } | { ok: true, interpreter: Interpreter }
@@ -77,6 +88,46 @@ export class Cpu {
     getRegisterValue(register: number, type: RegisterType): number {
         return this.getRegister(register, type).getLong()
     }
+
+    getStatusRegister(): number {
+        return this.cpu.wasm_get_sr()
+    }
+
+    getUserStackPointer(): number {
+        return this.cpu.wasm_get_usp()
+    }
+
+    getSupervisorStackPointer(): number {
+        return this.cpu.wasm_get_ssp()
+    }
+
+    getVectorBaseRegister(): number {
+        return this.cpu.wasm_get_vbr()
+    }
+
+    getSourceFunctionCode(): number {
+        return this.cpu.wasm_get_sfc()
+    }
+
+    getDestinationFunctionCode(): number {
+        return this.cpu.wasm_get_dfc()
+    }
+
+    getFloatingPointRegisters(): number[] {
+        return Array.from(this.cpu.wasm_get_fp_regs_value())
+    }
+
+    getFloatingPointControlRegister(): number {
+        return this.cpu.wasm_get_fpcr()
+    }
+
+    getFloatingPointStatusRegister(): number {
+        return this.cpu.wasm_get_fpsr()
+    }
+
+    getFloatingPointInstructionAddress(): number {
+        return this.cpu.wasm_get_fpiar()
+    }
 }
 
 export type InterruptHandler = (interrupt: Interrupt) => Promise<InterruptResult> | void
@@ -89,10 +140,22 @@ export class Interpreter {
         this.interpreter = interpreter
     }
 
+    static fromSRecords(text: string, options: InterpreterOptions): Interpreter {
+        return new Interpreter(RawInterpreter.wasm_from_srecords(text, options))
+    }
+
     answerInterrupt(interruptResult: InterruptResult) {
         this.interpreter.wasm_answer_interrupt(interruptResult)
     }
 
+    raiseInterrupt(level: number): boolean {
+        return this.interpreter.wasm_raise_interrupt(level)
+    }
+
+    getResetSignals(): number {
+        return this.interpreter.wasm_get_reset_signals()
+    }
+
     step(): Step {
         return this.interpreter.wasm_step()
     }
@@ -154,10 +217,30 @@ export class Interpreter {
         return this.interpreter.wasm_get_flags_as_number()
     }
 
+    getStatusRegister(): number {
+        return this.interpreter.wasm_get_sr()
+    }
+
     readMemoryBytes(address: number, length: number): Uint8Array {
         return this.interpreter.wasm_read_memory_bytes(address, length)
     }
 
+    dumpBinary(region: MemoryRegion): Uint8Array {
+        return this.interpreter.wasm_dump_binary(region)
+    }
+
+    dumpIntelHex(region: MemoryRegion): string {
+        return this.interpreter.wasm_dump_intel_hex(region)
+    }
+
+    loadBinary(bytes: Uint8Array, region: MemoryRegion) {
+        this.interpreter.wasm_load_binary(bytes, region)
+    }
+
+    loadIntelHex(text: string, region?: MemoryRegion) {
+        this.interpreter.wasm_load_intel_hex(text, region ?? null)
+    }
+
     getFlag(flag: Flags): boolean {
         return this.interpreter.wasm_get_flag(flag)
     }
@@ -182,6 +265,10 @@ export class Interpreter {
         return this.interpreter.wasm_get_instruction_at(address) as InstructionLine | null
     }
 
+    disassemble(address: number, length: number): DisassembledLine[] {
+        return this.interpreter.wasm_disassemble(address, length) as DisassembledLine[]
+    }
+
     getStatus(): InterpreterStatus {
         return this.interpreter.wasm_get_status()
     }
@@ -255,6 +342,10 @@ export class SemanticError {
     getError(): string {
         return this.error.wasm_get_error()
     }
+
+    isWarning(): boolean {
+        return this.error.wasm_is_warning()
+    }
 }
 
 export class CompiledProgram {
@@ -267,29 +358,53 @@ export class CompiledProgram {
     getCompiledProgram(): RawCompiler {
         return this.program
     }
+
+    getMemorySegments(): MemorySegment[] {
+        return this.program.wasm_get_memory_segments() as MemorySegment[]
+    }
+
+    toSRecords(format: SRecordFormat): string {
+        return this.program.wasm_to_srecords(format)
+    }
+
+    getSymbols(): Symbol[] {
+        return this.program.wasm_get_symbols() as Symbol[]
+    }
+
+    getSymbolMap(): string {
+        return this.program.wasm_get_symbol_map()
+    }
 }
 
 export class S68k {
     private _s68k: RawS68k
 
-    constructor(code: string) {
+    constructor(code: string, cpuModel?: CpuModel, fpuModel?: FpuModel) {
         this._s68k = new RawS68k(code)
+        if (cpuModel !== undefined) this._s68k.wasm_set_cpu_model(cpuModel)
+        if (fpuModel !== undefined) this._s68k.wasm_set_fpu_model(fpuModel)
     }
 
-    static compile(code: string, options?: InterpreterOptions): CompilationResult {
-        const s68k = new S68k(code)
+    static compile(code: string, options?: InterpreterOptions, cpuModel?: CpuModel, fpuModel?: FpuModel): CompilationResult {
+        const s68k = new S68k(code, cpuModel, fpuModel)
         const errors = s68k.semanticCheck()
         if (errors.length > 0) return {errors, ok: false}
         options = options ?? {
             history_size: 100,
             keep_history: true,
         }
+        //the interpreter runs the same model the code was checked against, unless specified
+        options = {
+            ...options,
+            cpu_model: options.cpu_model ?? CpuModel[s68k.getCpuModel()] as InterpreterOptions["cpu_model"],
+            fpu_model: options.fpu_model ?? FpuModel[s68k.getFpuModel()] as InterpreterOptions["fpu_model"],
+        }
         const interpreter = s68k.createInterpreter(options)
         return {interpreter, ok: true}
     }
 
-    static semanticCheck(code: string): SemanticError[] {
-        let s68k = new S68k(code)
+    static semanticCheck(code: string, cpuModel?: CpuModel, fpuModel?: FpuModel): SemanticError[] {
+        let s68k = new S68k(code, cpuModel, fpuModel)
         return s68k.semanticCheck()
     }
 
@@ -302,6 +417,14 @@ export class S68k {
         return S68k.lex(code)[0]
     }
 
+    getCpuModel(): CpuModel {
+        return this._s68k.wasm_get_cpu_model()
+    }
+
+    getFpuModel(): FpuModel {
+        return this._s68k.wasm_get_fpu_model()
+    }
+
     getLexedLines(): ParsedLine[] {
         return this._s68k.wasm_get_lexed_lines()
     }
@@ -315,10 +438,29 @@ export class S68k {
         return errors
     }
 
+    semanticWarnings(): SemanticError[] {
+        const warningWrapper = this._s68k.wasm_semantic_warnings()
+        const warnings: SemanticError[] = []
+        for (let i = 0; i < warningWrapper.get_length(); i++) {
+            warnings.push(new SemanticError(warningWrapper.get_error_at_index(i)))
+        }
+        return warnings
+    }
+
     compile(): CompiledProgram {
         return new CompiledProgram(this._s68k.wasm_compile())
     }
 
+    createListing(program?: CompiledProgram): Listing {
+        const compiled = program ?? this.compile()
+        return this._s68k.wasm_create_listing(compiled.getCompiledProgram())
+    }
+
+    createListingText(program?: CompiledProgram): string {
+        const compiled = program ?? this.compile()
+        return this._s68k.wasm_create_listing_text(compiled.getCompiledProgram())
+    }
+
     createInterpreter(options: InterpreterOptions, program?: CompiledProgram): Interpreter {
         if (program) {
             return new Interpreter(this._s68k.wasm_create_interpreter(program.getCompiledProgram(), options))
@@ -401,6 +543,8 @@ export {
     InterpreterStatus,
     Size,
     Condition,
+    CpuModel,
+    FpuModel,
     Step,
     ParsedLine,
     LexedLine,
@@ -408,10 +552,19 @@ export {
     LexedRegisterType,
     RegisterOperand,
     InstructionLine,
+    MemorySegment,
+    MemoryRegion,
+    DisassembledLine,
+    SRecordFormat,
+    RecordError,
     ExecutionStep,
     MutationOperation,
     InterpreterOptions,
     RuntimeError,
     Label,
+    Listing,
+    ListingLine,
+    ListingSymbol,
     StackFrame,
+    Symbol,
 }